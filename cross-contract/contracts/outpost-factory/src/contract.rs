@@ -1,17 +1,15 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-// use cw2::set_contract_version;
+use cw2::set_contract_version;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{ContractState, STATE};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{self, ContractState, STATE};
 
-/*
 // version info for migration info
-const CONTRACT_NAME: &str = "crates.io:outpost-factory"; // just a placeholder, not yet published
-const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-*/
+pub const CONTRACT_NAME: &str = "crates.io:outpost-factory"; // just a placeholder, not yet published
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -26,13 +24,18 @@ pub fn instantiate(
     // only we can call the migration function below. 
     // ofcourse, The instantiator will set themselves as admin in the wasm.Instanstiate msg 
 
-    // This contract cannot have an owner because it needs to be called by all users to create and map their outposts
+    // The instantiator becomes the cw_ownable owner, i.e. the factory admin. Ownership only gates admin functions:
+    // every user still calls this contract to create and map their outposts
     // We have a check below which ensures that users cannot call 'map' twice 
 
-    STATE.save(
-        deps.storage,
-        &ContractState::new(msg.storage_outpost_code_id, info.sender.to_string()),
-    )?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    cw_ownable::initialize_owner(deps.storage, deps.api, Some(info.sender.as_str()))?;
+
+    let mut state = ContractState::new(msg.storage_outpost_code_id, info.sender.to_string());
+    if let Some(max_outposts_per_user) = msg.max_outposts_per_user {
+        state.max_outposts_per_user = max_outposts_per_user;
+    }
+    STATE.save(deps.storage, &state)?;
     Ok(Response::default())
 }
 
@@ -46,22 +49,91 @@ pub fn execute(
     match msg {
         ExecuteMsg::CreateOutpost {
             channel_open_init_options,
-        } => execute::create_outpost(deps, env, info, channel_open_init_options),
-        ExecuteMsg::MapUserOutpost { outpost_owner} => execute::map_user_outpost(deps, env, info, outpost_owner),
-        ExecuteMsg::MigrateOutpost { outpost_owner, new_outpost_code_id } => execute::migrate_outpost(deps, env, info, outpost_owner, new_outpost_code_id),
+            connection_template,
+            outpost_name,
+            invite_code,
+        } => execute::create_outpost(deps, env, info, channel_open_init_options, connection_template, outpost_name, invite_code),
+        ExecuteMsg::MapUserOutpost { outpost_owner, outpost_name } => execute::map_user_outpost(deps, env, info, outpost_owner, outpost_name),
+        ExecuteMsg::MigrateOutpost { outpost_owner, new_outpost_code_id, outpost_name } => {
+            execute::migrate_outpost(deps, info, outpost_owner, new_outpost_code_id, outpost_name)
+        },
+        ExecuteMsg::PublishOutpostVersion { version, code_id, release_notes, set_as_default } => {
+            execute::publish_outpost_version(deps, env, info, version, code_id, release_notes, set_as_default.unwrap_or_default())
+        },
+        ExecuteMsg::DeprecateOutpostVersion { version } => execute::deprecate_outpost_version(deps, info, version),
+        ExecuteMsg::UpgradeMyOutpost { version, outpost_name } => execute::upgrade_my_outpost(deps, info, version, outpost_name),
+        ExecuteMsg::OutpostChannelOpened { ica_address } => execute::outpost_channel_opened(deps, env, info, ica_address),
+        ExecuteMsg::OutpostChannelClosed {} => execute::outpost_channel_closed(deps, env, info),
+        ExecuteMsg::OutpostDecommissioned {} => execute::outpost_decommissioned(deps, env, info),
+        ExecuteMsg::RetryChannel { outpost_name } => execute::retry_channel(deps, info, outpost_name),
+        ExecuteMsg::SetCreationPolicy { policy } => execute::set_creation_policy(deps, info, policy),
+        ExecuteMsg::UpdateAllowlist { add, remove } => execute::update_allowlist(deps, info, add, remove),
+        ExecuteMsg::AddInviteCodes { code_hashes } => execute::add_invite_codes(deps, info, code_hashes),
+        ExecuteMsg::SetCreationDeposit { deposit } => execute::set_creation_deposit(deps, info, deposit),
+        ExecuteMsg::SetConnectionTemplate { name, options } => execute::set_connection_template(deps, info, name, options),
+        ExecuteMsg::RemoveConnectionTemplate { name } => execute::remove_connection_template(deps, info, name),
+        ExecuteMsg::UpdateConfig { storage_outpost_code_id, max_outposts_per_user } => {
+            execute::update_config(deps, info, storage_outpost_code_id, max_outposts_per_user)
+        },
+        ExecuteMsg::UpdateOwnership(action) => execute::update_ownership(deps, env, info, action),
     }
 }
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetContractState {} => to_json_binary(&query::state(deps)?),
-        QueryMsg::GetUserOutpostAddress { user_address } => to_json_binary(&query::user_outpost_address(deps, user_address)?),
+        QueryMsg::GetUserOutpostAddress { user_address, outpost_name } => to_json_binary(&query::user_outpost_address(deps, user_address, outpost_name)?),
+        QueryMsg::GetUserOutposts { user_address } => to_json_binary(&query::user_outposts(deps, user_address)?),
         QueryMsg::GetAllUserOutpostAddresses {  } => to_json_binary(&query::get_all_user_outpost_addresses(deps)?),
+        QueryMsg::GetOutpostOwner { outpost_address } => to_json_binary(&query::outpost_owner(deps, outpost_address)?),
+        QueryMsg::GetDirectoryEntryByUser { user_address, outpost_name } => {
+            to_json_binary(&query::directory_entry_by_user(deps, user_address, outpost_name)?)
+        },
+        QueryMsg::GetDirectoryEntryByIca { ica_address } => to_json_binary(&query::directory_entry_by_ica(deps, ica_address)?),
+        QueryMsg::GetOutpostStatus { outpost_address } => to_json_binary(&query::outpost_status(deps, outpost_address)?),
+        QueryMsg::GetCreationConfig {} => to_json_binary(&query::creation_config(deps)?),
+        QueryMsg::IsAllowlisted { address } => to_json_binary(&query::is_allowlisted(deps, address)?),
+        QueryMsg::GetDeposit { user_address, outpost_name } => to_json_binary(&query::deposit(deps, user_address, outpost_name)?),
+        QueryMsg::GetConnectionTemplate { name } => to_json_binary(&query::connection_template(deps, name)?),
+        QueryMsg::GetConnectionTemplates {} => to_json_binary(&query::connection_templates(deps)?),
+        QueryMsg::GetOutpostVersion { version } => to_json_binary(&query::outpost_version(deps, version)?),
+        QueryMsg::GetOutpostVersions {} => to_json_binary(&query::outpost_versions(deps)?),
+        QueryMsg::Ownership {} => to_json_binary(&cw_ownable::get_ownership(deps.storage)?),
+    }
+}
+
+/// Migrate contract if version is lower than current version
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let prev_version = migrate::validate_semver(deps.as_ref())?;
+
+    let mut response = Response::new()
+        .add_attribute("previous_version", prev_version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION);
+
+    // State migration hooks, run in order. Each hook only runs if the contract is older than the release that introduced it
+    if prev_version < semver::Version::new(0, 2, 0) {
+        let migrated = migrate::name_user_outposts(deps.branch())?;
+        response = response.add_attribute("migrated_user_outposts", migrated.to_string());
+
+        let backfilled = migrate::backfill_outpost_statuses(deps.branch(), &env)?;
+        response = response.add_attribute("backfilled_outpost_statuses", backfilled.to_string());
+    }
+
+    // Factories instantiated before cw_ownable was added only have 'ContractState.admin'
+    if cw_ownable::get_ownership(deps.storage).ok().and_then(|ownership| ownership.owner).is_none() {
+        let state = STATE.load(deps.storage)?;
+        cw_ownable::initialize_owner(deps.storage, deps.api, Some(&state.admin))?;
+        response = response.add_attribute("initialized_owner", state.admin);
     }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(response)
 }
 
 mod execute {
-    use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Uint128, Event, to_json_binary};
+    use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Empty, Uint128, Event, to_json_binary};
     use storage_outpost::outpost_helpers::StorageOutpostContract;
     use storage_outpost::types::msg::ExecuteMsg as IcaControllerExecuteMsg;
     use storage_outpost::types::msg::MigrateMsg;
@@ -72,32 +144,72 @@ mod execute {
     };
     use storage_outpost::types::callback::Callback;
     use serde_json_wasm::from_str;
+    use storage_outpost::ibc::types::{keys::HOST_PORT_ID, metadata::TxEncoding};
+    use cosmwasm_std::IbcOrder;
 
-    use crate::state::{self, USER_ADDR_TO_OUTPOST_ADDR, LOCK};
+    use crate::msg::{Cw20BalanceResponse, Cw20QueryMsg, Cw721QueryMsg, Cw721TokensResponse};
+    use crate::state::{
+        self, CreationPolicy, OutpostLifecycle, OutpostStatus, OutpostVersion, USER_OUTPOSTS, OUTPOST_ADDR_TO_USER_ADDR,
+        OUTPOST_ADDR_TO_NAME, LOCK, OUTPOST_STATUS, OUTPOST_VERSIONS, CODE_ID_TO_VERSION, ICA_ADDR_TO_OUTPOST_ADDR,
+        ALLOWLIST, CREATION_DEPOSIT, CREATION_POLICY, DEPOSITS, INVITE_CODES, CONNECTION_TEMPLATES,
+    };
 
     use super::*;
     pub fn create_outpost(
-        deps: DepsMut,
+        mut deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        channel_open_init_options: ChannelOpenInitOptions,
+        channel_open_init_options: Option<ChannelOpenInitOptions>,
+        connection_template: Option<String>,
+        outpost_name: Option<String>,
+        invite_code: Option<String>,
     ) -> Result<Response, ContractError> {
         let state = STATE.load(deps.storage)?;
         // WARNING: This function is called by the user, so we cannot error:unauthorized if info.sender != admin 
 
         let storage_outpost_code_id = StorageOutpostCode::new(state.storage_outpost_code_id);
 
-        // Check if key already exists and disallow multiple outpost creations 
+        let outpost_name = outpost_name_or_default(outpost_name);
+        validate_outpost_name(&outpost_name)?;
+
+        let channel_open_init_options = resolve_channel_options(deps.as_ref(), channel_open_init_options, connection_template)?;
+
+        // Check if key already exists and disallow creating two outposts under the same name
         // If key exists, we don't care what the address is, just the mere existence of the key means an outpost was 
         // already created
             
-        if let Some(value) = USER_ADDR_TO_OUTPOST_ADDR.may_load(deps.storage, &info.sender.to_string())? {
+        if let Some(value) = USER_OUTPOSTS.may_load(deps.storage, (info.sender.as_str(), &outpost_name))? {
             return Err(ContractError::AlreadyCreated(value))
         }
 
+        let owned_outposts = USER_OUTPOSTS
+            .prefix(info.sender.as_str())
+            .keys(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .count();
+        if owned_outposts >= state.max_outposts_per_user as usize {
+            return Err(ContractError::TooManyOutposts { max: state.max_outposts_per_user })
+        }
+
+        check_creation_policy(deps.branch(), &info.sender, invite_code)?;
+
+        // The deposit is held until the outpost's channel opens, see 'outpost_channel_opened'
+        match CREATION_DEPOSIT.may_load(deps.storage)? {
+            Some(deposit) => {
+                if info.funds != [deposit.clone()] {
+                    return Err(ContractError::InvalidDeposit(deposit))
+                }
+                DEPOSITS.save(deps.storage, (info.sender.as_str(), &outpost_name), &deposit)?;
+            },
+            None => {
+                if !info.funds.is_empty() {
+                    return Err(ContractError::UnexpectedFunds {  })
+                }
+            },
+        }
+
         // Whoever calls this function will save a lock for themselves, which can only be used once. 
         // 'map_user_outpost' executed via callback from the instantiated outpost, can only run if this lock exists
-        let _lock = LOCK.save(deps.storage, &info.sender.to_string(), &true);
+        LOCK.save(deps.storage, (info.sender.as_str(), &outpost_name), &true)?;
 
         let callback = Callback {
             contract: env.contract.address.to_string(),
@@ -106,6 +218,8 @@ mod execute {
             msg: None, 
             // Even though this could be spoofed in 'map_user_outpost', that's ok because we have the lock to block
             outpost_owner: info.sender.to_string(),
+            outpost_name: Some(outpost_name.clone()),
+            on_channel_open_msg: None,
         };
 
         let instantiate_msg = storage_outpost::types::msg::InstantiateMsg {
@@ -117,7 +231,11 @@ mod execute {
         };
 
         let label
-         = format!("storage_outpost-owned by: {}", &info.sender.to_string());
+         = if outpost_name == state::DEFAULT_OUTPOST_NAME {
+            format!("storage_outpost-owned by: {}", &info.sender.to_string())
+        } else {
+            format!("storage_outpost-owned by: {} ({})", &info.sender.to_string(), outpost_name)
+        };
 
         // 'instantiate2' has the ability to pre compute the outpost's address
         // Unsure if 'instantiate2_address' from cosmwasm-std will work on Archway so we're not doing this for now
@@ -139,23 +257,33 @@ mod execute {
         env: Env,
         info: MessageInfo, //info.sender will be the outpost's address 
         outpost_owner: String, 
+        outpost_name: Option<String>,
     ) -> Result<Response, ContractError> {
         // this contract can't have an owner because it needs to be called back by every outpost it instantiates 
 
+        let outpost_name = outpost_name_or_default(outpost_name);
+
         // Load the lock state for the outpost owner
-        let lock = LOCK.may_load(deps.storage, &outpost_owner)?; 
+        let lock = LOCK.may_load(deps.storage, (&outpost_owner, &outpost_name))?; 
 
         // Check if the lock exists and is true
         if let Some(true) = lock {
             // If it does, overwrite it with false
-            LOCK.save(deps.storage, &outpost_owner, &false)?;
+            LOCK.save(deps.storage, (&outpost_owner, &outpost_name), &false)?;
         } else {
             // This function can only get called if the Lock was set in 'create_outpost'
             // If it doesn't exist or is false, return an unauthorized error
             return Err(ContractError::MissingLock {  })
         }
 
-    USER_ADDR_TO_OUTPOST_ADDR.save(deps.storage, &outpost_owner, &info.sender.to_string())?; // again, info.sender is actually the outpost address
+    USER_OUTPOSTS.save(deps.storage, (&outpost_owner, &outpost_name), &info.sender.to_string())?; // again, info.sender is actually the outpost address
+    OUTPOST_ADDR_TO_USER_ADDR.save(deps.storage, info.sender.as_str(), &outpost_owner)?;
+    OUTPOST_ADDR_TO_NAME.save(deps.storage, info.sender.as_str(), &outpost_name)?;
+    OUTPOST_STATUS.save(deps.storage, info.sender.as_str(), &OutpostStatus {
+        lifecycle: OutpostLifecycle::Instantiated,
+        ica_address: None,
+        updated_at: env.block.time,
+    })?;
 
     let mut event = Event::new("FACTORY:map_user_outpost");
     event = event.add_attribute("info.sender", &info.sender.to_string());
@@ -163,56 +291,635 @@ mod execute {
     Ok(Response::new().add_event(event)) // NOTE: this event is not propagated back up to the tx resp of the 'create_outpost' call
     }
 
-    pub fn migrate_outpost(
+    /// Called back by the outpost when its ICA channel handshake completes
+    pub fn outpost_channel_opened(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo, // info.sender is the outpost's address
+        ica_address: String,
+    ) -> Result<Response, ContractError> {
+        if !OUTPOST_ADDR_TO_USER_ADDR.has(deps.storage, info.sender.as_str()) {
+            return Err(ContractError::UnknownOutpost(info.sender.to_string()))
+        }
+
+        // ICA addresses are derived from the controller's port, so they can't be shared between outposts
+        if let Some(outpost_address) = ICA_ADDR_TO_OUTPOST_ADDR.may_load(deps.storage, &ica_address)? {
+            if outpost_address != info.sender {
+                return Err(ContractError::IcaAlreadyRegistered { ica_address, outpost_address })
+            }
+        }
+
+        // Reopening a channel on another connection gives the outpost a new ICA, drop the old directory entry
+        let previous_ica_address = OUTPOST_STATUS
+            .may_load(deps.storage, info.sender.as_str())?
+            .and_then(|status| status.ica_address);
+        if let Some(previous_ica_address) = previous_ica_address {
+            ICA_ADDR_TO_OUTPOST_ADDR.remove(deps.storage, &previous_ica_address);
+        }
+        ICA_ADDR_TO_OUTPOST_ADDR.save(deps.storage, &ica_address, &info.sender.to_string())?;
+
+        OUTPOST_STATUS.save(deps.storage, info.sender.as_str(), &OutpostStatus {
+            lifecycle: OutpostLifecycle::ChannelOpen,
+            ica_address: Some(ica_address.clone()),
+            updated_at: env.block.time,
+        })?;
+
+        let mut event = Event::new("FACTORY:outpost_channel_opened")
+            .add_attribute("outpost_address", info.sender.to_string())
+            .add_attribute("ica_address", ica_address);
+        let mut response = Response::new();
+
+        // The outpost works, so the user gets their creation deposit back
+        let outpost_owner = OUTPOST_ADDR_TO_USER_ADDR.load(deps.storage, info.sender.as_str())?;
+        let outpost_name = OUTPOST_ADDR_TO_NAME.load(deps.storage, info.sender.as_str())?;
+        if let Some(deposit) = DEPOSITS.may_load(deps.storage, (&outpost_owner, &outpost_name))? {
+            DEPOSITS.remove(deps.storage, (&outpost_owner, &outpost_name));
+            event = event.add_attribute("refunded_deposit", deposit.to_string());
+            response = response.add_message(BankMsg::Send { to_address: outpost_owner, amount: vec![deposit] });
+        }
+
+        Ok(response.add_event(event))
+    }
+
+    /// Called back by the outpost when its ICA channel closes. The ICA address is kept since reopening
+    /// a channel on the same connection recovers the same account
+    pub fn outpost_channel_closed(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo, // info.sender is the outpost's address
+    ) -> Result<Response, ContractError> {
+        if !OUTPOST_ADDR_TO_USER_ADDR.has(deps.storage, info.sender.as_str()) {
+            return Err(ContractError::UnknownOutpost(info.sender.to_string()))
+        }
+
+        let ica_address = OUTPOST_STATUS
+            .may_load(deps.storage, info.sender.as_str())?
+            .and_then(|status| status.ica_address);
+
+        OUTPOST_STATUS.save(deps.storage, info.sender.as_str(), &OutpostStatus {
+            lifecycle: OutpostLifecycle::ChannelClosed,
+            ica_address,
+            updated_at: env.block.time,
+        })?;
+
+        let event = Event::new("FACTORY:outpost_channel_closed")
+            .add_attribute("outpost_address", info.sender.to_string());
+
+        Ok(Response::new().add_event(event))
+    }
+
+    /// Called back by the outpost once its owner decommissioned it. The outpost is unregistered, which frees
+    /// its name for a new outpost, and only its final status is kept
+    pub fn outpost_decommissioned(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo, // info.sender is the outpost's address
+    ) -> Result<Response, ContractError> {
+        let outpost_owner = OUTPOST_ADDR_TO_USER_ADDR
+            .may_load(deps.storage, info.sender.as_str())?
+            .ok_or_else(|| ContractError::UnknownOutpost(info.sender.to_string()))?;
+        let outpost_name = OUTPOST_ADDR_TO_NAME.load(deps.storage, info.sender.as_str())?;
+
+        USER_OUTPOSTS.remove(deps.storage, (&outpost_owner, &outpost_name));
+        OUTPOST_ADDR_TO_USER_ADDR.remove(deps.storage, info.sender.as_str());
+        OUTPOST_ADDR_TO_NAME.remove(deps.storage, info.sender.as_str());
+        LOCK.remove(deps.storage, (&outpost_owner, &outpost_name));
+
+        let ica_address = OUTPOST_STATUS
+            .may_load(deps.storage, info.sender.as_str())?
+            .and_then(|status| status.ica_address);
+        if let Some(ica_address) = &ica_address {
+            ICA_ADDR_TO_OUTPOST_ADDR.remove(deps.storage, ica_address);
+        }
+        OUTPOST_STATUS.save(deps.storage, info.sender.as_str(), &OutpostStatus {
+            lifecycle: OutpostLifecycle::Decommissioned,
+            ica_address,
+            updated_at: env.block.time,
+        })?;
+
+        let event = Event::new("FACTORY:outpost_decommissioned")
+            .add_attribute("outpost_address", info.sender.to_string())
+            .add_attribute("outpost_owner", outpost_owner.clone());
+        let mut response = Response::new();
+
+        // The channel never opened, so the deposit wasn't refunded yet
+        if let Some(deposit) = DEPOSITS.may_load(deps.storage, (&outpost_owner, &outpost_name))? {
+            DEPOSITS.remove(deps.storage, (&outpost_owner, &outpost_name));
+            response = response.add_message(BankMsg::Send { to_address: outpost_owner, amount: vec![deposit] });
+        }
+
+        Ok(response.add_event(event))
+    }
+
+    /// Asks the sender's outpost to open a new channel with the options it was created with.
+    /// The factory is the outpost's admin, which is allowed to retry the handshake but not to change its options
+    pub fn retry_channel(
+        deps: DepsMut,
+        info: MessageInfo,
+        outpost_name: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let outpost_address = load_user_outpost(deps.as_ref(), info.sender.as_str(), outpost_name)?;
+
+        if let Some(status) = OUTPOST_STATUS.may_load(deps.storage, &outpost_address)? {
+            if status.lifecycle == OutpostLifecycle::ChannelOpen {
+                return Err(ContractError::ChannelAlreadyOpen(outpost_address))
+            }
+        }
+
+        let outpost = StorageOutpostContract::new(deps.api.addr_validate(&outpost_address)?);
+        let cosmos_msg = outpost.call(IcaControllerExecuteMsg::CreateChannel {
+            channel_open_init_options: None,
+        })?;
+
+        let event = Event::new("FACTORY:retry_channel")
+            .add_attribute("info.sender", info.sender.to_string())
+            .add_attribute("outpost_address", outpost_address);
+
+        Ok(Response::new().add_message(cosmos_msg).add_event(event))
+    }
+
+    pub fn set_connection_template(
         deps: DepsMut,
+        info: MessageInfo,
+        name: String,
+        options: ChannelOpenInitOptions,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        if !is_valid_name(&name) {
+            return Err(ContractError::InvalidConnectionTemplate(format!("invalid name '{name}'")))
+        }
+        if options.connection_id.is_empty() || options.counterparty_connection_id.is_empty() {
+            return Err(ContractError::InvalidConnectionTemplate("connection ids cannot be empty".to_string()))
+        }
+        CONNECTION_TEMPLATES.save(deps.storage, &name, &options)?;
+
+        let event = Event::new("FACTORY:set_connection_template")
+            .add_attribute("name", name)
+            .add_attribute("connection_id", options.connection_id)
+            .add_attribute("counterparty_connection_id", options.counterparty_connection_id);
+
+        Ok(Response::new().add_event(event))
+    }
+
+    pub fn remove_connection_template(
+        deps: DepsMut,
+        info: MessageInfo,
+        name: String,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        if !CONNECTION_TEMPLATES.has(deps.storage, &name) {
+            return Err(ContractError::UnknownConnectionTemplate(name))
+        }
+        CONNECTION_TEMPLATES.remove(deps.storage, &name);
+
+        let event = Event::new("FACTORY:remove_connection_template").add_attribute("name", name);
+
+        Ok(Response::new().add_event(event))
+    }
+
+    /// Returns the options the outpost's channel should be opened with.
+    /// Raw options are only trusted if they point at a registered connection, or if the admin hasn't registered any.
+    /// The matching template's options are used then, so its encoding and ordering can't be bypassed.
+    fn resolve_channel_options(
+        deps: Deps,
+        channel_open_init_options: Option<ChannelOpenInitOptions>,
+        connection_template: Option<String>,
+    ) -> Result<ChannelOpenInitOptions, ContractError> {
+        match (channel_open_init_options, connection_template) {
+            (None, Some(name)) => CONNECTION_TEMPLATES
+                .may_load(deps.storage, &name)?
+                .ok_or(ContractError::UnknownConnectionTemplate(name)),
+            (Some(options), None) => {
+                let templates = CONNECTION_TEMPLATES
+                    .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+                    .collect::<StdResult<Vec<_>>>()?;
+                if templates.is_empty() {
+                    return Ok(options)
+                }
+                let mut same_connection = templates
+                    .into_iter()
+                    .map(|(_, template)| template)
+                    .filter(|template| {
+                        template.connection_id == options.connection_id
+                            && template.counterparty_connection_id == options.counterparty_connection_id
+                    })
+                    .peekable();
+                if same_connection.peek().is_none() {
+                    return Err(ContractError::UnknownConnection {
+                        connection_id: options.connection_id,
+                        counterparty_connection_id: options.counterparty_connection_id,
+                    })
+                }
+                // Options left out are taken from the template, the ones given must agree with it
+                same_connection
+                    .find(|template| {
+                        agrees(&options.tx_encoding, &template.tx_encoding, TxEncoding::Protobuf)
+                            && agrees(&options.channel_ordering, &template.channel_ordering, IbcOrder::Ordered)
+                            && agrees(&options.counterparty_port_id, &template.counterparty_port_id, HOST_PORT_ID.to_string())
+                    })
+                    .ok_or(ContractError::ChannelOptionsMismatch {
+                        connection_id: options.connection_id,
+                    })
+            },
+            _ => Err(ContractError::InvalidChannelOptions {  }),
+        }
+    }
+
+    /// Returns true if the requested option is left out or equals the template's, defaults included.
+    fn agrees<T: PartialEq>(requested: &Option<T>, template: &Option<T>, default: T) -> bool {
+        requested.as_ref().is_none_or(|requested| requested == template.as_ref().unwrap_or(&default))
+    }
+
+    pub fn update_config(
+        deps: DepsMut,
+        info: MessageInfo,
+        storage_outpost_code_id: Option<u64>,
+        max_outposts_per_user: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let mut state = STATE.load(deps.storage)?;
+
+        let mut event = Event::new("FACTORY:update_config");
+        if let Some(storage_outpost_code_id) = storage_outpost_code_id {
+            state.storage_outpost_code_id = storage_outpost_code_id;
+            event = event.add_attribute("storage_outpost_code_id", storage_outpost_code_id.to_string());
+        }
+        if let Some(max_outposts_per_user) = max_outposts_per_user {
+            state.max_outposts_per_user = max_outposts_per_user;
+            event = event.add_attribute("max_outposts_per_user", max_outposts_per_user.to_string());
+        }
+        STATE.save(deps.storage, &state)?;
+
+        Ok(Response::new().add_event(event))
+    }
+
+    pub fn update_ownership(
+        mut deps: DepsMut,
         env: Env,
         info: MessageInfo,
-        outpost_owner: String,
-        new_outpost_code_id: String,
+        action: cw_ownable::Action,
     ) -> Result<Response, ContractError> {
+        // Admin only functions would be locked forever, including migrating outposts
+        if action == cw_ownable::Action::RenounceOwnership {
+            return Err(ContractError::CannotRenounceOwnership {  })
+        }
+
+        let ownership = cw_ownable::update_ownership(deps.branch(), &env.block, &info.sender, action)?;
 
+        // Accepting a transfer is what changes the owner, keep the legacy admin field in step with it
         let mut state = STATE.load(deps.storage)?;
+        if let Some(owner) = &ownership.owner {
+            if state.admin != owner.as_str() {
+                state.admin = owner.to_string();
+                STATE.save(deps.storage, &state)?;
+            }
+        }
+
+        let event = Event::new("FACTORY:update_ownership").add_attributes(ownership.into_attributes());
+
+        Ok(Response::new().add_event(event))
+    }
+
+    pub fn set_creation_policy(
+        deps: DepsMut,
+        info: MessageInfo,
+        policy: CreationPolicy,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        match &policy {
+            CreationPolicy::Cw721Holder { contract } => {
+                deps.api.addr_validate(contract)?;
+            },
+            CreationPolicy::Cw20Holder { contract, min_balance } => {
+                deps.api.addr_validate(contract)?;
+                if min_balance.is_zero() {
+                    return Err(ContractError::InvalidCreationPolicy("cw20 min_balance must be greater than zero".to_string()))
+                }
+            },
+            CreationPolicy::Open | CreationPolicy::Allowlist | CreationPolicy::InviteCode => {},
+        }
+        CREATION_POLICY.save(deps.storage, &policy)?;
+
+        let event = Event::new("FACTORY:set_creation_policy")
+            .add_attribute("policy", to_json_binary(&policy)?.to_string());
 
-        if info.sender.to_string() != state.admin {
-            return Err(ContractError::NotAdmin {  })
+        Ok(Response::new().add_event(event))
+    }
+
+    pub fn update_allowlist(
+        deps: DepsMut,
+        info: MessageInfo,
+        add: Vec<String>,
+        remove: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        for address in &add {
+            let address = deps.api.addr_validate(address)?;
+            ALLOWLIST.save(deps.storage, address.as_str(), &Empty {})?;
+        }
+        for address in &remove {
+            ALLOWLIST.remove(deps.storage, address);
+        }
+
+        let event = Event::new("FACTORY:update_allowlist")
+            .add_attribute("added", add.len().to_string())
+            .add_attribute("removed", remove.len().to_string());
+
+        Ok(Response::new().add_event(event))
+    }
+
+    pub fn add_invite_codes(
+        deps: DepsMut,
+        info: MessageInfo,
+        code_hashes: Vec<String>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        for code_hash in &code_hashes {
+            // Must look like the output of 'hash_and_hex', otherwise the code could never be redeemed
+            let is_sha256_hex = code_hash.len() == 64
+                && code_hash.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c));
+            if !is_sha256_hex {
+                return Err(ContractError::InvalidInviteCodeHash(code_hash.clone()))
+            }
+            INVITE_CODES.save(deps.storage, code_hash, &Empty {})?;
         }
 
+        let event = Event::new("FACTORY:add_invite_codes")
+            .add_attribute("added", code_hashes.len().to_string());
+
+        Ok(Response::new().add_event(event))
+    }
+
+    pub fn set_creation_deposit(
+        deps: DepsMut,
+        info: MessageInfo,
+        deposit: Option<Coin>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        let mut event = Event::new("FACTORY:set_creation_deposit");
+        match deposit {
+            Some(deposit) if !deposit.amount.is_zero() => {
+                event = event.add_attribute("deposit", deposit.to_string());
+                CREATION_DEPOSIT.save(deps.storage, &deposit)?;
+            },
+            // A zero deposit is no deposit at all
+            _ => {
+                event = event.add_attribute("deposit", "none");
+                CREATION_DEPOSIT.remove(deps.storage);
+            },
+        }
+
+        Ok(Response::new().add_event(event))
+    }
+
+    /// Errors if the sender isn't allowed to create outposts under the current creation policy.
+    /// Invite codes are used up here, so this must only run when the outpost is actually created
+    fn check_creation_policy(deps: DepsMut, sender: &Addr, invite_code: Option<String>) -> Result<(), ContractError> {
+        let policy = CREATION_POLICY.may_load(deps.storage)?.unwrap_or(CreationPolicy::Open);
+
+        match policy {
+            CreationPolicy::Open => {},
+            CreationPolicy::Allowlist => {
+                if !ALLOWLIST.has(deps.storage, sender.as_str()) {
+                    return Err(ContractError::NotAllowlisted(sender.to_string()))
+                }
+            },
+            CreationPolicy::InviteCode => {
+                let invite_code = invite_code.ok_or(ContractError::InvalidInviteCode {  })?;
+                let code_hash = storage_outpost::helpers::filetree_helpers::hash_and_hex(&invite_code);
+                if !INVITE_CODES.has(deps.storage, &code_hash) {
+                    return Err(ContractError::InvalidInviteCode {  })
+                }
+                INVITE_CODES.remove(deps.storage, &code_hash);
+            },
+            CreationPolicy::Cw721Holder { contract } => {
+                let response: Cw721TokensResponse = deps.querier.query_wasm_smart(&contract, &Cw721QueryMsg::Tokens {
+                    owner: sender.to_string(),
+                    start_after: None,
+                    limit: Some(1),
+                })?;
+                if response.tokens.is_empty() {
+                    return Err(ContractError::NotATokenHolder(contract))
+                }
+            },
+            CreationPolicy::Cw20Holder { contract, min_balance } => {
+                let response: Cw20BalanceResponse = deps.querier.query_wasm_smart(&contract, &Cw20QueryMsg::Balance {
+                    address: sender.to_string(),
+                })?;
+                if response.balance < min_balance {
+                    return Err(ContractError::NotATokenHolder(contract))
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    pub fn migrate_outpost(
+        deps: DepsMut,
+        info: MessageInfo,
+        outpost_owner: String,
+        new_outpost_code_id: String,
+        outpost_name: Option<String>,
+    ) -> Result<Response, ContractError> {
+
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
         // Find the owner's outpost address
-        let outpost_address = USER_ADDR_TO_OUTPOST_ADDR.load(deps.storage, &outpost_owner)?;
+        let outpost_address = load_user_outpost(deps.as_ref(), &outpost_owner, outpost_name)?;
+
+        let new_outpost_code_id = new_outpost_code_id
+            .parse::<u64>()
+            .map_err(|_| ContractError::InvalidCodeId(new_outpost_code_id.clone()))?;
 
-        let error_msg: String = String::from("Outpost contract address is not a valid bech32 address. Conversion back to addr failed");
+        // The admin is held to the same registry as users, only approved releases are ever migrated to
+        let version = CODE_ID_TO_VERSION
+            .may_load(deps.storage, new_outpost_code_id)?
+            .ok_or(ContractError::UnpublishedCodeId(new_outpost_code_id))?;
+        if OUTPOST_VERSIONS.load(deps.storage, &version)?.deprecated {
+            return Err(ContractError::VersionDeprecated(version))
+        }
 
         // Call the outpost's helper API 
-        let storage_outpost_code = StorageOutpostContract::new(deps.api.addr_validate(&outpost_address).expect(&error_msg));
+        let storage_outpost_code = StorageOutpostContract::new(deps.api.addr_validate(&outpost_address)?);
 
         // The outpost's migrate entry point is just '{}'
         let migrate_msg = MigrateMsg {};
 
-        let cast_err: String = String::from("Could not cast new outpost code to u64");
-        let new_outpost_code_id_u64 = new_outpost_code_id.parse::<u64>().expect(&cast_err);
-
         let cosmos_msg = storage_outpost_code.migrate(
             migrate_msg,
-            new_outpost_code_id_u64,
+            new_outpost_code_id,
         )?;
 
-        let mut event = Event::new("Migration: success");
+        let event = Event::new("Migration: success")
+            .add_attribute("outpost_address", outpost_address)
+            .add_attribute("to_code_id", new_outpost_code_id.to_string())
+            .add_attribute("version", version);
+
+        // NOTE: This used to overwrite 'storage_outpost_code_id' so that new outposts would use the migrated code.
+        // The default code id is now only changed explicitly by publishing a version with 'set_as_default'
+        
+        Ok(Response::new().add_message(cosmos_msg).add_event(event)) 
+    }
+
+    pub fn publish_outpost_version(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        version: String,
+        code_id: u64,
+        release_notes: String,
+        set_as_default: bool,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        let mut state = STATE.load(deps.storage)?;
 
-        // Optimistically make sure the factory knows the new code id of the outpost 
-        // A new code id will trigger many migrations, so we only need to save it once
-        if state.storage_outpost_code_id != new_outpost_code_id_u64 {
+        // Store the normalized label so that lookups don't depend on how the admin typed it
+        let version = semver::Version::parse(&version)?.to_string();
 
-            state.storage_outpost_code_id = new_outpost_code_id_u64;
+        if OUTPOST_VERSIONS.has(deps.storage, &version) {
+            return Err(ContractError::VersionAlreadyPublished(version))
+        }
+        if let Some(published) = CODE_ID_TO_VERSION.may_load(deps.storage, code_id)? {
+            return Err(ContractError::CodeIdAlreadyPublished { code_id, version: published })
+        }
+
+        let outpost_version = OutpostVersion {
+            version: version.clone(),
+            code_id,
+            release_notes,
+            deprecated: false,
+            published_at: env.block.time,
+        };
+        OUTPOST_VERSIONS.save(deps.storage, &version, &outpost_version)?;
+        CODE_ID_TO_VERSION.save(deps.storage, code_id, &version)?;
+
+        if set_as_default {
+            state.storage_outpost_code_id = code_id;
             STATE.save(deps.storage, &state)?;
+        }
+
+        let event = Event::new("FACTORY:publish_outpost_version")
+            .add_attribute("version", version)
+            .add_attribute("code_id", code_id.to_string())
+            .add_attribute("set_as_default", set_as_default.to_string());
+
+        Ok(Response::new().add_event(event))
+    }
+
+    pub fn deprecate_outpost_version(
+        deps: DepsMut,
+        info: MessageInfo,
+        version: String,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+
+        let version = semver::Version::parse(&version)?.to_string();
+
+        OUTPOST_VERSIONS.update(deps.storage, &version, |entry| -> Result<_, ContractError> {
+            let mut entry = entry.ok_or_else(|| ContractError::UnknownVersion(version.clone()))?;
+            entry.deprecated = true;
+            Ok(entry)
+        })?;
+
+        let event = Event::new("FACTORY:deprecate_outpost_version")
+            .add_attribute("version", version);
 
+        Ok(Response::new().add_event(event))
+    }
+
+    pub fn upgrade_my_outpost(
+        deps: DepsMut,
+        info: MessageInfo,
+        version: String,
+        outpost_name: Option<String>,
+    ) -> Result<Response, ContractError> {
+        // WARNING: This function is called by the user, so the outpost to upgrade is always looked up from info.sender.
+        // Users can only ever migrate an outpost they own, and only to a version the admin has approved
+        let outpost_address = load_user_outpost(deps.as_ref(), info.sender.as_str(), outpost_name)?;
+
+        let requested_version = semver::Version::parse(&version)?;
+        let target = OUTPOST_VERSIONS
+            .may_load(deps.storage, &requested_version.to_string())?
+            .ok_or_else(|| ContractError::UnknownVersion(requested_version.to_string()))?;
+        if target.deprecated {
+            return Err(ContractError::VersionDeprecated(target.version))
         }
-        
-        Ok(Response::new().add_message(cosmos_msg).add_event(event)) 
+
+        let current_code_id = deps.querier.query_wasm_contract_info(&outpost_address)?.code_id;
+        if current_code_id == target.code_id {
+            return Err(ContractError::AlreadyOnVersion(target.version))
+        }
+
+        // Outposts created before the registry existed may run an unpublished code id, in which case we can't
+        // compare versions and simply let the owner move onto an approved release
+        if let Some(current_version) = CODE_ID_TO_VERSION.may_load(deps.storage, current_code_id)? {
+            if semver::Version::parse(&current_version)? > requested_version {
+                return Err(ContractError::DowngradeNotAllowed {
+                    current: current_version,
+                    requested: target.version,
+                })
+            }
+        }
+
+        let outpost = StorageOutpostContract::new(deps.api.addr_validate(&outpost_address)?);
+        let cosmos_msg = outpost.migrate(MigrateMsg {}, target.code_id)?;
+
+        let event = Event::new("FACTORY:upgrade_my_outpost")
+            .add_attribute("outpost_owner", info.sender.to_string())
+            .add_attribute("outpost_address", outpost_address)
+            .add_attribute("from_code_id", current_code_id.to_string())
+            .add_attribute("to_code_id", target.code_id.to_string())
+            .add_attribute("version", target.version);
+
+        Ok(Response::new().add_message(cosmos_msg).add_event(event))
     }
 }
 
+/// Outposts created without a name, and queries that don't name one, use the default outpost
+fn outpost_name_or_default(outpost_name: Option<String>) -> String {
+    outpost_name.unwrap_or_else(|| state::DEFAULT_OUTPOST_NAME.to_string())
+}
+
+/// Outpost names end up in contract labels and query keys, so keep them short and plain
+fn validate_outpost_name(outpost_name: &str) -> Result<(), ContractError> {
+    if !is_valid_name(outpost_name) {
+        return Err(ContractError::InvalidOutpostName(outpost_name.to_string()))
+    }
+    Ok(())
+}
+
+/// 1 to 32 lowercase letters, digits, '-' or '_'
+fn is_valid_name(name: &str) -> bool {
+    let valid_char = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_';
+    !name.is_empty() && name.len() <= 32 && name.chars().all(valid_char)
+}
+
+/// Returns the address of the user's outpost with the given name, or of their default outpost
+fn load_user_outpost(deps: Deps, user_address: &str, outpost_name: Option<String>) -> Result<String, ContractError> {
+    let outpost_name = outpost_name_or_default(outpost_name);
+    state::USER_OUTPOSTS
+        .may_load(deps.storage, (user_address, &outpost_name))?
+        .ok_or_else(|| ContractError::NoOutpost { user: user_address.to_string(), name: outpost_name })
+}
+
 mod query {
-    use crate::state::{USER_ADDR_TO_OUTPOST_ADDR};
+    use cosmwasm_std::StdError;
+
+    use cosmwasm_std::Coin;
+
+    use crate::msg::{ConnectionTemplate, CreationConfig, DirectoryEntry};
+    use crate::state::{
+        CreationPolicy, OutpostStatus, OutpostVersion, USER_OUTPOSTS, OUTPOST_ADDR_TO_USER_ADDR, OUTPOST_ADDR_TO_NAME,
+        OUTPOST_STATUS, OUTPOST_VERSIONS, ICA_ADDR_TO_OUTPOST_ADDR, ALLOWLIST, CREATION_DEPOSIT, CREATION_POLICY, DEPOSITS,
+        CONNECTION_TEMPLATES,
+    };
 
     use super::*;
 
@@ -221,33 +928,1198 @@ mod query {
         STATE.load(deps.storage)
     }
 
-    /// Returns the outpost address this user owns
-    pub fn user_outpost_address(deps: Deps, user_address: String) -> StdResult<String> {
-        USER_ADDR_TO_OUTPOST_ADDR.load(deps.storage, &user_address)
+    pub fn connection_template(deps: Deps, name: String) -> StdResult<ConnectionTemplate> {
+        let options = CONNECTION_TEMPLATES.load(deps.storage, &name)?;
+        Ok(ConnectionTemplate { name, options })
+    }
+
+    pub fn connection_templates(deps: Deps) -> StdResult<Vec<ConnectionTemplate>> {
+        CONNECTION_TEMPLATES
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .map(|item| item.map(|(name, options)| ConnectionTemplate { name, options }))
+            .collect()
+    }
+
+    /// Returns who may create outposts and the deposit they must make
+    pub fn creation_config(deps: Deps) -> StdResult<CreationConfig> {
+        Ok(CreationConfig {
+            policy: CREATION_POLICY.may_load(deps.storage)?.unwrap_or(CreationPolicy::Open),
+            deposit: CREATION_DEPOSIT.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn is_allowlisted(deps: Deps, address: String) -> StdResult<bool> {
+        Ok(ALLOWLIST.has(deps.storage, &address))
+    }
+
+    /// Returns the deposit held for the user's outpost, if its channel hasn't opened yet
+    pub fn deposit(deps: Deps, user_address: String, outpost_name: Option<String>) -> StdResult<Option<Coin>> {
+        DEPOSITS.may_load(deps.storage, (&user_address, &outpost_name_or_default(outpost_name)))
+    }
+
+    /// Returns the address of the user's outpost with this name, or of their default outpost
+    pub fn user_outpost_address(deps: Deps, user_address: String, outpost_name: Option<String>) -> StdResult<String> {
+        USER_OUTPOSTS.load(deps.storage, (&user_address, &outpost_name_or_default(outpost_name)))
+    }
+
+    /// Returns the name and address of every outpost this user owns
+    pub fn user_outposts(deps: Deps, user_address: String) -> StdResult<Vec<(String, String)>> {
+        USER_OUTPOSTS
+            .prefix(&user_address)
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .collect()
     }
 
-    // Get every key value pair from the 'USER_ADDR_TO_OUTPOST_ADDR' map
+    // Get every user and outpost address pair from the 'USER_OUTPOSTS' map
     pub fn get_all_user_outpost_addresses(deps: Deps) -> StdResult<Vec<(String, String)>> {
         // Create a vector to store all entries
         let mut all_entries = Vec::new();
     
         // Use the prefix_range function to iterate over all key-value pairs in the map
-        let pairs = USER_ADDR_TO_OUTPOST_ADDR
+        let pairs = USER_OUTPOSTS
             .range(deps.storage, None, None, cosmwasm_std::Order::Ascending);
     
-        // Collect each key-value pair
+        // Collect each key-value pair, dropping the outpost name
         for pair in pairs {
-            let (key, value) = pair?;
-            all_entries.push((key.to_string(), value));
+            let ((user_address, _outpost_name), outpost_address) = pair?;
+            all_entries.push((user_address, outpost_address));
         }
     
         Ok(all_entries)
     }
+
+    /// Returns the user address that owns this outpost
+    pub fn outpost_owner(deps: Deps, outpost_address: String) -> StdResult<String> {
+        OUTPOST_ADDR_TO_USER_ADDR.load(deps.storage, &outpost_address)
+    }
+
+    /// Resolves a user to their outpost and its ICA address.
+    /// Errors if the user has no outpost, or if its channel has never opened
+    pub fn directory_entry_by_user(deps: Deps, user_address: String, outpost_name: Option<String>) -> StdResult<DirectoryEntry> {
+        let outpost_name = outpost_name_or_default(outpost_name);
+        let outpost_address = USER_OUTPOSTS.load(deps.storage, (&user_address, &outpost_name))?;
+        let ica_address = OUTPOST_STATUS
+            .may_load(deps.storage, &outpost_address)?
+            .and_then(|status| status.ica_address)
+            .ok_or_else(|| StdError::generic_err(format!("outpost {outpost_address} has no ICA address yet")))?;
+
+        Ok(DirectoryEntry { user_address, outpost_name, outpost_address, ica_address })
+    }
+
+    /// Resolves an ICA address to the outpost controlling it and the outpost's owner
+    pub fn directory_entry_by_ica(deps: Deps, ica_address: String) -> StdResult<DirectoryEntry> {
+        let outpost_address = ICA_ADDR_TO_OUTPOST_ADDR.load(deps.storage, &ica_address)?;
+        let user_address = OUTPOST_ADDR_TO_USER_ADDR.load(deps.storage, &outpost_address)?;
+        let outpost_name = OUTPOST_ADDR_TO_NAME.load(deps.storage, &outpost_address)?;
+
+        Ok(DirectoryEntry { user_address, outpost_name, outpost_address, ica_address })
+    }
+
+    /// Returns the lifecycle of an outpost created by this factory.
+    /// Outposts mapped before 0.2.0 have a status only if they could be queried during the factory's migration
+    pub fn outpost_status(deps: Deps, outpost_address: String) -> StdResult<OutpostStatus> {
+        OUTPOST_STATUS.load(deps.storage, &outpost_address)
+    }
+
+    /// Returns a single entry of the outpost code id registry
+    pub fn outpost_version(deps: Deps, version: String) -> StdResult<OutpostVersion> {
+        let version = semver::Version::parse(&version)
+            .map_err(|err| cosmwasm_std::StdError::generic_err(err.to_string()))?;
+        OUTPOST_VERSIONS.load(deps.storage, &version.to_string())
+    }
+
+    /// Returns every published outpost version sorted by semver precedence.
+    /// Keys are ordered lexicographically in storage, so "0.10.0" would otherwise sort before "0.9.0"
+    pub fn outpost_versions(deps: Deps) -> StdResult<Vec<OutpostVersion>> {
+        let mut versions = OUTPOST_VERSIONS
+            .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+            .map(|entry| entry.map(|(_, version)| version))
+            .collect::<StdResult<Vec<_>>>()?;
+
+        versions.sort_by_cached_key(|entry| semver::Version::parse(&entry.version).ok());
+
+        Ok(versions)
+    }
+}
+
+mod migrate {
+    use cosmwasm_std::{Addr, Order};
+    use cw_storage_plus::Map;
+    use storage_outpost::outpost_helpers::StorageOutpostContract;
+
+    use crate::state::{
+        OutpostLifecycle, OutpostStatus, DEFAULT_OUTPOST_NAME, ICA_ADDR_TO_OUTPOST_ADDR, OUTPOST_ADDR_TO_NAME,
+        OUTPOST_ADDR_TO_USER_ADDR, OUTPOST_STATUS, USER_OUTPOSTS,
+    };
+
+    use super::*;
+
+    /// Before 0.2.0 users could only own one outpost, mapped from their address
+    pub const LEGACY_USER_ADDR_TO_OUTPOST_ADDR: Map<&str, String> = Map::new("user_addr_to_outpost_addr");
+
+    /// Before 0.2.0 locks were keyed by the user's address only
+    pub const LEGACY_LOCK: Map<&str, bool> = Map::new("lock");
+
+    /// Factories deployed before cw2 was wired up never recorded a version.
+    /// 0.1.0 is the only release that shipped without it
+    const UNVERSIONED_RELEASE: semver::Version = semver::Version::new(0, 1, 0);
+
+    /// Validate that the contract version is semver compliant
+    /// and greater than the previous version. Returns the previous version.
+    pub fn validate_semver(deps: Deps) -> Result<semver::Version, ContractError> {
+        let Some(prev_cw2_version) = cw2::CONTRACT.may_load(deps.storage)? else {
+            return Ok(UNVERSIONED_RELEASE)
+        };
+        if prev_cw2_version.contract != CONTRACT_NAME {
+            return Err(ContractError::InvalidMigrationVersion {
+                expected: CONTRACT_NAME.to_string(),
+                actual: prev_cw2_version.contract,
+            });
+        }
+
+        let version: semver::Version = CONTRACT_VERSION.parse()?;
+        let prev_version: semver::Version = prev_cw2_version.version.parse()?;
+        if prev_version >= version {
+            return Err(ContractError::InvalidMigrationVersion {
+                expected: format!("> {prev_version}"),
+                actual: CONTRACT_VERSION.to_string(),
+            });
+        }
+        Ok(prev_version)
+    }
+
+    /// 0.2.0 keys outposts by user and name. Existing outposts become their user's default outpost, and
+    /// the reverse lookups added in 0.2.0 are filled in. Returns the number of outposts moved
+    pub fn name_user_outposts(deps: DepsMut) -> Result<u64, ContractError> {
+        let pairs = LEGACY_USER_ADDR_TO_OUTPOST_ADDR
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        for (user_address, outpost_address) in &pairs {
+            USER_OUTPOSTS.save(deps.storage, (user_address, DEFAULT_OUTPOST_NAME), outpost_address)?;
+            OUTPOST_ADDR_TO_USER_ADDR.save(deps.storage, outpost_address, user_address)?;
+            OUTPOST_ADDR_TO_NAME.save(deps.storage, outpost_address, &DEFAULT_OUTPOST_NAME.to_string())?;
+            LEGACY_USER_ADDR_TO_OUTPOST_ADDR.remove(deps.storage, user_address);
+        }
+
+        // Locks only live for the duration of a 'create_outpost' transaction, so the old ones are all spent
+        LEGACY_LOCK.clear(deps.storage);
+
+        Ok(pairs.len() as u64)
+    }
+
+    /// 0.2.0 added 'OUTPOST_STATUS' and the ICA directory, which outposts fill in as their channel changes.
+    /// Outposts whose channel opened before then are asked for their state instead. Outposts that can't be
+    /// queried are skipped. Returns the number of statuses written
+    pub fn backfill_outpost_statuses(deps: DepsMut, env: &Env) -> Result<u64, ContractError> {
+        let outpost_addresses = OUTPOST_ADDR_TO_USER_ADDR
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut backfilled = 0;
+        for outpost_address in outpost_addresses {
+            if OUTPOST_STATUS.has(deps.storage, &outpost_address) {
+                continue
+            }
+
+            let outpost = StorageOutpostContract::new(Addr::unchecked(&outpost_address));
+            let Ok(outpost_state) = outpost.query_state(deps.querier) else {
+                continue
+            };
+
+            let status = match outpost_state.ica_info {
+                Some(ica_info) => {
+                    let channel_open = outpost
+                        .query_channel(deps.querier)
+                        .map(|channel| channel.is_open())
+                        .unwrap_or_default();
+                    ICA_ADDR_TO_OUTPOST_ADDR.save(deps.storage, &ica_info.ica_address, &outpost_address)?;
+                    OutpostStatus {
+                        lifecycle: if channel_open { OutpostLifecycle::ChannelOpen } else { OutpostLifecycle::ChannelClosed },
+                        ica_address: Some(ica_info.ica_address),
+                        updated_at: env.block.time,
+                    }
+                }
+                None => OutpostStatus {
+                    lifecycle: OutpostLifecycle::Instantiated,
+                    ica_address: None,
+                    updated_at: env.block.time,
+                },
+            };
+            OUTPOST_STATUS.save(deps.storage, &outpost_address, &status)?;
+            backfilled += 1;
+        }
+
+        Ok(backfilled)
+    }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use cosmwasm_std::{
+        coin, to_json_binary, Addr, Binary, Coin, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Uint128, WasmMsg,
+    };
+    use cw_multi_test::{App, AppResponse, Contract, ContractWrapper, Executor};
+    use cw_storage_plus::Item;
+    use storage_outpost::ibc::types::metadata::TxEncoding;
+    use storage_outpost::types::msg::{
+        options::ChannelOpenInitOptions, ExecuteMsg as OutpostExecuteMsg, InstantiateMsg as OutpostInstantiateMsg,
+        MigrateMsg as OutpostMigrateMsg, OutpostFactoryExecuteMsg, QueryMsg as OutpostQueryMsg,
+    };
+
+    use crate::msg::{
+        ConnectionTemplate, CreationConfig, Cw20BalanceResponse, Cw20QueryMsg, Cw721QueryMsg, Cw721TokensResponse, DirectoryEntry, ExecuteMsg,
+        InstantiateMsg, MigrateMsg, QueryMsg,
+    };
+    use crate::state::{
+        ContractState, CreationPolicy, OutpostLifecycle, OutpostStatus, OutpostVersion, OUTPOST_STATUS,
+    };
+    use crate::ContractError;
+
+    use super::migrate::{LEGACY_LOCK, LEGACY_USER_ADDR_TO_OUTPOST_ADDR};
+    use super::{CONTRACT_NAME, CONTRACT_VERSION};
+
+    const ADMIN: &str = "admin";
+    const ALICE: &str = "alice";
+
+    // Where the mock outpost sends its lifecycle notifications
+    const MOCK_CALLBACK_CONTRACT: Item<String> = Item::new("callback_contract");
+    // The options the mock outpost was instantiated with
+    const MOCK_CHANNEL_OPTIONS: Item<ChannelOpenInitOptions> = Item::new("channel_options");
+
+    fn channel_options(connection_id: &str) -> ChannelOpenInitOptions {
+        ChannelOpenInitOptions {
+            connection_id: connection_id.to_string(),
+            counterparty_connection_id: connection_id.to_string(),
+            counterparty_port_id: None,
+            tx_encoding: None,
+            channel_ordering: None,
+        }
+    }
+
+    // The real outpost's entry points are compiled out with 'no_exports', so we stand in a contract which
+    // behaves the same way towards the factory: it calls back 'MapUserOutpost' when it's instantiated
+    fn mock_outpost_instantiate(deps: DepsMut, _env: Env, _info: MessageInfo, msg: OutpostInstantiateMsg) -> StdResult<Response> {
+        let mut response = Response::new();
+        if let Some(options) = msg.channel_open_init_options {
+            MOCK_CHANNEL_OPTIONS.save(deps.storage, &options)?;
+        }
+        if let Some(callback) = msg.callback {
+            MOCK_CALLBACK_CONTRACT.save(deps.storage, &callback.contract)?;
+            response = response.add_message(callback.instantiate_msg()?);
+        }
+        Ok(response)
+    }
+
+    // Opening a channel completes the handshake straight away, as if a relayer were running
+    fn mock_outpost_execute(deps: DepsMut, env: Env, _info: MessageInfo, msg: OutpostExecuteMsg) -> StdResult<Response> {
+        match msg {
+            OutpostExecuteMsg::CreateChannel { .. } => Ok(Response::new().add_message(WasmMsg::Execute {
+                contract_addr: MOCK_CALLBACK_CONTRACT.load(deps.storage)?,
+                msg: to_json_binary(&OutpostFactoryExecuteMsg::OutpostChannelOpened {
+                    ica_address: format!("jkl1ica-{}", env.contract.address),
+                })?,
+                funds: vec![],
+            })),
+            _ => Ok(Response::new()),
+        }
+    }
+
+    fn mock_outpost_query(_deps: Deps, _env: Env, _msg: OutpostQueryMsg) -> StdResult<Binary> {
+        to_json_binary(&Empty {})
+    }
+
+    fn mock_outpost_migrate(_deps: DepsMut, _env: Env, _msg: OutpostMigrateMsg) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn mock_outpost() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(mock_outpost_execute, mock_outpost_instantiate, mock_outpost_query)
+            .with_migrate(mock_outpost_migrate);
+        Box::new(contract)
+    }
+
+    // Token contracts in which only Alice holds anything
+    fn mock_token_execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn mock_token_instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    fn mock_cw721_query(_deps: Deps, _env: Env, msg: Cw721QueryMsg) -> StdResult<Binary> {
+        let Cw721QueryMsg::Tokens { owner, .. } = msg;
+        let tokens = if owner == ALICE { vec!["1".to_string()] } else { vec![] };
+        to_json_binary(&Cw721TokensResponse { tokens })
+    }
+
+    fn mock_cw20_query(_deps: Deps, _env: Env, msg: Cw20QueryMsg) -> StdResult<Binary> {
+        let Cw20QueryMsg::Balance { address } = msg;
+        let balance = if address == ALICE { Uint128::new(100) } else { Uint128::zero() };
+        to_json_binary(&Cw20BalanceResponse { balance })
+    }
+
+    fn factory() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(super::execute, super::instantiate, super::query)
+            .with_migrate(super::migrate);
+        Box::new(contract)
+    }
+
+    // Stands in for a 0.1.0 factory: no cw2 version, one unnamed outpost per user and no reverse outpost map
+    fn legacy_instantiate(deps: DepsMut, _env: Env, info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+        let state = format!(r#"{{"storage_outpost_code_id":{},"admin":"{}"}}"#, msg.storage_outpost_code_id, info.sender);
+        deps.storage.set(b"state", state.as_bytes());
+        LEGACY_USER_ADDR_TO_OUTPOST_ADDR.save(deps.storage, ALICE, &"outpost-alice".to_string())?;
+        LEGACY_USER_ADDR_TO_OUTPOST_ADDR.save(deps.storage, "bob", &"outpost-bob".to_string())?;
+        LEGACY_LOCK.save(deps.storage, ALICE, &false)?;
+        Ok(Response::new())
+    }
+
+    fn legacy_factory() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(super::execute, legacy_instantiate, super::query);
+        Box::new(contract)
+    }
+
+    struct Suite {
+        app: App,
+        factory: Addr,
+        outpost_code_id: u64,
+    }
+
+    impl Suite {
+        fn new() -> Self {
+            let mut app = App::default();
+            let outpost_code_id = app.store_code(mock_outpost());
+            let factory_code_id = app.store_code(factory());
+
+            let factory = app
+                .instantiate_contract(
+                    factory_code_id,
+                    Addr::unchecked(ADMIN),
+                    &InstantiateMsg { storage_outpost_code_id: outpost_code_id, max_outposts_per_user: Some(3) },
+                    &[],
+                    "outpost factory",
+                    Some(ADMIN.to_string()),
+                )
+                .unwrap();
+
+            Self { app, factory, outpost_code_id }
+        }
+
+        fn execute(&mut self, sender: &str, msg: &ExecuteMsg) -> Result<AppResponse, ContractError> {
+            self.app
+                .execute_contract(Addr::unchecked(sender), self.factory.clone(), msg, &[])
+                .map_err(|err| err.downcast().unwrap())
+        }
+
+        fn query<T: serde::de::DeserializeOwned>(&self, msg: &QueryMsg) -> StdResult<T> {
+            self.app.wrap().query_wasm_smart(self.factory.clone(), msg)
+        }
+
+        fn create_outpost(&mut self, sender: &str) -> Addr {
+            self.create_named_outpost(sender, None).unwrap()
+        }
+
+        fn create_named_outpost(&mut self, sender: &str, outpost_name: Option<&str>) -> Result<Addr, ContractError> {
+            self.create_gated_outpost(sender, outpost_name, None, &[])
+        }
+
+        fn create_gated_outpost(
+            &mut self,
+            sender: &str,
+            outpost_name: Option<&str>,
+            invite_code: Option<&str>,
+            funds: &[Coin],
+        ) -> Result<Addr, ContractError> {
+            let msg = ExecuteMsg::CreateOutpost {
+                channel_open_init_options: Some(channel_options("connection-0")),
+                connection_template: None,
+                outpost_name: outpost_name.map(str::to_string),
+                invite_code: invite_code.map(str::to_string),
+            };
+            self.app
+                .execute_contract(Addr::unchecked(sender), self.factory.clone(), &msg, funds)
+                .map_err(|err| err.downcast::<ContractError>().unwrap())?;
+
+            Ok(self.user_outpost(sender, outpost_name))
+        }
+
+        fn create_outpost_on(
+            &mut self,
+            sender: &str,
+            channel_open_init_options: Option<ChannelOpenInitOptions>,
+            connection_template: Option<&str>,
+        ) -> Result<Addr, ContractError> {
+            self.execute(sender, &ExecuteMsg::CreateOutpost {
+                channel_open_init_options,
+                connection_template: connection_template.map(str::to_string),
+                outpost_name: None,
+                invite_code: None,
+            })?;
+
+            Ok(self.user_outpost(sender, None))
+        }
+
+        fn user_outpost(&self, user: &str, outpost_name: Option<&str>) -> Addr {
+            let outpost: String = self
+                .query(&QueryMsg::GetUserOutpostAddress {
+                    user_address: user.to_string(),
+                    outpost_name: outpost_name.map(str::to_string),
+                })
+                .unwrap();
+            Addr::unchecked(outpost)
+        }
+
+        fn store_outpost_code(&mut self) -> u64 {
+            self.app.store_code(mock_outpost())
+        }
+
+        fn publish(&mut self, version: &str, code_id: u64) -> Result<AppResponse, ContractError> {
+            self.execute(ADMIN, &ExecuteMsg::PublishOutpostVersion {
+                version: version.to_string(),
+                code_id,
+                release_notes: format!("release {version}"),
+                set_as_default: None,
+            })
+        }
+
+        fn outpost_code_id(&self, outpost: &Addr) -> u64 {
+            self.app.wrap().query_wasm_contract_info(outpost).unwrap().code_id
+        }
+    }
+
+    #[test]
+    fn only_admin_can_publish() {
+        let mut suite = Suite::new();
+        let code_id = suite.outpost_code_id;
+
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::PublishOutpostVersion {
+                version: "0.1.1".to_string(),
+                code_id,
+                release_notes: String::new(),
+                set_as_default: None,
+            })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)));
+
+        suite.publish("0.1.1", code_id).unwrap();
+        let err = suite.publish("0.1.1", code_id).unwrap_err();
+        assert!(matches!(err, ContractError::VersionAlreadyPublished(_)));
+
+        let err = suite.publish("not-semver", code_id).unwrap_err();
+        assert!(matches!(err, ContractError::SemverError(_)));
+    }
+
+    #[test]
+    fn versions_are_sorted_by_semver() {
+        let mut suite = Suite::new();
+        let first = suite.outpost_code_id;
+        let second = suite.store_outpost_code();
+        let third = suite.store_outpost_code();
+
+        suite.publish("0.10.0", third).unwrap();
+        suite.publish("0.2.0", second).unwrap();
+        suite.publish("0.1.1", first).unwrap();
+
+        let versions: Vec<OutpostVersion> = suite.query(&QueryMsg::GetOutpostVersions {}).unwrap();
+        let labels: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+        assert_eq!(labels, vec!["0.1.1", "0.2.0", "0.10.0"]);
+
+        let version: OutpostVersion = suite
+            .query(&QueryMsg::GetOutpostVersion { version: "0.2.0".to_string() })
+            .unwrap();
+        assert_eq!(version.code_id, second);
+        assert!(!version.deprecated);
+    }
+
+    #[test]
+    fn publishing_as_default_changes_new_outposts_code_id() {
+        let mut suite = Suite::new();
+        let new_code_id = suite.store_outpost_code();
+
+        suite
+            .execute(ADMIN, &ExecuteMsg::PublishOutpostVersion {
+                version: "0.2.0".to_string(),
+                code_id: new_code_id,
+                release_notes: String::new(),
+                set_as_default: Some(true),
+            })
+            .unwrap();
+
+        let state: ContractState = suite.query(&QueryMsg::GetContractState {}).unwrap();
+        assert_eq!(state.storage_outpost_code_id, new_code_id);
+
+        let outpost = suite.create_outpost(ALICE);
+        assert_eq!(suite.outpost_code_id(&outpost), new_code_id);
+    }
+
+    #[test]
+    fn owner_can_upgrade_their_outpost() {
+        let mut suite = Suite::new();
+        let old_code_id = suite.outpost_code_id;
+        let new_code_id = suite.store_outpost_code();
+        suite.publish("0.1.1", old_code_id).unwrap();
+        suite.publish("0.2.0", new_code_id).unwrap();
+
+        let outpost = suite.create_outpost(ALICE);
+        assert_eq!(suite.outpost_code_id(&outpost), old_code_id);
+
+        suite
+            .execute(ALICE, &ExecuteMsg::UpgradeMyOutpost { version: "0.2.0".to_string(), outpost_name: None })
+            .unwrap();
+        assert_eq!(suite.outpost_code_id(&outpost), new_code_id);
+
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::UpgradeMyOutpost { version: "0.2.0".to_string(), outpost_name: None })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyOnVersion(_)));
+
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::UpgradeMyOutpost { version: "0.1.1".to_string(), outpost_name: None })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::DowngradeNotAllowed { .. }));
+
+        // Upgrading never changes the code id used for new outposts
+        let state: ContractState = suite.query(&QueryMsg::GetContractState {}).unwrap();
+        assert_eq!(state.storage_outpost_code_id, old_code_id);
+    }
+
+    #[test]
+    fn cannot_upgrade_to_unknown_or_deprecated_versions() {
+        let mut suite = Suite::new();
+        let new_code_id = suite.store_outpost_code();
+        suite.publish("0.2.0", new_code_id).unwrap();
+
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::UpgradeMyOutpost { version: "0.2.0".to_string(), outpost_name: None })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NoOutpost { .. }));
 
+        suite.create_outpost(ALICE);
 
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::UpgradeMyOutpost { version: "0.3.0".to_string(), outpost_name: None })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownVersion(_)));
 
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::DeprecateOutpostVersion { version: "0.2.0".to_string() })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)));
 
+        suite
+            .execute(ADMIN, &ExecuteMsg::DeprecateOutpostVersion { version: "0.2.0".to_string() })
+            .unwrap();
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::UpgradeMyOutpost { version: "0.2.0".to_string(), outpost_name: None })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::VersionDeprecated(_)));
+    }
+
+    #[test]
+    fn admin_migration_keeps_default_code_id() {
+        let mut suite = Suite::new();
+        let old_code_id = suite.outpost_code_id;
+        let new_code_id = suite.store_outpost_code();
+        let outpost = suite.create_outpost(ALICE);
+        let migrate = |code_id: String| ExecuteMsg::MigrateOutpost {
+            outpost_owner: ALICE.to_string(),
+            new_outpost_code_id: code_id,
+            outpost_name: None,
+        };
+
+        // Only published, current releases can be migrated to
+        let err = suite.execute(ADMIN, &migrate("latest".to_string())).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCodeId(_)));
+        let err = suite.execute(ADMIN, &migrate(new_code_id.to_string())).unwrap_err();
+        assert!(matches!(err, ContractError::UnpublishedCodeId(code_id) if code_id == new_code_id));
+        suite.publish("0.2.0", new_code_id).unwrap();
+        suite
+            .execute(ADMIN, &ExecuteMsg::DeprecateOutpostVersion { version: "0.2.0".to_string() })
+            .unwrap();
+        let err = suite.execute(ADMIN, &migrate(new_code_id.to_string())).unwrap_err();
+        assert!(matches!(err, ContractError::VersionDeprecated(_)));
+
+        let new_code_id = suite.store_outpost_code();
+        suite.publish("0.3.0", new_code_id).unwrap();
+        suite.execute(ADMIN, &migrate(new_code_id.to_string())).unwrap();
+
+        assert_eq!(suite.outpost_code_id(&outpost), new_code_id);
+        let state: ContractState = suite.query(&QueryMsg::GetContractState {}).unwrap();
+        assert_eq!(state.storage_outpost_code_id, old_code_id);
+    }
+
+    #[test]
+    fn migrate_from_unversioned_factory() {
+        let mut app = App::default();
+        let admin = Addr::unchecked(ADMIN);
+        let legacy_code_id = app.store_code(legacy_factory());
+        let new_code_id = app.store_code(factory());
+
+        let contract = app
+            .instantiate_contract(
+                legacy_code_id,
+                admin.clone(),
+                &InstantiateMsg { storage_outpost_code_id: 1, max_outposts_per_user: None },
+                &[],
+                "legacy factory",
+                Some(ADMIN.to_string()),
+            )
+            .unwrap();
+        assert!(cw2::query_contract_info(&app.wrap(), contract.to_string()).is_err());
+
+        let response = app
+            .migrate_contract(admin.clone(), contract.clone(), &MigrateMsg {}, new_code_id)
+            .unwrap();
+        let wasm_event = response.events.iter().find(|event| event.ty == "wasm").unwrap();
+        assert!(wasm_event.attributes.iter().any(|attr| attr.key == "previous_version" && attr.value == "0.1.0"));
+        assert!(wasm_event.attributes.iter().any(|attr| attr.key == "migrated_user_outposts" && attr.value == "2"));
+
+        let owner: String = app
+            .wrap()
+            .query_wasm_smart(contract.clone(), &QueryMsg::GetOutpostOwner { outpost_address: "outpost-bob".to_string() })
+            .unwrap();
+        assert_eq!(owner, "bob");
+
+        // Untouched state is carried over as is, with the default outpost cap
+        let state: ContractState = app.wrap().query_wasm_smart(contract.clone(), &QueryMsg::GetContractState {}).unwrap();
+        assert_eq!(state, ContractState::new(1, ADMIN.to_string()));
+        let ownership: cw_ownable::Ownership<Addr> = app.wrap().query_wasm_smart(contract.clone(), &QueryMsg::Ownership {}).unwrap();
+        assert_eq!(ownership.owner, Some(admin.clone()));
+
+        // Existing outposts are their users' default outpost
+        let outpost: String = app
+            .wrap()
+            .query_wasm_smart(contract.clone(), &QueryMsg::GetUserOutpostAddress { user_address: ALICE.to_string(), outpost_name: None })
+            .unwrap();
+        assert_eq!(outpost, "outpost-alice");
+
+        let version = cw2::query_contract_info(&app.wrap(), contract.to_string()).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        // Migrating onto the same version is rejected
+        let err: ContractError = app
+            .migrate_contract(admin, contract, &MigrateMsg {}, new_code_id)
+            .unwrap_err()
+            .downcast()
+            .unwrap();
+        assert!(matches!(err, ContractError::InvalidMigrationVersion { .. }));
+    }
+
+    #[test]
+    fn mapping_records_outpost_owner() {
+        let mut suite = Suite::new();
+        let outpost = suite.create_outpost(ALICE);
+
+        let owner: String = suite
+            .query(&QueryMsg::GetOutpostOwner { outpost_address: outpost.to_string() })
+            .unwrap();
+        assert_eq!(owner, ALICE);
+    }
+
+    fn outpost_status(suite: &Suite, outpost: &Addr) -> OutpostStatus {
+        suite
+            .query(&QueryMsg::GetOutpostStatus { outpost_address: outpost.to_string() })
+            .unwrap()
+    }
+
+    #[test]
+    fn outposts_report_channel_lifecycle() {
+        let mut suite = Suite::new();
+        let outpost = suite.create_outpost(ALICE);
+        assert_eq!(outpost_status(&suite, &outpost).lifecycle, OutpostLifecycle::Instantiated);
+        assert_eq!(outpost_status(&suite, &outpost).ica_address, None);
+
+        suite
+            .execute(outpost.as_str(), &ExecuteMsg::OutpostChannelOpened { ica_address: "jkl1ica".to_string() })
+            .unwrap();
+        let status = outpost_status(&suite, &outpost);
+        assert_eq!(status.lifecycle, OutpostLifecycle::ChannelOpen);
+        assert_eq!(status.ica_address, Some("jkl1ica".to_string()));
+
+        suite.execute(outpost.as_str(), &ExecuteMsg::OutpostChannelClosed {}).unwrap();
+        let status = outpost_status(&suite, &outpost);
+        assert_eq!(status.lifecycle, OutpostLifecycle::ChannelClosed);
+        assert_eq!(status.ica_address, Some("jkl1ica".to_string()));
+    }
+
+    #[test]
+    fn only_outposts_can_report_channel_lifecycle() {
+        let mut suite = Suite::new();
+        suite.create_outpost(ALICE);
+
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::OutpostChannelOpened { ica_address: "jkl1ica".to_string() })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownOutpost(_)));
+
+        let err = suite.execute(ALICE, &ExecuteMsg::OutpostChannelClosed {}).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownOutpost(_)));
+    }
+
+    #[test]
+    fn owner_can_retry_channel_until_open() {
+        let mut suite = Suite::new();
+
+        let err = suite.execute(ALICE, &ExecuteMsg::RetryChannel { outpost_name: None }).unwrap_err();
+        assert!(matches!(err, ContractError::NoOutpost { .. }));
+
+        // The handshake never completed, so the outpost is still only instantiated
+        let outpost = suite.create_outpost(ALICE);
+        suite.execute(ALICE, &ExecuteMsg::RetryChannel { outpost_name: None }).unwrap();
+
+        let status = outpost_status(&suite, &outpost);
+        assert_eq!(status.lifecycle, OutpostLifecycle::ChannelOpen);
+        assert_eq!(status.ica_address, Some(format!("jkl1ica-{outpost}")));
+
+        let err = suite.execute(ALICE, &ExecuteMsg::RetryChannel { outpost_name: None }).unwrap_err();
+        assert!(matches!(err, ContractError::ChannelAlreadyOpen(_)));
+
+        // A closed channel can be reopened
+        suite.execute(outpost.as_str(), &ExecuteMsg::OutpostChannelClosed {}).unwrap();
+        suite.execute(ALICE, &ExecuteMsg::RetryChannel { outpost_name: None }).unwrap();
+        assert_eq!(outpost_status(&suite, &outpost).lifecycle, OutpostLifecycle::ChannelOpen);
+    }
+
+    #[test]
+    fn directory_resolves_users_and_icas() {
+        let mut suite = Suite::new();
+        let outpost = suite.create_outpost(ALICE);
+
+        // No ICA until the channel opens
+        let err = suite
+            .query::<DirectoryEntry>(&QueryMsg::GetDirectoryEntryByUser { user_address: ALICE.to_string(), outpost_name: None })
+            .unwrap_err();
+        assert!(err.to_string().contains("has no ICA address yet"));
+
+        suite
+            .execute(outpost.as_str(), &ExecuteMsg::OutpostChannelOpened { ica_address: "jkl1alice".to_string() })
+            .unwrap();
+
+        let expected = DirectoryEntry {
+            user_address: ALICE.to_string(),
+            outpost_name: "default".to_string(),
+            outpost_address: outpost.to_string(),
+            ica_address: "jkl1alice".to_string(),
+        };
+        let entry: DirectoryEntry = suite
+            .query(&QueryMsg::GetDirectoryEntryByUser { user_address: ALICE.to_string(), outpost_name: None })
+            .unwrap();
+        assert_eq!(entry, expected);
+        let entry: DirectoryEntry = suite
+            .query(&QueryMsg::GetDirectoryEntryByIca { ica_address: "jkl1alice".to_string() })
+            .unwrap();
+        assert_eq!(entry, expected);
+
+        // A new ICA replaces the old directory entry
+        suite
+            .execute(outpost.as_str(), &ExecuteMsg::OutpostChannelOpened { ica_address: "jkl1alice2".to_string() })
+            .unwrap();
+        assert!(suite
+            .query::<DirectoryEntry>(&QueryMsg::GetDirectoryEntryByIca { ica_address: "jkl1alice".to_string() })
+            .is_err());
+        let entry: DirectoryEntry = suite
+            .query(&QueryMsg::GetDirectoryEntryByIca { ica_address: "jkl1alice2".to_string() })
+            .unwrap();
+        assert_eq!(entry.user_address, ALICE);
+    }
+
+    #[test]
+    fn outposts_cannot_claim_another_outposts_ica() {
+        let mut suite = Suite::new();
+        let alice_outpost = suite.create_outpost(ALICE);
+        let bob_outpost = suite.create_outpost("bob");
+
+        suite
+            .execute(alice_outpost.as_str(), &ExecuteMsg::OutpostChannelOpened { ica_address: "jkl1alice".to_string() })
+            .unwrap();
+        let err = suite
+            .execute(bob_outpost.as_str(), &ExecuteMsg::OutpostChannelOpened { ica_address: "jkl1alice".to_string() })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::IcaAlreadyRegistered { .. }));
+    }
+
+    #[test]
+    fn migration_backfills_directory_from_outposts() {
+        use cosmwasm_std::testing::{mock_dependencies, mock_env};
+        use cosmwasm_std::{ContractResult, SystemError, SystemResult, WasmQuery};
+
+        let mut deps = mock_dependencies();
+        for (user, outpost) in [(ALICE, "outpost-alice"), ("bob", "outpost-bob"), ("carol", "outpost-carol")] {
+            LEGACY_USER_ADDR_TO_OUTPOST_ADDR.save(deps.as_mut().storage, user, &outpost.to_string()).unwrap();
+        }
+        super::migrate::name_user_outposts(deps.as_mut()).unwrap();
+        // Alice's channel is open, Bob's handshake never completed and Carol's outpost can't be queried
+        deps.querier.update_wasm(|query| {
+            let WasmQuery::Smart { contract_addr, msg } = query else {
+                return SystemResult::Err(SystemError::Unknown {});
+            };
+            let response = match (contract_addr.as_str(), cosmwasm_std::from_json(msg).unwrap()) {
+                ("outpost-alice", OutpostQueryMsg::GetContractState {}) => {
+                    r#"{"admin":"factory","ica_info":{"ica_address":"jkl1alice","channel_id":"channel-1","encoding":"proto3"}}"#
+                }
+                ("outpost-alice", OutpostQueryMsg::GetChannel {}) => {
+                    r#"{"channel":{"endpoint":{"port_id":"wasm.outpost-alice","channel_id":"channel-1"},"counterparty_endpoint":{"port_id":"icahost","channel_id":"channel-9"},"order":"ORDER_ORDERED","version":"ics27-1","connection_id":"connection-0"},"channel_status":"STATE_OPEN"}"#
+                }
+                ("outpost-bob", OutpostQueryMsg::GetContractState {}) => r#"{"admin":"factory"}"#,
+                _ => return SystemResult::Err(SystemError::NoSuchContract { addr: contract_addr.clone() }),
+            };
+            SystemResult::Ok(ContractResult::Ok(Binary::from(response.as_bytes())))
+        });
+
+        let backfilled = super::migrate::backfill_outpost_statuses(deps.as_mut(), &mock_env()).unwrap();
+        assert_eq!(backfilled, 2);
+
+        let alice = OUTPOST_STATUS.load(&deps.storage, "outpost-alice").unwrap();
+        assert_eq!(alice.lifecycle, OutpostLifecycle::ChannelOpen);
+        let entry = super::query::directory_entry_by_ica(deps.as_ref(), "jkl1alice".to_string()).unwrap();
+        assert_eq!(entry.user_address, ALICE);
+
+        let bob = OUTPOST_STATUS.load(&deps.storage, "outpost-bob").unwrap();
+        assert_eq!(bob.lifecycle, OutpostLifecycle::Instantiated);
+        assert!(!OUTPOST_STATUS.has(&deps.storage, "outpost-carol"));
+    }
+
+    #[test]
+    fn users_can_own_several_named_outposts() {
+        let mut suite = Suite::new();
+        let default = suite.create_outpost(ALICE);
+        let work = suite.create_named_outpost(ALICE, Some("work")).unwrap();
+        assert_ne!(default, work);
+
+        let err = suite.create_named_outpost(ALICE, Some("work")).unwrap_err();
+        assert!(matches!(err, ContractError::AlreadyCreated(_)));
+        let err = suite.create_named_outpost(ALICE, Some("Not Valid")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidOutpostName(_)));
+
+        // The suite allows three outposts per user
+        suite.create_named_outpost(ALICE, Some("app-1")).unwrap();
+        let err = suite.create_named_outpost(ALICE, Some("app-2")).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyOutposts { max: 3 }));
+        suite.create_named_outpost("bob", Some("app-2")).unwrap();
+
+        let outposts: Vec<(String, String)> = suite
+            .query(&QueryMsg::GetUserOutposts { user_address: ALICE.to_string() })
+            .unwrap();
+        let names: Vec<&str> = outposts.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["app-1", "default", "work"]);
+
+        // Named outposts report to the directory like the default one
+        suite
+            .execute(work.as_str(), &ExecuteMsg::OutpostChannelOpened { ica_address: "jkl1work".to_string() })
+            .unwrap();
+        let entry: DirectoryEntry = suite
+            .query(&QueryMsg::GetDirectoryEntryByIca { ica_address: "jkl1work".to_string() })
+            .unwrap();
+        assert_eq!(entry.outpost_name, "work");
+        assert_eq!(entry.user_address, ALICE);
+    }
+
+    #[test]
+    fn only_admin_can_change_outpost_cap() {
+        let mut suite = Suite::new();
+
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::UpdateConfig { storage_outpost_code_id: None, max_outposts_per_user: Some(10) })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)));
+
+        suite.create_outpost(ALICE);
+        suite
+            .execute(ADMIN, &ExecuteMsg::UpdateConfig { storage_outpost_code_id: None, max_outposts_per_user: Some(1) })
+            .unwrap();
+        let err = suite.create_named_outpost(ALICE, Some("work")).unwrap_err();
+        assert!(matches!(err, ContractError::TooManyOutposts { max: 1 }));
+    }
+
+    fn set_creation_policy(suite: &mut Suite, policy: CreationPolicy) {
+        suite.execute(ADMIN, &ExecuteMsg::SetCreationPolicy { policy }).unwrap();
+    }
+
+    #[test]
+    fn only_admin_can_configure_creation() {
+        let mut suite = Suite::new();
+
+        let config: CreationConfig = suite.query(&QueryMsg::GetCreationConfig {}).unwrap();
+        assert_eq!(config, CreationConfig { policy: CreationPolicy::Open, deposit: None });
+
+        let msgs = [
+            ExecuteMsg::SetCreationPolicy { policy: CreationPolicy::Allowlist },
+            ExecuteMsg::UpdateAllowlist { add: vec![ALICE.to_string()], remove: vec![] },
+            ExecuteMsg::AddInviteCodes { code_hashes: vec![] },
+            ExecuteMsg::SetCreationDeposit { deposit: None },
+        ];
+        for msg in &msgs {
+            let err = suite.execute(ALICE, msg).unwrap_err();
+            assert!(matches!(err, ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)));
+        }
+
+        let err = suite
+            .execute(ADMIN, &ExecuteMsg::SetCreationPolicy {
+                policy: CreationPolicy::Cw20Holder { contract: "token".to_string(), min_balance: Uint128::zero() },
+            })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidCreationPolicy(_)));
+        let err = suite
+            .execute(ADMIN, &ExecuteMsg::AddInviteCodes { code_hashes: vec!["not-a-hash".to_string()] })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidInviteCodeHash(_)));
+    }
+
+    #[test]
+    fn allowlist_gates_creation() {
+        let mut suite = Suite::new();
+        set_creation_policy(&mut suite, CreationPolicy::Allowlist);
+
+        let err = suite.create_named_outpost(ALICE, None).unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted(_)));
+
+        suite
+            .execute(ADMIN, &ExecuteMsg::UpdateAllowlist { add: vec![ALICE.to_string(), "bob".to_string()], remove: vec![] })
+            .unwrap();
+        assert!(suite.query::<bool>(&QueryMsg::IsAllowlisted { address: ALICE.to_string() }).unwrap());
+        suite.create_outpost(ALICE);
+
+        suite
+            .execute(ADMIN, &ExecuteMsg::UpdateAllowlist { add: vec![], remove: vec!["bob".to_string()] })
+            .unwrap();
+        let err = suite.create_named_outpost("bob", None).unwrap_err();
+        assert!(matches!(err, ContractError::NotAllowlisted(_)));
+    }
+
+    #[test]
+    fn invite_codes_can_only_be_used_once() {
+        let mut suite = Suite::new();
+        set_creation_policy(&mut suite, CreationPolicy::InviteCode);
+        let code_hash = storage_outpost::helpers::filetree_helpers::hash_and_hex("welcome-alice");
+        suite.execute(ADMIN, &ExecuteMsg::AddInviteCodes { code_hashes: vec![code_hash] }).unwrap();
+
+        let err = suite.create_gated_outpost(ALICE, None, None, &[]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidInviteCode {}));
+        let err = suite.create_gated_outpost(ALICE, None, Some("welcome-bob"), &[]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidInviteCode {}));
+
+        suite.create_gated_outpost(ALICE, None, Some("welcome-alice"), &[]).unwrap();
+        let err = suite.create_gated_outpost("bob", None, Some("welcome-alice"), &[]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidInviteCode {}));
+    }
+
+    #[test]
+    fn token_holders_can_create_outposts() {
+        let mut suite = Suite::new();
+        let cw721_code_id = suite
+            .app
+            .store_code(Box::new(ContractWrapper::new(mock_token_execute, mock_token_instantiate, mock_cw721_query)));
+        let cw20_code_id = suite
+            .app
+            .store_code(Box::new(ContractWrapper::new(mock_token_execute, mock_token_instantiate, mock_cw20_query)));
+        let cw721 = suite.app.instantiate_contract(cw721_code_id, Addr::unchecked(ADMIN), &Empty {}, &[], "nft", None).unwrap();
+        let cw20 = suite.app.instantiate_contract(cw20_code_id, Addr::unchecked(ADMIN), &Empty {}, &[], "token", None).unwrap();
+
+        set_creation_policy(&mut suite, CreationPolicy::Cw721Holder { contract: cw721.to_string() });
+        suite.create_outpost(ALICE);
+        let err = suite.create_named_outpost("bob", None).unwrap_err();
+        assert!(matches!(err, ContractError::NotATokenHolder(_)));
+
+        set_creation_policy(&mut suite, CreationPolicy::Cw20Holder { contract: cw20.to_string(), min_balance: Uint128::new(100) });
+        suite.create_named_outpost(ALICE, Some("work")).unwrap();
+        let err = suite.create_named_outpost("bob", None).unwrap_err();
+        assert!(matches!(err, ContractError::NotATokenHolder(_)));
+
+        set_creation_policy(&mut suite, CreationPolicy::Cw20Holder { contract: cw20.to_string(), min_balance: Uint128::new(101) });
+        let err = suite.create_named_outpost(ALICE, Some("app-1")).unwrap_err();
+        assert!(matches!(err, ContractError::NotATokenHolder(_)));
+    }
+
+    #[test]
+    fn deposit_is_refunded_when_channel_opens() {
+        let mut suite = Suite::new();
+        suite.app.init_modules(|router, _, storage| {
+            router.bank.init_balance(storage, &Addr::unchecked(ALICE), vec![coin(1_000, "ujkl")]).unwrap();
+        });
+
+        // Funds aren't accepted unless a deposit is required
+        let err = suite.create_gated_outpost(ALICE, None, None, &[coin(10, "ujkl")]).unwrap_err();
+        assert!(matches!(err, ContractError::UnexpectedFunds {}));
+
+        suite
+            .execute(ADMIN, &ExecuteMsg::SetCreationDeposit { deposit: Some(coin(100, "ujkl")) })
+            .unwrap();
+        let err = suite.create_gated_outpost(ALICE, None, None, &[]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidDeposit(_)));
+        let err = suite.create_gated_outpost(ALICE, None, None, &[coin(50, "ujkl")]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidDeposit(_)));
+
+        let outpost = suite.create_gated_outpost(ALICE, None, None, &[coin(100, "ujkl")]).unwrap();
+        let balance = suite.app.wrap().query_balance(ALICE, "ujkl").unwrap();
+        assert_eq!(balance.amount.u128(), 900);
+        let deposit: Option<Coin> = suite
+            .query(&QueryMsg::GetDeposit { user_address: ALICE.to_string(), outpost_name: None })
+            .unwrap();
+        assert_eq!(deposit, Some(coin(100, "ujkl")));
+
+        suite.execute(ALICE, &ExecuteMsg::RetryChannel { outpost_name: None }).unwrap();
+        assert_eq!(outpost_status(&suite, &outpost).lifecycle, OutpostLifecycle::ChannelOpen);
+        let balance = suite.app.wrap().query_balance(ALICE, "ujkl").unwrap();
+        assert_eq!(balance.amount.u128(), 1_000);
+        let deposit: Option<Coin> = suite
+            .query(&QueryMsg::GetDeposit { user_address: ALICE.to_string(), outpost_name: None })
+            .unwrap();
+        assert_eq!(deposit, None);
+    }
+
+    #[test]
+    fn admin_is_rotated_in_two_steps() {
+        let mut suite = Suite::new();
+        const MULTISIG: &str = "multisig";
+
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::UpdateOwnership(cw_ownable::Action::TransferOwnership {
+                new_owner: ALICE.to_string(),
+                expiry: None,
+            }))
+            .unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(_)));
+        let err = suite.execute(ADMIN, &ExecuteMsg::UpdateOwnership(cw_ownable::Action::RenounceOwnership)).unwrap_err();
+        assert!(matches!(err, ContractError::CannotRenounceOwnership {}));
+
+        suite
+            .execute(ADMIN, &ExecuteMsg::UpdateOwnership(cw_ownable::Action::TransferOwnership {
+                new_owner: MULTISIG.to_string(),
+                expiry: None,
+            }))
+            .unwrap();
+
+        // Nothing changes until the multisig accepts
+        let ownership: cw_ownable::Ownership<Addr> = suite.query(&QueryMsg::Ownership {}).unwrap();
+        assert_eq!(ownership.owner, Some(Addr::unchecked(ADMIN)));
+        assert_eq!(ownership.pending_owner, Some(Addr::unchecked(MULTISIG)));
+        suite.execute(ADMIN, &ExecuteMsg::UpdateConfig { storage_outpost_code_id: None, max_outposts_per_user: Some(2) }).unwrap();
+
+        suite.execute(MULTISIG, &ExecuteMsg::UpdateOwnership(cw_ownable::Action::AcceptOwnership)).unwrap();
+        let state: ContractState = suite.query(&QueryMsg::GetContractState {}).unwrap();
+        assert_eq!(state.admin, MULTISIG);
+
+        let err = suite.execute(ADMIN, &ExecuteMsg::UpdateConfig { storage_outpost_code_id: None, max_outposts_per_user: Some(5) }).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)));
+        suite.execute(MULTISIG, &ExecuteMsg::UpdateConfig { storage_outpost_code_id: None, max_outposts_per_user: Some(5) }).unwrap();
+    }
+
+    #[test]
+    fn admin_can_update_config() {
+        let mut suite = Suite::new();
+        let new_code_id = suite.store_outpost_code();
+
+        let err = suite
+            .execute(ALICE, &ExecuteMsg::UpdateConfig { storage_outpost_code_id: Some(new_code_id), max_outposts_per_user: None })
+            .unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)));
+
+        suite
+            .execute(ADMIN, &ExecuteMsg::UpdateConfig { storage_outpost_code_id: Some(new_code_id), max_outposts_per_user: None })
+            .unwrap();
+        let state: ContractState = suite.query(&QueryMsg::GetContractState {}).unwrap();
+        assert_eq!(state.storage_outpost_code_id, new_code_id);
+        assert_eq!(state.max_outposts_per_user, 3);
+
+        let outpost = suite.create_outpost(ALICE);
+        assert_eq!(suite.outpost_code_id(&outpost), new_code_id);
+    }
+
+    #[test]
+    fn outposts_are_created_from_connection_templates() {
+        let mut suite = Suite::new();
+        let outpost_connection = |suite: &Suite, outpost: &Addr| -> String {
+            let raw = suite.app.wrap().query_wasm_raw(outpost, b"channel_options".as_slice()).unwrap().unwrap();
+            cosmwasm_std::from_json::<ChannelOpenInitOptions>(&raw).unwrap().connection_id
+        };
+        let set_template = |name: &str, connection_id: &str| ExecuteMsg::SetConnectionTemplate {
+            name: name.to_string(),
+            options: channel_options(connection_id),
+        };
+
+        let err = suite.execute(ALICE, &set_template("jackal-mainnet", "connection-1")).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)));
+        let err = suite.execute(ADMIN, &set_template("Jackal Mainnet", "connection-1")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidConnectionTemplate(_)));
+        suite.execute(ADMIN, &set_template("jackal-mainnet", "connection-1")).unwrap();
+
+        let templates: Vec<ConnectionTemplate> = suite.query(&QueryMsg::GetConnectionTemplates {}).unwrap();
+        assert_eq!(templates, vec![ConnectionTemplate { name: "jackal-mainnet".to_string(), options: channel_options("connection-1") }]);
+
+        let err = suite.create_outpost_on(ALICE, None, None).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChannelOptions {}));
+        let err = suite.create_outpost_on(ALICE, Some(channel_options("connection-1")), Some("jackal-mainnet")).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChannelOptions {}));
+        let err = suite.create_outpost_on(ALICE, None, Some("jackal-testnet")).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownConnectionTemplate(_)));
+
+        // Once templates exist, raw options must point at one of their connections
+        let err = suite.create_outpost_on(ALICE, Some(channel_options("connection-0")), None).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownConnection { .. }));
+        let outpost = suite.create_outpost_on(ALICE, Some(channel_options("connection-1")), None).unwrap();
+        assert_eq!(outpost_connection(&suite, &outpost), "connection-1");
+
+        // The light client expired: new outposts move to a fresh connection
+        suite.execute(ADMIN, &set_template("jackal-mainnet", "connection-7")).unwrap();
+        let outpost = suite.create_outpost_on("bob", None, Some("jackal-mainnet")).unwrap();
+        assert_eq!(outpost_connection(&suite, &outpost), "connection-7");
+        let err = suite.create_outpost_on("carol", Some(channel_options("connection-1")), None).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownConnection { .. }));
+
+        // Raw options take the template's encoding and ordering, and can't ask for different ones
+        let json_options = ChannelOpenInitOptions {
+            tx_encoding: Some(TxEncoding::Proto3Json),
+            ..channel_options("connection-3")
+        };
+        suite
+            .execute(ADMIN, &ExecuteMsg::SetConnectionTemplate { name: "jackal-json".to_string(), options: json_options.clone() })
+            .unwrap();
+        let protobuf_options = ChannelOpenInitOptions {
+            tx_encoding: Some(TxEncoding::Protobuf),
+            ..channel_options("connection-3")
+        };
+        let err = suite.create_outpost_on("carol", Some(protobuf_options), None).unwrap_err();
+        assert!(matches!(err, ContractError::ChannelOptionsMismatch { .. }));
+        let outpost = suite.create_outpost_on("carol", Some(channel_options("connection-3")), None).unwrap();
+        let raw = suite.app.wrap().query_wasm_raw(&outpost, b"channel_options".as_slice()).unwrap().unwrap();
+        assert_eq!(cosmwasm_std::from_json::<ChannelOpenInitOptions>(&raw).unwrap(), json_options);
+
+        suite.execute(ADMIN, &ExecuteMsg::RemoveConnectionTemplate { name: "jackal-mainnet".to_string() }).unwrap();
+        let err = suite.create_outpost_on("dave", None, Some("jackal-mainnet")).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownConnectionTemplate(_)));
+    }
+
+    #[test]
+    fn decommissioned_outposts_are_unregistered() {
+        let mut suite = Suite::new();
+        let outpost = suite.create_outpost(ALICE);
+        suite
+            .execute(outpost.as_str(), &ExecuteMsg::OutpostChannelOpened { ica_address: "jkl1alice".to_string() })
+            .unwrap();
+
+        let err = suite.execute("mallory", &ExecuteMsg::OutpostDecommissioned {}).unwrap_err();
+        assert!(matches!(err, ContractError::UnknownOutpost(_)));
+        suite.execute(outpost.as_str(), &ExecuteMsg::OutpostDecommissioned {}).unwrap();
+
+        let status = outpost_status(&suite, &outpost);
+        assert_eq!(status.lifecycle, OutpostLifecycle::Decommissioned);
+        assert!(suite
+            .query::<String>(&QueryMsg::GetOutpostOwner { outpost_address: outpost.to_string() })
+            .is_err());
+        assert!(suite
+            .query::<DirectoryEntry>(&QueryMsg::GetDirectoryEntryByIca { ica_address: "jkl1alice".to_string() })
+            .is_err());
+
+        // The outpost can't report anything anymore, and its name is free again
+        let err = suite
+            .execute(outpost.as_str(), &ExecuteMsg::OutpostChannelClosed {})
+            .unwrap_err();
+        assert!(matches!(err, ContractError::UnknownOutpost(_)));
+        let new_outpost = suite.create_outpost(ALICE);
+        assert_ne!(new_outpost, outpost);
+    }
+}
@@ -21,9 +21,99 @@ pub enum ContractError {
     #[error("lock file does not exist")]
     MissingLock {},
 
+    #[error("{0}")]
+    OwnershipError(#[from] cw_ownable::OwnershipError),
+
+    #[error("The factory always needs an admin, transfer ownership instead of renouncing it")]
+    CannotRenounceOwnership {},
+
     #[error("Outpost already created. Outpost Address: {0}")]
     AlreadyCreated(String),
 
-    #[error("Only the factory admin can perform outpost migrations")]
-    NotAdmin {},
+    #[error("Users can own at most {max} outposts")]
+    TooManyOutposts { max: u32 },
+
+    #[error("Invalid outpost name '{0}': use 1 to 32 lowercase letters, digits, '-' or '_'")]
+    InvalidOutpostName(String),
+
+    #[error("invalid migration version: expected {expected}, got {actual}")]
+    InvalidMigrationVersion { expected: String, actual: String },
+
+    #[error("semver parse error: {0}")]
+    SemverError(#[from] semver::Error),
+
+    #[error("No outpost named '{name}' found for user: {user}")]
+    NoOutpost { user: String, name: String },
+
+    #[error("Outpost version {0} has already been published")]
+    VersionAlreadyPublished(String),
+
+    #[error("Code id {code_id} is already published as version {version}")]
+    CodeIdAlreadyPublished { code_id: u64, version: String },
+
+    #[error("Outpost version {0} has not been published")]
+    UnknownVersion(String),
+
+    #[error("Invalid code id: {0}")]
+    InvalidCodeId(String),
+
+    #[error("Code id {0} has not been published as an outpost version")]
+    UnpublishedCodeId(u64),
+
+    #[error("Outpost version {0} is deprecated")]
+    VersionDeprecated(String),
+
+    #[error("Outpost is already running version {0}")]
+    AlreadyOnVersion(String),
+
+    #[error("{0} is not an outpost created by this factory")]
+    UnknownOutpost(String),
+
+    #[error("ICA address {ica_address} is already registered to outpost {outpost_address}")]
+    IcaAlreadyRegistered { ica_address: String, outpost_address: String },
+
+    #[error("Outpost {0} already has an open channel")]
+    ChannelAlreadyOpen(String),
+
+    #[error("Pass exactly one of 'connection_template' and 'channel_open_init_options'")]
+    InvalidChannelOptions {},
+
+    #[error("Unknown connection template: {0}")]
+    UnknownConnectionTemplate(String),
+
+    #[error("{connection_id} to {counterparty_connection_id} doesn't match any connection template")]
+    UnknownConnection {
+        connection_id: String,
+        counterparty_connection_id: String,
+    },
+
+    #[error("The channel options on {connection_id} differ from its connection template's")]
+    ChannelOptionsMismatch { connection_id: String },
+
+    #[error("Invalid connection template: {0}")]
+    InvalidConnectionTemplate(String),
+
+    #[error("{0} is not allowed to create outposts")]
+    NotAllowlisted(String),
+
+    #[error("Missing, unknown or already used invite code")]
+    InvalidInviteCode {},
+
+    #[error("Invite code hashes must be hex encoded sha256 hashes, got {0}")]
+    InvalidInviteCodeHash(String),
+
+    #[error("Creating an outpost requires holding tokens of {0}")]
+    NotATokenHolder(String),
+
+    #[error("Invalid creation policy: {0}")]
+    InvalidCreationPolicy(String),
+
+    #[error("Creating an outpost requires a deposit of exactly {0}")]
+    InvalidDeposit(cosmwasm_std::Coin),
+
+    #[error("Creating an outpost doesn't require funds")]
+    UnexpectedFunds {},
+
+    #[error("Cannot downgrade outpost from version {current} to {requested}")]
+    DowngradeNotAllowed { current: String, requested: String },
 }
@@ -1,38 +1,244 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Coin, Uint128};
 use storage_outpost::types::msg::options::ChannelOpenInitOptions;
 
+use crate::state::CreationPolicy;
+
 #[cw_serde]
 pub struct InstantiateMsg {
     pub storage_outpost_code_id: u64,
+    /// How many outposts each user may own. Defaults to one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_outposts_per_user: Option<u32>,
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
+/// 'UpdateOwnership' rotates the factory admin in two steps: the admin proposes a new one with 'transfer_ownership',
+/// which only takes effect once the new admin sends 'accept_ownership'
+#[cw_ownable::cw_ownable_execute]
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// Exactly one of 'connection_template' and 'channel_open_init_options' must be given
     CreateOutpost {
-        channel_open_init_options: ChannelOpenInitOptions,
+        /// Connection details, which must match a registered connection template unless none are registered.
+        /// Prefer 'connection_template'
+        #[serde(skip_serializing_if = "Option::is_none")]
+        channel_open_init_options: Option<ChannelOpenInitOptions>,
+        /// The name of a connection template registered by the admin, e.g. "jackal-mainnet"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        connection_template: Option<String>,
+        /// Users can own several outposts, e.g. "personal" and "work", each with its own ICA.
+        /// Defaults to "default"
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+        /// Required when the creation policy is 'invite_code'. Each code can only be used once
+        #[serde(skip_serializing_if = "Option::is_none")]
+        invite_code: Option<String>,
     },
     // When the outpost is created for a user, the created outpost contract will call back this factory contract
     // to execute the below function and map the user's address to their owned outpost
     MapUserOutpost {
         outpost_owner: String, // This function is called for a specific purpose of updating a map so we don't make the params optional 
+        // Outposts created before outposts were named don't send this
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
     },
     // Migrations thoroughly tested
+    /// Admin only: migrate a user's outpost to a published version that isn't deprecated.
+    /// 'new_outpost_code_id' is the version's code id as a decimal string
     MigrateOutpost {
         outpost_owner: String, 
         new_outpost_code_id: String,
-    }
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+    },
+    /// Admin only: approve an outpost code id under a semver label so users can opt into it
+    PublishOutpostVersion {
+        version: String,
+        code_id: u64,
+        release_notes: String,
+        /// If true, newly created outposts will be instantiated from this code id
+        #[serde(skip_serializing_if = "Option::is_none")]
+        set_as_default: Option<bool>,
+    },
+    /// Admin only: stop users from upgrading to this version. Outposts already running it are untouched
+    DeprecateOutpostVersion {
+        version: String,
+    },
+    /// Called by an outpost owner to migrate their own outpost to a published version.
+    /// The factory is the wasm admin of every outpost it creates, so it performs the migration on the owner's behalf
+    UpgradeMyOutpost {
+        version: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+    },
+    /// Called by an outpost this factory created once its ICA channel handshake completes
+    OutpostChannelOpened {
+        ica_address: String,
+    },
+    /// Called by an outpost this factory created when its ICA channel closes
+    OutpostChannelClosed {},
+    // Called back by the outpost once its owner decommissioned it, which unregisters it
+    OutpostDecommissioned {},
+    /// Called by an outpost owner whose channel handshake never completed, or whose channel was closed.
+    /// The factory asks the outpost to submit a new channel open init with the options it was created with
+    RetryChannel {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+    },
+    /// Admin only: register a named connection template, or replace it, e.g. when its light client expired
+    /// and new outposts should use a fresh connection. Existing outposts keep the connection they were created with
+    SetConnectionTemplate {
+        name: String,
+        options: ChannelOpenInitOptions,
+    },
+    /// Admin only: stop new outposts from using a connection template
+    RemoveConnectionTemplate {
+        name: String,
+    },
+    /// Admin only: change the code id new outposts are instantiated from, and how many outposts each user may own.
+    /// Fields left out are not changed. Users already above the cap keep their outposts
+    UpdateConfig {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        storage_outpost_code_id: Option<u64>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        max_outposts_per_user: Option<u32>,
+    },
+    /// Admin only: choose who may create outposts
+    SetCreationPolicy {
+        policy: CreationPolicy,
+    },
+    /// Admin only: add or remove addresses from the allowlist used by the 'allowlist' creation policy
+    UpdateAllowlist {
+        add: Vec<String>,
+        remove: Vec<String>,
+    },
+    /// Admin only: register one-time invite codes for the 'invite_code' creation policy.
+    /// Only the hex encoded sha256 hash of each code is sent and stored, so codes never appear on chain before use
+    AddInviteCodes {
+        code_hashes: Vec<String>,
+    },
+    /// Admin only: require a deposit in native tokens to create an outpost. It is refunded to the user once
+    /// their outpost's channel opens. Unset to stop requiring a deposit
+    SetCreationDeposit {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        deposit: Option<Coin>,
+    },
 }
 
+#[cw_ownable::cw_ownable_query]
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     /// GetContractState returns the contact's state.
     #[returns(crate::state::ContractState)]
     GetContractState {},
-    /// GetUserOutpostAddress returns the outpost address owned by the given user address
+    /// GetUserOutpostAddress returns the address of the user's outpost with the given name, or of their default outpost
     #[returns(String)]
-    GetUserOutpostAddress { user_address: String},
+    GetUserOutpostAddress {
+        user_address: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+    },
+    /// GetUserOutposts returns the name and address of every outpost the user owns
+    #[returns(Vec<(String, String)>)]
+    GetUserOutposts { user_address: String },
     /// GetAllUserOutpostAddresses returns all user-to-outpost mappings.
+    /// Users owning several outposts appear once per outpost
     #[returns(Vec<(String, String)>)]
     GetAllUserOutpostAddresses {},
+    /// GetOutpostOwner returns the user address that owns the given outpost
+    #[returns(String)]
+    GetOutpostOwner { outpost_address: String },
+    /// GetDirectoryEntryByUser resolves a user address to their outpost and its ICA (Jackal) address
+    #[returns(DirectoryEntry)]
+    GetDirectoryEntryByUser {
+        user_address: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+    },
+    /// GetDirectoryEntryByIca resolves an ICA (Jackal) address to the outpost controlling it and that outpost's owner
+    #[returns(DirectoryEntry)]
+    GetDirectoryEntryByIca { ica_address: String },
+    /// GetOutpostStatus returns how far along its lifecycle the given outpost is
+    #[returns(crate::state::OutpostStatus)]
+    GetOutpostStatus { outpost_address: String },
+    /// GetCreationConfig returns who may create outposts and the deposit they must make
+    #[returns(CreationConfig)]
+    GetCreationConfig {},
+    /// IsAllowlisted returns whether the address may create outposts under the 'allowlist' creation policy
+    #[returns(bool)]
+    IsAllowlisted { address: String },
+    /// GetDeposit returns the deposit held for the user's outpost until its channel opens
+    #[returns(Option<Coin>)]
+    GetDeposit {
+        user_address: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+    },
+    /// GetConnectionTemplate returns the connection details registered under this name
+    #[returns(ConnectionTemplate)]
+    GetConnectionTemplate { name: String },
+    /// GetConnectionTemplates returns every registered connection template, ordered by name
+    #[returns(Vec<ConnectionTemplate>)]
+    GetConnectionTemplates {},
+    /// GetOutpostVersion returns a single entry of the outpost code id registry
+    #[returns(crate::state::OutpostVersion)]
+    GetOutpostVersion { version: String },
+    /// GetOutpostVersions returns every published outpost version, ordered from oldest to newest
+    #[returns(Vec<crate::state::OutpostVersion>)]
+    GetOutpostVersions {},
+}
+
+/// Links a user to their outpost and the Interchain Account it controls on Jackal
+#[cw_serde]
+pub struct DirectoryEntry {
+    pub user_address: String,
+    pub outpost_name: String,
+    pub outpost_address: String,
+    /// The Jackal address to use when sharing files with this user, e.g. in 'MsgAddViewers'
+    pub ica_address: String,
+}
+
+#[cw_serde]
+pub struct ConnectionTemplate {
+    pub name: String,
+    pub options: ChannelOpenInitOptions,
+}
+
+#[cw_serde]
+pub struct CreationConfig {
+    pub policy: CreationPolicy,
+    pub deposit: Option<Coin>,
+}
+
+// The factory only needs one query from each token standard, so we mirror them here instead of
+// depending on the cw721 and cw20 crates
+
+/// The subset of the cw721 query messages used to gate outpost creation
+#[cw_serde]
+pub enum Cw721QueryMsg {
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct Cw721TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+/// The subset of the cw20 query messages used to gate outpost creation
+#[cw_serde]
+pub enum Cw20QueryMsg {
+    Balance { address: String },
+}
+
+#[cw_serde]
+pub struct Cw20BalanceResponse {
+    pub balance: Uint128,
 }
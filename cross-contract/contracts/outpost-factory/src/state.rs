@@ -1,17 +1,67 @@
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Coin, Empty};
 use cw_storage_plus::{Item, Map};
+use storage_outpost::types::msg::options::ChannelOpenInitOptions;
 
 pub use contract::ContractState;
+pub use gating::CreationPolicy;
+pub use lifecycle::{OutpostLifecycle, OutpostStatus};
+pub use registry::OutpostVersion;
 
 /// The item used for storing the outpost's code id 
 pub const STATE: Item<ContractState> = Item::new("state");
 
-/// A mapping of the user's address to the outpost address they own
-pub const USER_ADDR_TO_OUTPOST_ADDR: Map<&str, String> = Map::new("user_addr_to_outpost_addr");
+/// The name given to outposts created without one. Queries which don't name an outpost return this one,
+/// which is the outpost users had before they could own several
+pub const DEFAULT_OUTPOST_NAME: &str = "default";
+
+/// How many outposts a user may own unless the admin configures otherwise
+pub const DEFAULT_MAX_OUTPOSTS_PER_USER: u32 = 1;
+
+/// A mapping of the user's address and outpost name to the outpost address
+pub const USER_OUTPOSTS: Map<(&str, &str), String> = Map::new("user_outposts");
+
+/// The reverse of 'USER_OUTPOSTS', used to recognise calls coming from outposts this factory created
+pub const OUTPOST_ADDR_TO_USER_ADDR: Map<&str, String> = Map::new("outpost_addr_to_user_addr");
+
+/// The name each outpost was created under
+pub const OUTPOST_ADDR_TO_NAME: Map<&str, String> = Map::new("outpost_addr_to_name");
+
+/// The lifecycle of every outpost this factory created, keyed by outpost address
+pub const OUTPOST_STATUS: Map<&str, OutpostStatus> = Map::new("outpost_status");
+
+/// The reverse of the ICA addresses in 'OUTPOST_STATUS', so a Jackal address can be resolved to its outpost.
+/// Together with the user/outpost maps this forms the factory's directory
+pub const ICA_ADDR_TO_OUTPOST_ADDR: Map<&str, String> = Map::new("ica_addr_to_outpost_addr");
 
 /// This behaves like a lock file which ensures that users can only create an outpost for themselves
 /// It's a needed work around that's caused by inter-contract executions being signed by the calling contract instead of the user's signature
-pub const LOCK: Map<&str, bool> = Map::new("lock");
+/// Keyed by the user's address and the outpost name
+pub const LOCK: Map<(&str, &str), bool> = Map::new("outpost_lock");
+
+/// Who may create outposts. Anyone may if this was never set
+pub const CREATION_POLICY: Item<CreationPolicy> = Item::new("creation_policy");
+
+/// The addresses allowed to create outposts under 'CreationPolicy::Allowlist'
+pub const ALLOWLIST: Map<&str, Empty> = Map::new("allowlist");
+
+/// The hex encoded sha256 hashes of the unused invite codes for 'CreationPolicy::InviteCode'
+pub const INVITE_CODES: Map<&str, Empty> = Map::new("invite_codes");
+
+/// The deposit required to create an outpost, if any
+pub const CREATION_DEPOSIT: Item<Coin> = Item::new("creation_deposit");
+
+/// Deposits held until the outpost's channel opens, keyed by the user's address and the outpost name
+pub const DEPOSITS: Map<(&str, &str), Coin> = Map::new("deposits");
+
+/// The connections new outposts may open their channel on, keyed by a name chosen by the admin
+pub const CONNECTION_TEMPLATES: Map<&str, ChannelOpenInitOptions> = Map::new("connection_templates");
+
+/// The registry of approved outpost code ids, keyed by their semver label
+pub const OUTPOST_VERSIONS: Map<&str, OutpostVersion> = Map::new("outpost_versions");
+
+/// Reverse lookup of 'OUTPOST_VERSIONS' so we can tell which version an outpost is running from its code id
+pub const CODE_ID_TO_VERSION: Map<u64, String> = Map::new("code_id_to_version");
 
 mod contract {
 
@@ -21,7 +71,15 @@ mod contract {
     pub struct ContractState {
         /// The code ID of the storage-outpost contract.
         pub storage_outpost_code_id: u64,
+        /// A mirror of the cw_ownable owner, which is what gates admin functions and what 'UpdateOwnership' changes
         pub admin: String,
+        /// How many named outposts each user may own
+        #[serde(default = "default_max_outposts_per_user")]
+        pub max_outposts_per_user: u32,
+    }
+
+    fn default_max_outposts_per_user() -> u32 {
+        DEFAULT_MAX_OUTPOSTS_PER_USER
     }
 
     impl ContractState {
@@ -29,8 +87,76 @@ mod contract {
         pub fn new(storage_outpost_code_id: u64, admin: String) -> Self {
             Self {
                 storage_outpost_code_id,
-                admin
+                admin,
+                max_outposts_per_user: DEFAULT_MAX_OUTPOSTS_PER_USER,
             }
         }
     }
-}
\ No newline at end of file
+}
+
+mod registry {
+    use cosmwasm_std::Timestamp;
+
+    use super::*;
+
+    /// An approved release of the storage-outpost contract
+    #[cw_serde]
+    pub struct OutpostVersion {
+        /// The semver label of the release
+        pub version: String,
+        /// The code id the release was stored under
+        pub code_id: u64,
+        pub release_notes: String,
+        /// Deprecated versions cannot be upgraded to, but outposts already running them keep working
+        pub deprecated: bool,
+        pub published_at: Timestamp,
+    }
+}
+
+mod lifecycle {
+    use cosmwasm_std::Timestamp;
+
+    use super::*;
+
+    /// The stages an outpost goes through after the factory instantiates it
+    #[cw_serde]
+    pub enum OutpostLifecycle {
+        /// The outpost exists but its ICA channel handshake hasn't completed
+        Instantiated,
+        /// The ICA channel is open and the outpost can send messages
+        ChannelOpen,
+        /// The ICA channel was closed. The ICA is kept by the host and can be reused by retrying the channel
+        ChannelClosed,
+        /// The owner retired the outpost. It's no longer registered to them and can't be used again
+        Decommissioned,
+    }
+
+    #[cw_serde]
+    pub struct OutpostStatus {
+        pub lifecycle: OutpostLifecycle,
+        /// The Jackal address of the outpost's ICA, known once the channel has opened
+        pub ica_address: Option<String>,
+        pub updated_at: Timestamp,
+    }
+}
+
+mod gating {
+    use cosmwasm_std::Uint128;
+
+    use super::*;
+
+    /// Who may create outposts
+    #[cw_serde]
+    pub enum CreationPolicy {
+        /// Anyone
+        Open,
+        /// Only addresses the admin added to the allowlist
+        Allowlist,
+        /// Anyone holding an unused invite code
+        InviteCode,
+        /// Anyone owning at least one token of the cw721 contract
+        Cw721Holder { contract: String },
+        /// Anyone holding at least 'min_balance' of the cw20 token
+        Cw20Holder { contract: String, min_balance: Uint128 },
+    }
+}
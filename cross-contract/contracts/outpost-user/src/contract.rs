@@ -1,11 +1,16 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use cw2::set_contract_version;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::state::{ContractState, STATE, FILE_NOTE};
 
+// version info for migration info
+pub const CONTRACT_NAME: &str = "crates.io:outpost-user"; // just a placeholder, not yet published
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 use storage_outpost::types::msg::ExecuteMsg as OutpostExecuteMsg;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -21,9 +26,11 @@ pub fn instantiate(
     // This contract cannot have an owner because it needs to be called by all users to map their outpost
     // We have a check below which ensures that users cannot call 'map' twice 
 
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     STATE.save(
         deps.storage,
-        &ContractState::new(msg.storage_outpost_address),
+        &ContractState::new(msg.storage_outpost_address, info.sender.to_string()),
     )?;
     Ok(Response::default())
 }
@@ -56,6 +63,26 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
+/// Migrate contract if version is lower than current version
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let prev_version = migrate::validate_semver(deps.as_ref())?;
+
+    let mut response = Response::new()
+        .add_attribute("previous_version", prev_version.to_string())
+        .add_attribute("new_version", CONTRACT_VERSION);
+
+    // State migration hooks, run in order. Each hook only runs if the contract is older than the release that introduced it
+    if prev_version < semver::Version::new(0, 2, 0) {
+        let admin = migrate::add_admin(deps.branch(), &env)?;
+        response = response.add_attribute("admin", admin);
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(response)
+}
+
 mod execute {
     use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Uint128, Event, to_json_binary};
     use storage_outpost::outpost_helpers::StorageOutpostContract;
@@ -139,7 +166,8 @@ mod execute {
         let outpost_msg = OutpostExecuteMsg::SendCosmosMsgs { 
             messages: messages, 
             packet_memo: packet_memo, 
-            timeout_seconds: timeout_seconds };
+            timeout_seconds: timeout_seconds,
+            fill_signer_fields: None };
 
         let outpost_msg = outpost_contract.call(outpost_msg)?;
 
@@ -186,4 +214,158 @@ mod query {
     }
 }
 
+mod migrate {
+    use cosmwasm_schema::cw_serde;
+    use cw_storage_plus::Item;
+
+    use super::*;
+
+    /// Contracts deployed before cw2 was wired up never recorded a version.
+    /// 0.1.0 is the only release that shipped without it
+    const UNVERSIONED_RELEASE: semver::Version = semver::Version::new(0, 1, 0);
+
+    /// The state layout of 0.1.0, before the admin field was added
+    #[cw_serde]
+    struct ContractStateV0_1 {
+        storage_outpost_address: String,
+    }
+
+    const STATE_V0_1: Item<ContractStateV0_1> = Item::new("state");
+
+    /// Validate that the contract version is semver compliant
+    /// and greater than the previous version. Returns the previous version.
+    pub fn validate_semver(deps: Deps) -> Result<semver::Version, ContractError> {
+        let Some(prev_cw2_version) = cw2::CONTRACT.may_load(deps.storage)? else {
+            return Ok(UNVERSIONED_RELEASE)
+        };
+        if prev_cw2_version.contract != CONTRACT_NAME {
+            return Err(ContractError::InvalidMigrationVersion {
+                expected: CONTRACT_NAME.to_string(),
+                actual: prev_cw2_version.contract,
+            });
+        }
+
+        let version: semver::Version = CONTRACT_VERSION.parse()?;
+        let prev_version: semver::Version = prev_cw2_version.version.parse()?;
+        if prev_version >= version {
+            return Err(ContractError::InvalidMigrationVersion {
+                expected: format!("> {prev_version}"),
+                actual: CONTRACT_VERSION.to_string(),
+            });
+        }
+        Ok(prev_version)
+    }
+
+    /// 0.2.0 added 'ContractState.admin'. Only the wasm admin can migrate, so they become the admin.
+    /// Returns the admin that was saved
+    pub fn add_admin(deps: DepsMut, env: &Env) -> Result<String, ContractError> {
+        let old_state = STATE_V0_1.load(deps.storage)?;
+
+        let admin = deps
+            .querier
+            .query_wasm_contract_info(env.contract.address.to_string())?
+            .admin
+            .ok_or(ContractError::NoAdmin {})?;
+
+        STATE.save(deps.storage, &ContractState::new(old_state.storage_outpost_address, admin.clone()))?;
+
+        Ok(admin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{Addr, Empty};
+    use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+    use crate::msg::{InstantiateMsg, MigrateMsg, QueryMsg};
+    use crate::state::ContractState;
+    use crate::ContractError;
+
+    use super::*;
+
+    // Stands in for a 0.1.0 outpost user: no cw2 version and no admin in state
+    fn legacy_instantiate(deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+        deps.storage.set(
+            b"state",
+            format!(r#"{{"storage_outpost_address":"{}"}}"#, msg.storage_outpost_address).as_bytes(),
+        );
+        Ok(Response::new())
+    }
+
+    fn legacy_outpost_user() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(super::execute, legacy_instantiate, super::query);
+        Box::new(contract)
+    }
+
+    fn outpost_user() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(super::execute, super::instantiate, super::query)
+            .with_migrate(super::migrate);
+        Box::new(contract)
+    }
+
+    #[test]
+    fn instantiate_sets_admin_and_version() {
+        let mut app = App::default();
+        let code_id = app.store_code(outpost_user());
+
+        let contract = app
+            .instantiate_contract(
+                code_id,
+                Addr::unchecked("deployer"),
+                &InstantiateMsg { storage_outpost_address: "outpost".to_string() },
+                &[],
+                "outpost user",
+                None,
+            )
+            .unwrap();
+
+        let state: ContractState = app.wrap().query_wasm_smart(contract.clone(), &QueryMsg::GetContractState {}).unwrap();
+        assert_eq!(state, ContractState::new("outpost".to_string(), "deployer".to_string()));
+
+        let version = cw2::query_contract_info(&app.wrap(), contract.to_string()).unwrap();
+        assert_eq!(version.version, CONTRACT_VERSION);
+    }
+
+    #[test]
+    fn migration_adds_admin() {
+        let admin = Addr::unchecked("admin");
+        let mut app = App::default();
+        let old_code_id = app.store_code(legacy_outpost_user());
+        let new_code_id = app.store_code(outpost_user());
+
+        let contract = app
+            .instantiate_contract(
+                old_code_id,
+                Addr::unchecked("sender"),
+                &InstantiateMsg { storage_outpost_address: "outpost".to_string() },
+                &[],
+                "v1 contract",
+                Some(admin.to_string()),
+            )
+            .unwrap();
+
+        // The old layout doesn't deserialize into the new state
+        let res: StdResult<ContractState> = app.wrap().query_wasm_smart(contract.clone(), &QueryMsg::GetContractState {});
+        assert!(res.is_err());
+
+        app.migrate_contract(admin.clone(), contract.clone(), &MigrateMsg {}, new_code_id)
+            .unwrap();
+
+        let state: ContractState = app.wrap().query_wasm_smart(contract.clone(), &QueryMsg::GetContractState {}).unwrap();
+        assert_eq!(state, ContractState::new("outpost".to_string(), admin.to_string()));
+
+        let version = cw2::query_contract_info(&app.wrap(), contract.to_string()).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+
+        let err: ContractError = app
+            .migrate_contract(admin, contract, &MigrateMsg {}, new_code_id)
+            .unwrap_err()
+            .downcast()
+            .unwrap();
+        assert!(matches!(err, ContractError::InvalidMigrationVersion { .. }));
+    }
+}
+
 
@@ -11,4 +11,13 @@ pub enum ContractError {
 
     #[error("Outpost already created. Outpost Address: {0}")]
     AlreadyCreated(String),
+
+    #[error("invalid migration version: expected {expected}, got {actual}")]
+    InvalidMigrationVersion { expected: String, actual: String },
+
+    #[error("semver parse error: {0}")]
+    SemverError(#[from] semver::Error),
+
+    #[error("contract has no wasm admin")]
+    NoAdmin {},
 }
@@ -9,6 +9,9 @@ pub struct InstantiateMsg {
     pub storage_outpost_address: String,
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 // #[ica_callback_execute] let's implement this later
 #[cw_serde]
 pub enum ExecuteMsg {
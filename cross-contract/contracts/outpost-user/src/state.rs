@@ -20,13 +20,16 @@ mod contract {
     pub struct ContractState {
         /// The code ID of the storage-outpost contract.
         pub storage_outpost_address: String,
+        /// The instantiator of this contract. Contracts migrated from 0.1.0 use their wasm admin
+        pub admin: String,
     }
 
     impl ContractState {
         /// Creates a new ContractState.
-        pub fn new(storage_outpost_address: String) -> Self {
+        pub fn new(storage_outpost_address: String, admin: String) -> Self {
             Self {
                 storage_outpost_address,
+                admin,
             }
         }
     }
@@ -2,12 +2,12 @@
 
 use cosmos_sdk_proto::tendermint::p2p::packet;
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Event, Empty, CosmosMsg, IbcQuery};
+use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult, Event, Empty, CosmosMsg, IbcQuery};
 use crate::ibc::types::stargate::channel::new_ica_channel_open_init_cosmos_msg;
 use crate::types::keys::{self, CONTRACT_NAME, CONTRACT_VERSION};
-use crate::types::msg::{OutpostFactoryExecuteMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::types::msg::{OutpostFactoryExecuteMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SudoMsg, IbcLifecycleComplete};
 use crate::types::state::{
-    self, CallbackCounter, ChannelState, ContractState, CALLBACK_COUNTER, CHANNEL_STATE, STATE, CHANNEL_OPEN_INIT_OPTIONS, ALLOW_CHANNEL_OPEN_INIT
+    self, CallbackCounter, ChannelState, ContractState, CALLBACK, CALLBACK_COUNTER, CHANNEL_STATE, STATE, CHANNEL_OPEN_INIT_OPTIONS, ALLOW_CHANNEL_OPEN_INIT
 };
 use crate::types::ContractError;
 use crate::types::filetree::{MsgPostKey, MsgPostFile};
@@ -25,8 +25,6 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg, //call back object is nested here 
 ) -> Result<Response, ContractError> {
-    use cosmwasm_std::WasmMsg;
-
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     // SECURITY NOTE: If Alice instantiated an outpost that's owned by Bob, this really has no consequence
@@ -71,6 +69,13 @@ pub fn instantiate(
 
     ALLOW_CHANNEL_OPEN_INIT.save(deps.storage, &true)?;
 
+    // Keep the callback so its contract can be notified when the channel opens or closes
+    if let Some(ref callback) = msg.callback {
+        CALLBACK.save(deps.storage, callback)?;
+    }
+
+    let mut messages: Vec<CosmosMsg> = Vec::new();
+
     // If channel open init options are provided, open the channel.
     if let Some(channel_open_init_options) = msg.channel_open_init_options {
         let ica_channel_open_init_msg = new_ica_channel_open_init_cosmos_msg(
@@ -81,31 +86,21 @@ pub fn instantiate(
             channel_open_init_options.tx_encoding,
             channel_open_init_options.channel_ordering,
         );
-
-    // Only call the factory contract back and execute 'MapuserOutpost' if instructed to do so--i.e., callback object exists
-    let callback_factory_msg = if let Some(callback) = &msg.callback {
-
-        Some(CosmosMsg::Wasm(WasmMsg::Execute { 
-            contract_addr: callback.contract.clone(), 
-            msg: to_json_binary(&OutpostFactoryExecuteMsg::MapUserOutpost { 
-                outpost_owner: callback.outpost_owner.clone(), 
-            }).ok().expect("Failed to serialize callback_msg"), 
-            funds: vec![], 
-        }))
-    } else {
-        None
-    };
-
-    let mut messages: Vec<CosmosMsg> = Vec::new();
-    messages.push(ica_channel_open_init_msg);
-    if let Some(msg) = callback_factory_msg {
-        messages.push(msg)
+        messages.push(ica_channel_open_init_msg);
     }
-    
-    Ok(Response::new().add_messages(messages).add_event(event).add_attribute("outpost_address", env.contract.address.to_string()))  
-    } else {
-        Ok(Response::default())
+
+    // Only call back if instructed to do so--i.e., callback object exists
+    // The factory is asked to execute 'MapUserOutpost', other contracts execute the msg they nested in the callback
+    if let Some(callback) = &msg.callback {
+        messages.push(callback.instantiate_msg()?);
     }
+
+    // The instantiator gets the outpost address back as data, e.g. in the reply to its instantiate submessage
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_event(event)
+        .add_attribute("outpost_address", env.contract.address.to_string())
+        .set_data(to_json_binary(&env.contract.address)?))
 }
 
 /// Handles the execution of the contract.
@@ -125,44 +120,206 @@ pub fn execute(
             messages,
             packet_memo,
             timeout_seconds,
+            fill_signer_fields,
+        } => execute::send_cosmos_msgs(
+            deps,
+            env,
+            info,
+            messages,
+            packet_memo,
+            timeout_seconds,
+            fill_signer_fields.unwrap_or_default(),
+        ),
+        ExecuteMsg::SendJackalMsgs {
+            messages,
+            packet_memo,
+            timeout_seconds,
+        } => execute::send_jackal_msgs(deps, env, info, messages, packet_memo, timeout_seconds),
+        ExecuteMsg::Decommission {
+            cleanup_messages,
+            return_funds,
+            timeout_seconds,
+        } => execute::decommission(deps, env, info, cleanup_messages, return_funds, timeout_seconds),
+        ExecuteMsg::TransferFiles {
+            paths,
+            destination,
+            batch_size,
+            timeout_seconds,
+        } => execute::transfer_files(deps, env, info, paths, destination, batch_size, timeout_seconds),
+        ExecuteMsg::SetTransferChannel { channel_id } => execute::set_transfer_channel(deps, info, channel_id),
+        ExecuteMsg::FundIca { memo, timeout_seconds } => execute::fund_ica(deps, env, info, memo, timeout_seconds),
+        ExecuteMsg::WithdrawFromIca {
+            amount,
+            receiver,
+            timeout_seconds,
+        } => execute::withdraw_from_ica(deps, env, info, amount, receiver, timeout_seconds),
+        ExecuteMsg::BuyStorage {
+            bytes,
+            duration_days,
+            for_address,
+            payment_denom,
+            referral,
+            timeout_seconds,
+        } => execute::buy_storage(
+            deps,
+            env,
+            info,
+            bytes,
+            duration_days,
+            for_address,
+            payment_denom,
+            referral,
+            timeout_seconds,
+        ),
+        ExecuteMsg::QueryHost {
+            queries,
+            callback,
+            timeout_seconds,
+        } => execute::query_host(deps, env, info, queries, callback, timeout_seconds),
+        ExecuteMsg::Vote {
+            proposal_id,
+            option,
+            timeout_seconds,
         } => {
-            execute::send_cosmos_msgs(deps, env, info, messages, packet_memo, timeout_seconds)
-        },
+            let vote = CosmosMsg::Gov(cosmwasm_std::GovMsg::Vote { proposal_id, vote: option });
+            execute::send_gov_msg(deps, env, info, "vote", proposal_id, vote, timeout_seconds)
+        }
+        ExecuteMsg::VoteWeighted {
+            proposal_id,
+            options,
+            timeout_seconds,
+        } => execute::vote_weighted(deps, env, info, proposal_id, options, timeout_seconds),
+        ExecuteMsg::SubmitProposal {
+            title,
+            summary,
+            messages,
+            initial_deposit,
+            metadata,
+            timeout_seconds,
+        } => execute::submit_proposal(deps, env, info, title, summary, messages, initial_deposit, metadata, timeout_seconds),
+        ExecuteMsg::Deposit {
+            proposal_id,
+            amount,
+            timeout_seconds,
+        } => execute::deposit(deps, env, info, proposal_id, amount, timeout_seconds),
+        ExecuteMsg::GrantAuthorization {
+            grantee,
+            authorization,
+            expiration,
+            timeout_seconds,
+        } => execute::grant_authorization(deps, env, info, grantee, authorization, expiration, timeout_seconds),
+        ExecuteMsg::RevokeAuthorization {
+            grantee,
+            authorization,
+            timeout_seconds,
+        } => execute::revoke_authorization(deps, env, info, grantee, authorization, timeout_seconds),
+        ExecuteMsg::GrantAllowance {
+            grantee,
+            spend_limit,
+            expiration,
+            allowed_messages,
+            timeout_seconds,
+        } => execute::grant_allowance(deps, env, info, grantee, spend_limit, expiration, allowed_messages, timeout_seconds),
+        ExecuteMsg::RevokeAllowance {
+            grantee,
+            timeout_seconds,
+        } => execute::revoke_allowance(deps, env, info, grantee, timeout_seconds),
+        ExecuteMsg::SetHostAllowMessages { allow_messages } => {
+            execute::set_host_allow_messages(deps, env, info, allow_messages)
+        }
     }
 }
 
 /// Handles the query of the contract.
 #[cfg(not(feature = "no_exports"))]
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetContractState {} => to_json_binary(&query::state(deps)?),
         QueryMsg::GetChannel {} => to_json_binary(&query::channel(deps)?),
         QueryMsg::GetCallbackCounter {} => to_json_binary(&query::callback_counter(deps)?),
+        QueryMsg::GetDecommission {} => to_json_binary(&query::decommission(deps)?),
+        QueryMsg::GetFileTransfers { start_after, limit } => {
+            to_json_binary(&query::file_transfers(deps, start_after, limit)?)
+        }
+        QueryMsg::GetIcaFunding {} => to_json_binary(&crate::types::ica_funding::ica_funding(deps.storage)?),
+        QueryMsg::GetStoragePurchase { id } => to_json_binary(&state::STORAGE_PURCHASES.load(deps.storage, id)?),
+        QueryMsg::GetStoragePurchases { start_before, limit } => {
+            to_json_binary(&query::storage_purchases(deps, start_before, limit)?)
+        }
+        QueryMsg::GetHostParams {} => to_json_binary(&state::HOST_PARAMS.may_load(deps.storage)?),
+        QueryMsg::GetHostQueryResult { query } => {
+            to_json_binary(&state::HOST_QUERY_RESULTS.may_load(deps.storage, &query.key())?)
+        }
+        QueryMsg::GetGrants { grantee } => {
+            to_json_binary(&crate::types::grants::active_grants(deps.storage, grantee.as_deref(), env.block.time)?)
+        }
+        QueryMsg::GetHostQueryResults { start_after, limit } => {
+            to_json_binary(&query::host_query_results(deps, start_after, limit)?)
+        }
         QueryMsg::Ownership {} => to_json_binary(&query::get_owner(deps)?),
     }
 }
 
-/// Migrate contract if version is lower than current version
+/// Handles the replies to the submessages sent by the contract.
 #[cfg(not(feature = "no_exports"))]
 #[entry_point]
-pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    migrate::validate_semver(deps.as_ref())?;
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        keys::CALLBACK_NOTIFICATION_REPLY_ID => reply::callback_notification(msg),
+        keys::SEND_PACKET_REPLY_ID => reply::send_packet(deps, msg),
+        id => Err(ContractError::UnknownReplyId(id)),
+    }
+}
 
-    cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    // If state structure changed in any contract version in the way migration is needed, it
-    // should occur here
+/// Handles the ibc-hooks callbacks of the ICS-20 transfers funding the ICA.
+#[cfg(not(feature = "no_exports"))]
+#[entry_point]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck {
+            channel,
+            sequence,
+            success,
+            ..
+        }) => sudo::transfer_complete(deps, env, channel, sequence, success),
+        SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcTimeout { channel, sequence }) => {
+            sudo::transfer_complete(deps, env, channel, sequence, false)
+        }
+    }
+}
 
-    Ok(Response::default())
+/// Migrate contract if version is lower than current version
+#[cfg(not(feature = "no_exports"))]
+#[entry_point]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // If state structure changed in any contract version in the way migration is needed, add a
+    // step to 'migrate::MIGRATIONS' rather than migrating here
+    migrate::run(deps, &env)
 }
 
 mod execute {
-    use cosmwasm_std::{coin, coins, BankMsg, CosmosMsg, IbcMsg, IbcTimeout, IbcTimeoutBlock, StdResult};
+    use cosmwasm_std::{coin, coins, BankMsg, CosmosMsg, IbcTimeoutBlock};
     use prost::Message;
 
     use crate::{
-        ibc::types::{metadata::TxEncoding, packet::IcaPacketData, stargate::channel},
-        types::msg::options::ChannelOpenInitOptions,
+        ibc::types::{memo::{self, PacketMemo}, metadata::TxEncoding, packet::{IcaPacketData, DEFAULT_TIMEOUT_SECONDS}, stargate::{channel, transfer}},
+        types::decommission::{self, Decommission, DecommissionStage},
+        types::file_transfer::{self, FileTransfer, FileTransferStatus, DEFAULT_BATCH_SIZE, MAX_BATCH_SIZE},
+        types::gov,
+        types::grants,
+        types::host_params::{self, HostParams, HostParamsSource},
+        types::host_query::{self, HostQuery},
+        types::jackal_msg::JackalMsg,
+        types::signer,
+        types::storage::MSG_BUY_STORAGE_TYPE_URL,
+        types::ica_funding,
+        types::storage_purchase::{self, PurchaseStatus, StoragePurchase},
+        types::msg::{
+            options::{ChannelOpenInitOptions, FileDestination, ReturnFunds},
+            DirectoryEntry, OutpostFactoryQueryMsg,
+        },
+        types::state::{PacketKind, DECOMMISSION, FILE_TRANSFERS},
     };
 
     use cosmos_sdk_proto::cosmos::{bank::v1beta1::MsgSend, base::v1beta1::Coin};
@@ -171,7 +328,7 @@ mod execute {
     use super::*;
 
     /// Submits a stargate `MsgChannelOpenInit` to the chain.
-    /// Can only be called by the contract owner or a whitelisted address.
+    /// Can only be called by the contract owner or the admin.
     /// Only the contract owner can include the channel open init options.
     
     pub fn create_channel(
@@ -180,7 +337,15 @@ mod execute {
         info: MessageInfo,
         options: Option<ChannelOpenInitOptions>,
     ) -> Result<Response, ContractError> {
-        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+
+        if cw_ownable::assert_owner(deps.storage, &info.sender).is_err() {
+            // The admin--the factory for outposts it created--can only retry with the saved options
+            STATE.load(deps.storage)?.verify_admin(&info.sender)?;
+            if options.is_some() {
+                return Err(ContractError::Unauthorized {});
+            }
+        }
 
         let options = if let Some(new_options) = options {
             state::CHANNEL_OPEN_INIT_OPTIONS.save(deps.storage, &new_options)?;
@@ -214,6 +379,7 @@ mod execute {
         messages: Vec<CosmosMsg>,
         packet_memo: Option<String>,
         timeout_seconds: Option<u64>,
+        fill_signer_fields: bool,
         // Optional Size_of_data - v0.1.1 release?
     ) -> Result<Response, ContractError> {
 
@@ -221,9 +387,21 @@ mod execute {
         // NOTE: You could give ownership of the outpost to a non-factory contract, e.g., an nft minter
         // and the nft minter could call this function
         cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        if let Some(packet_memo) = &packet_memo {
+            memo::validate(packet_memo)?;
+        }
 
         let contract_state = STATE.load(deps.storage)?;
         let ica_info = contract_state.get_ica_info()?;
+        let messages = if fill_signer_fields {
+            messages
+                .into_iter()
+                .map(|msg| signer::fill_signer_fields(msg, &ica_info.ica_address))
+                .collect::<Result<_, _>>()?
+        } else {
+            messages
+        };
 
         let ica_packet = IcaPacketData::from_cosmos_msgs(
             messages,
@@ -231,88 +409,2440 @@ mod execute {
             packet_memo,
             &ica_info.ica_address,
         )?;
+        host_params::ensure_packet_allowed(deps.storage, &ica_packet)?;
         let send_packet_msg = ica_packet.to_ibc_msg(&env, ica_info.channel_id, timeout_seconds)?;
 
         Ok(Response::default().add_message(send_packet_msg))
 
     }
-}
 
+    /// Sends an array of [`JackalMsg`] to the ICA host.
+    pub fn send_jackal_msgs(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        messages: Vec<JackalMsg>,
+        packet_memo: Option<String>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        if let Some(packet_memo) = &packet_memo {
+            memo::validate(packet_memo)?;
+        }
 
+        for msg in &messages {
+            msg.validate()?;
+        }
 
-mod query {
-    use std::error::Error;
+        let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+        if !matches!(ica_info.encoding, crate::ibc::types::metadata::TxEncoding::Protobuf) {
+            return Err(ContractError::UnsupportedPacketEncoding(ica_info.encoding.to_string()));
+        }
+        let notifications: Vec<_> = messages
+            .iter()
+            .flat_map(|msg| msg.notifications(&ica_info.ica_address))
+            .map(|msg| msg.to_any(&ica_info.ica_address))
+            .collect();
+        let messages = messages.into_iter().map(|msg| msg.to_any(&ica_info.ica_address)).collect();
+        let ica_packet = IcaPacketData::from_proto_anys(messages, packet_memo);
+        host_params::ensure_packet_allowed(deps.storage, &ica_packet)?;
+        let send_packet_msg = ica_packet.to_ibc_msg(&env, ica_info.channel_id.clone(), timeout_seconds)?;
+        let mut response = Response::default().add_message(send_packet_msg);
 
-    use cosmwasm_std::StdError;
+        // The host rejects notifying an account without a counter, which mustn't undo sharing the files
+        if !notifications.is_empty() {
+            let notify_packet = IcaPacketData::from_proto_anys(notifications, None);
+            host_params::ensure_packet_allowed(deps.storage, &notify_packet)?;
+            response = response.add_message(notify_packet.to_ibc_msg(&env, ica_info.channel_id, timeout_seconds)?);
+        }
 
-    use super::*;
+        Ok(response)
+    }
 
-    /// Returns the saved contract state.
-    pub fn state(deps: Deps) -> StdResult<ContractState> {
-        STATE.load(deps.storage)
+    /// Starts retiring the outpost. Can only be called by the owner.
+    /// See [`crate::types::decommission`] for the whole process.
+    pub fn decommission(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        cleanup_messages: Vec<CosmosMsg>,
+        return_funds: Option<ReturnFunds>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+
+        let mut messages = cleanup_messages;
+        if let Some(return_funds) = return_funds {
+            // A failed transfer would leave the funds on an ICA no one controls once the channel closes
+            if return_funds.amount.is_empty() {
+                return Err(ContractError::NoFunds {});
+            }
+            let receiver = match return_funds.receiver {
+                Some(receiver) => deps.api.addr_validate(&receiver)?.to_string(),
+                None => info.sender.to_string(),
+            };
+            messages.extend(ica_funding::host_transfers(
+                &env,
+                &return_funds.transfer_channel_id,
+                return_funds.amount,
+                &receiver,
+                timeout_seconds,
+            ));
+        }
+
+        let channel_open = CHANNEL_STATE
+            .may_load(deps.storage)?
+            .is_some_and(|channel_state| channel_state.is_open());
+
+        let mut event = Event::new("OUTPOST:decommission");
+        event = event.add_attribute("info.sender", info.sender.to_string());
+
+        if !messages.is_empty() {
+            // The ICA needs the channel to clean up
+            if !channel_open {
+                return Err(ContractError::ChannelNotOpen {});
+            }
+            let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+            let ica_packet = IcaPacketData::from_cosmos_msgs(messages, &ica_info.encoding, None, &ica_info.ica_address)?;
+            host_params::ensure_packet_allowed(deps.storage, &ica_packet)?;
+            let send_packet_msg = ica_packet.to_ibc_msg(&env, ica_info.channel_id, timeout_seconds)?;
+
+            DECOMMISSION.save(deps.storage, &Decommission::new(DecommissionStage::CleaningUp, env.block.time))?;
+            // The channel is closed once this packet is acknowledged, see the packet ledger
+            let send_packet_msg = PacketKind::Decommission.track(deps.storage, send_packet_msg)?;
+
+            event = event.add_attribute("stage", "cleaning_up");
+            return Ok(Response::new()
+                .add_submessage(send_packet_msg)
+                .add_event(event));
+        }
+
+        DECOMMISSION.save(deps.storage, &Decommission::new(DecommissionStage::ClosingChannel, env.block.time))?;
+        if channel_open {
+            let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+            let close_channel_msg = decommission::close_channel(deps.storage, ica_info.channel_id)?;
+
+            event = event.add_attribute("stage", "closing_channel");
+            return Ok(Response::new().add_message(close_channel_msg).add_event(event));
+        }
+
+        // Nothing to clean up and no channel to close
+        let notification = decommission::finish(deps.storage)?;
+
+        event = event.add_attribute("stage", "decommissioned");
+        Ok(Response::new().add_submessages(notification).add_event(event))
     }
 
-    /// Returns the saved channel state if it exists.
-    pub fn channel(deps: Deps) -> StdResult<ChannelState> {
-        CHANNEL_STATE.load(deps.storage)
+    /// Hands the given files over to another ICA, one packet per batch. Can only be called by the owner.
+    /// See [`crate::types::file_transfer`] for how the transfers are tracked.
+    #[allow(clippy::too_many_arguments)]
+    pub fn transfer_files(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        paths: Vec<String>,
+        destination: FileDestination,
+        batch_size: Option<u32>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+
+        if paths.is_empty() {
+            return Err(ContractError::NoFilesToTransfer {});
+        }
+        let batch_size = batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+        if batch_size == 0 || batch_size > MAX_BATCH_SIZE {
+            return Err(ContractError::InvalidBatchSize { max: MAX_BATCH_SIZE });
+        }
+
+        let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+        if !matches!(ica_info.encoding, crate::ibc::types::metadata::TxEncoding::Protobuf) {
+            return Err(ContractError::UnsupportedPacketEncoding(ica_info.encoding.to_string()));
+        }
+        let new_owner = resolve_file_destination(deps.as_ref(), destination)?;
+        // The files can't be taken back from an account nobody controls
+        grants::validate_grantee(&new_owner, &ica_info.ica_address)?;
+
+        let mut paths = paths;
+        paths.sort();
+        paths.dedup();
+
+        let mut response = Response::new();
+        for batch in paths.chunks(batch_size as usize) {
+            let mut messages = Vec::with_capacity(batch.len());
+            for path in batch {
+                file_transfer::ensure_transferable(deps.storage, path)?;
+                FILE_TRANSFERS.save(
+                    deps.storage,
+                    path,
+                    &FileTransfer {
+                        path: path.clone(),
+                        new_owner: new_owner.clone(),
+                        status: FileTransferStatus::Pending,
+                    },
+                )?;
+                messages.push(file_transfer::change_owner_msg(path, &ica_info.ica_address, &new_owner));
+            }
+
+            let ica_packet = IcaPacketData::from_proto_anys(messages, None);
+            host_params::ensure_packet_allowed(deps.storage, &ica_packet)?;
+            let send_packet_msg = ica_packet.to_ibc_msg(&env, ica_info.channel_id.clone(), timeout_seconds)?;
+            let kind = PacketKind::FileTransfer { paths: batch.to_vec() };
+            response = response.add_submessage(kind.track(deps.storage, send_packet_msg)?);
+        }
+
+        let event = Event::new("OUTPOST:transfer_files")
+            .add_attribute("info.sender", info.sender.to_string())
+            .add_attribute("new_owner", new_owner)
+            .add_attribute("files", paths.len().to_string());
+
+        Ok(response.add_event(event))
     }
 
-    /// Returns the saved callback counter.
-    pub fn callback_counter(deps: Deps) -> StdResult<CallbackCounter> {
-        CALLBACK_COUNTER.load(deps.storage)
+    /// Returns the ICA address receiving the files.
+    fn resolve_file_destination(deps: Deps, destination: FileDestination) -> Result<String, ContractError> {
+        match destination {
+            FileDestination::Ica { address } => Ok(address),
+            FileDestination::Outpost { address } => {
+                let contract_state: ContractState = deps
+                    .querier
+                    .query_wasm_smart(address, &QueryMsg::GetContractState {})?;
+                Ok(contract_state.get_ica_info()?.ica_address)
+            }
+            FileDestination::User {
+                user_address,
+                outpost_name,
+            } => {
+                let factory = CALLBACK
+                    .may_load(deps.storage)?
+                    .filter(crate::types::callback::Callback::is_factory)
+                    .ok_or(ContractError::NoFactory {})?;
+                let entry: DirectoryEntry = deps.querier.query_wasm_smart(
+                    factory.contract,
+                    &OutpostFactoryQueryMsg::GetDirectoryEntryByUser {
+                        user_address,
+                        outpost_name,
+                    },
+                )?;
+                Ok(entry.ica_address)
+            }
+        }
     }
 
-    /// Return the outpost owner
-    pub fn get_owner(deps: Deps) -> StdResult<String> {
-        let ownership = cw_ownable::get_ownership(deps.storage)?;
+    /// Sets the ICS-20 channel the ICA is funded over. Can only be called by the owner.
+    pub fn set_transfer_channel(
+        deps: DepsMut,
+        info: MessageInfo,
+        channel_id: String,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        state::TRANSFER_CHANNEL.save(deps.storage, &channel_id)?;
 
-        if let Some(owner) = ownership.owner {
-            Ok(owner.to_string())
-        } else {
-            Err(StdError::generic_err("No owner found"))
+        let event = Event::new("OUTPOST:set_transfer_channel").add_attribute("channel_id", channel_id);
+        Ok(Response::new().add_event(event))
+    }
+
+    /// Sends the attached funds to the ICA over ICS-20, one transfer per coin.
+    /// See [`crate::types::ica_funding`] for how failed transfers are refunded.
+    pub fn fund_ica(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        memo: Option<PacketMemo>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        decommission::ensure_active(deps.storage)?;
+        if info.funds.is_empty() {
+            return Err(ContractError::NoFunds {});
+        }
+
+        let channel_id = state::TRANSFER_CHANNEL
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoTransferChannel {})?;
+        let ica_address = STATE.load(deps.storage)?.get_ica_info()?.ica_address;
+        let timeout = env.block.time.plus_seconds(timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS));
+        let memo = ica_funding::callback_memo(&env.contract.address, memo)?;
+
+        let mut response = Response::new();
+        for amount in info.funds {
+            let transfer_msg = transfer::new_transfer_cosmos_msg(
+                env.contract.address.to_string(),
+                channel_id.clone(),
+                amount.clone(),
+                ica_address.clone(),
+                timeout,
+                memo.clone(),
+            );
+            let kind = PacketKind::FundIca {
+                sender: info.sender.to_string(),
+                amount,
+            };
+            response = response.add_submessage(kind.track(deps.storage, transfer_msg)?);
         }
+
+        let event = Event::new("OUTPOST:fund_ica")
+            .add_attribute("info.sender", info.sender.to_string())
+            .add_attribute("ica_address", ica_address)
+            .add_attribute("channel_id", channel_id);
+        Ok(response.add_event(event))
     }
-}
 
-mod migrate {
-    use super::{keys, state, ContractError, Deps};
+    /// Sends the ICA's funds back to this chain over the [transfer channel](ExecuteMsg::SetTransferChannel).
+    /// Can only be called by the owner. The receiver defaults to the owner.
+    pub fn withdraw_from_ica(
+        mut deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        amount: Vec<cosmwasm_std::Coin>,
+        receiver: Option<String>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        if amount.is_empty() {
+            return Err(ContractError::NoFunds {});
+        }
 
-    /// Validate that the contract version is semver compliant
-    /// and greater than the previous version.
-    pub fn validate_semver(deps: Deps) -> Result<(), ContractError> {
-        let prev_cw2_version = cw2::get_contract_version(deps.storage)?;
-        if prev_cw2_version.contract != keys::CONTRACT_NAME {
-            return Err(ContractError::InvalidMigrationVersion {
-                expected: keys::CONTRACT_NAME.to_string(),
-                actual: prev_cw2_version.contract,
-            });
+        host_params::ensure_allowed(deps.storage, [transfer::MSG_TRANSFER_TYPE_URL])?;
+
+        let receiver = match receiver {
+            Some(receiver) => deps.api.addr_validate(&receiver)?.to_string(),
+            None => info.sender.to_string(),
+        };
+        let host_channel_id = ica_funding::host_transfer_channel(deps.as_ref())?;
+        let withdrawal_msg =
+            ica_funding::withdrawal_packet(deps.branch(), &env, &host_channel_id, receiver.clone(), amount, None, timeout_seconds)?;
+
+        let event = Event::new("OUTPOST:withdraw_from_ica")
+            .add_attribute("info.sender", info.sender.to_string())
+            .add_attribute("receiver", receiver)
+            .add_attribute("host_channel_id", host_channel_id);
+        Ok(Response::new().add_submessage(withdrawal_msg).add_event(event))
+    }
+
+    /// Sends the attached funds to the ICA, which then buys a storage plan with them. Can only be called by the owner.
+    /// See [`crate::types::storage_purchase`] for the whole process.
+    #[allow(clippy::too_many_arguments)]
+    pub fn buy_storage(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        bytes: u64,
+        duration_days: u64,
+        for_address: Option<String>,
+        payment_denom: Option<String>,
+        referral: Option<String>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        let [amount] = info.funds.as_slice() else {
+            return Err(ContractError::InvalidFunds {});
+        };
+        if bytes == 0 || duration_days == 0 {
+            return Err(ContractError::InvalidStoragePlan {});
         }
+        // The ICA sends the funds back with a transfer if the purchase fails
+        host_params::ensure_allowed(deps.storage, [MSG_BUY_STORAGE_TYPE_URL, transfer::MSG_TRANSFER_TYPE_URL])?;
 
-        let version: semver::Version = keys::CONTRACT_VERSION.parse()?;
-        let prev_version: semver::Version = prev_cw2_version.version.parse()?;
-        if prev_version >= version {
-            return Err(ContractError::InvalidMigrationVersion {
-                expected: format!("> {prev_version}"),
-                actual: keys::CONTRACT_VERSION.to_string(),
-            });
+        let channel_id = state::TRANSFER_CHANNEL
+            .may_load(deps.storage)?
+            .ok_or(ContractError::NoTransferChannel {})?;
+        let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+        // 'MsgBuyStorage' is only sent once the funds arrived, check it can be before taking them
+        if !matches!(ica_info.encoding, crate::ibc::types::metadata::TxEncoding::Protobuf) {
+            return Err(ContractError::UnsupportedPacketEncoding(ica_info.encoding.to_string()));
         }
-        Ok(())
+        let ica_address = ica_info.ica_address;
+        // The plan is paid for, and refunded, with the coins the transfer delivers to the ICA
+        let host_denom = ica_funding::host_denom(deps.as_ref(), &amount.denom)?;
+        if payment_denom.as_ref().is_some_and(|payment_denom| *payment_denom != host_denom) {
+            return Err(ContractError::InvalidPaymentDenom { expected: host_denom, found: payment_denom.unwrap_or_default() });
+        }
+
+        let purchase = storage_purchase::create(
+            deps.storage,
+            StoragePurchase {
+                id: 0,
+                buyer: info.sender.to_string(),
+                amount: amount.clone(),
+                for_address: for_address.unwrap_or_else(|| ica_address.clone()),
+                bytes,
+                duration_days,
+                payment_denom: host_denom,
+                referral,
+                timeout_seconds,
+                status: PurchaseStatus::Funding,
+            },
+        )?;
+        // Only the purchase is checked here, the plan itself is validated by the host
+        purchase.buy_storage_msg(&ica_address)?;
+
+        let transfer_msg = transfer::new_transfer_cosmos_msg(
+            env.contract.address.to_string(),
+            channel_id,
+            amount.clone(),
+            ica_address,
+            env.block.time.plus_seconds(timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS)),
+            ica_funding::callback_memo(&env.contract.address, None)?,
+        );
+        let transfer_msg = PacketKind::PurchaseFunding { id: purchase.id }.track(deps.storage, transfer_msg)?;
+
+        let event = Event::new("OUTPOST:buy_storage")
+            .add_attribute("info.sender", info.sender.to_string())
+            .add_attribute("purchase_id", purchase.id.to_string())
+            .add_attribute("for_address", purchase.for_address);
+        Ok(Response::new()
+            .add_submessage(transfer_msg)
+            .add_event(event)
+            .set_data(to_json_binary(&purchase.id)?))
     }
 
-    /// Validate that the channel encoding is protobuf if set.
-    pub fn validate_channel_encoding(deps: Deps) -> Result<(), ContractError> {
-        // Reject the migration if the channel encoding is not protobuf
-        if let Some(ica_info) = state::STATE.load(deps.storage)?.ica_info {
-            if !matches!(
-                ica_info.encoding,
-                crate::ibc::types::metadata::TxEncoding::Protobuf
-            ) {
-                return Err(ContractError::UnsupportedPacketEncoding(
-                    ica_info.encoding.to_string(),
-                ));
-            }
+    /// Has the ICA query Jackal's state with `MsgModuleQuerySafe`. Can only be called by the owner.
+    /// See [`crate::types::host_query`] for how the answers are handled.
+    pub fn query_host(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        queries: Vec<HostQuery>,
+        callback: Option<String>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        if queries.is_empty() || queries.len() > host_query::MAX_QUERIES {
+            return Err(ContractError::InvalidHostQueryCount { max: host_query::MAX_QUERIES });
         }
+        let callback = callback
+            .map(|contract| deps.api.addr_validate(&contract).map(|addr| addr.to_string()))
+            .transpose()?;
 
-        Ok(())
+        let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+        if !matches!(ica_info.encoding, crate::ibc::types::metadata::TxEncoding::Protobuf) {
+            return Err(ContractError::UnsupportedPacketEncoding(ica_info.encoding.to_string()));
+        }
+        let query_msg = host_query::query_msg(&ica_info.ica_address, &queries);
+        let ica_packet = IcaPacketData::from_proto_anys(vec![query_msg], None);
+        host_params::ensure_packet_allowed(deps.storage, &ica_packet)?;
+        let send_packet_msg = ica_packet.to_ibc_msg(&env, ica_info.channel_id, timeout_seconds)?;
+
+        let event = Event::new("OUTPOST:query_host")
+            .add_attribute("info.sender", info.sender.to_string())
+            .add_attribute("queries", queries.len().to_string());
+        let send_packet_msg = PacketKind::HostQuery { queries, callback }.track(deps.storage, send_packet_msg)?;
+
+        Ok(Response::new().add_submessage(send_packet_msg).add_event(event))
+    }
+
+    /// Sends a gov message from the ICA. Can only be called by the owner.
+    #[allow(clippy::too_many_arguments)]
+    pub fn send_gov_msg(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        action: &str,
+        proposal_id: u64,
+        msg: CosmosMsg,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+
+        let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+        let ica_packet = IcaPacketData::from_cosmos_msgs(vec![msg], &ica_info.encoding, None, &ica_info.ica_address)?;
+        send_gov_packet(deps, env, info, action, proposal_id, ica_info.channel_id, ica_packet, timeout_seconds)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn send_gov_packet(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        action: &str,
+        proposal_id: u64,
+        channel_id: String,
+        ica_packet: IcaPacketData,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        host_params::ensure_packet_allowed(deps.storage, &ica_packet)?;
+        let send_packet_msg = ica_packet.to_ibc_msg(&env, channel_id, timeout_seconds)?;
+
+        let event = Event::new(format!("OUTPOST:{action}"))
+            .add_attribute("info.sender", info.sender.to_string())
+            .add_attribute("proposal_id", proposal_id.to_string());
+        Ok(Response::new().add_message(send_packet_msg).add_event(event))
+    }
+
+    /// Splits the ICA's vote on a proposal. Can only be called by the owner.
+    pub fn vote_weighted(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        proposal_id: u64,
+        options: Vec<cosmwasm_std::WeightedVoteOption>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        gov::validate_weighted_vote(&options)?;
+        let vote = CosmosMsg::Gov(cosmwasm_std::GovMsg::VoteWeighted { proposal_id, options });
+        send_gov_msg(deps, env, info, "vote_weighted", proposal_id, vote, timeout_seconds)
+    }
+
+    /// Submits a proposal from the ICA. Can only be called by the owner, on a protobuf channel.
+    /// The proposal id is only known on the host, so `proposal_id` is 0 in the event.
+    #[allow(clippy::too_many_arguments)]
+    pub fn submit_proposal(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        title: String,
+        summary: String,
+        messages: Vec<gov::ProposalMsg>,
+        initial_deposit: Vec<cosmwasm_std::Coin>,
+        metadata: Option<String>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+
+        let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+        if !matches!(ica_info.encoding, crate::ibc::types::metadata::TxEncoding::Protobuf) {
+            return Err(ContractError::UnsupportedPacketEncoding(ica_info.encoding.to_string()));
+        }
+        let msg = gov::submit_proposal_msg(&ica_info.ica_address, title, summary, messages, initial_deposit, metadata);
+        let ica_packet = IcaPacketData::from_proto_anys(vec![msg], None);
+        send_gov_packet(deps, env, info, "submit_proposal", 0, ica_info.channel_id, ica_packet, timeout_seconds)
+    }
+
+    /// Adds to a proposal's deposit from the ICA. Can only be called by the owner, on a protobuf channel.
+    pub fn deposit(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        proposal_id: u64,
+        amount: Vec<cosmwasm_std::Coin>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        if amount.is_empty() {
+            return Err(ContractError::NoFunds {});
+        }
+
+        let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+        if !matches!(ica_info.encoding, crate::ibc::types::metadata::TxEncoding::Protobuf) {
+            return Err(ContractError::UnsupportedPacketEncoding(ica_info.encoding.to_string()));
+        }
+        let msg = gov::deposit_msg(&ica_info.ica_address, proposal_id, amount);
+        let ica_packet = IcaPacketData::from_proto_anys(vec![msg], None);
+        send_gov_packet(deps, env, info, "deposit", proposal_id, ica_info.channel_id, ica_packet, timeout_seconds)
+    }
+
+    /// Sends authz or feegrant messages from the ICA, and records the change once the host acknowledges them.
+    #[allow(clippy::too_many_arguments)]
+    fn send_grant_msgs(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        action: &str,
+        grantee: &str,
+        msgs: Vec<Any>,
+        change: grants::GrantChange,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+        if !matches!(ica_info.encoding, crate::ibc::types::metadata::TxEncoding::Protobuf) {
+            return Err(ContractError::UnsupportedPacketEncoding(ica_info.encoding.to_string()));
+        }
+        let ica_packet = IcaPacketData::from_proto_anys(msgs, None);
+        host_params::ensure_packet_allowed(deps.storage, &ica_packet)?;
+        let send_packet_msg = ica_packet.to_ibc_msg(&env, ica_info.channel_id, timeout_seconds)?;
+        let send_packet_msg = PacketKind::Grant { change }.track(deps.storage, send_packet_msg)?;
+
+        let event = Event::new(format!("OUTPOST:{action}"))
+            .add_attribute("info.sender", info.sender.to_string())
+            .add_attribute("grantee", grantee);
+        Ok(Response::new().add_submessage(send_packet_msg).add_event(event))
+    }
+
+    /// Issues authz grants from the ICA. Can only be called by the owner.
+    pub fn grant_authorization(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        grantee: String,
+        authorization: grants::Authorization,
+        expiration: Option<cosmwasm_std::Timestamp>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        let ica_address = STATE.load(deps.storage)?.get_ica_info()?.ica_address;
+        grants::validate_grantee(&grantee, &ica_address)?;
+        if expiration.is_some_and(|expiration| expiration <= env.block.time) {
+            return Err(ContractError::InvalidExpiration {});
+        }
+
+        let grants: Vec<_> = authorization
+            .msg_type_urls()
+            .into_iter()
+            .map(|msg_type_url| grants::AuthzGrant {
+                grantee: grantee.clone(),
+                msg_type_url,
+                expiration,
+                granted_at: env.block.time,
+            })
+            .collect();
+        let msgs = grants::grant_msgs(&ica_address, &grants);
+        let change = grants::GrantChange::Grant { grants };
+        send_grant_msgs(deps, env, info, "grant_authorization", &grantee, msgs, change, timeout_seconds)
+    }
+
+    /// Revokes authz grants issued by the ICA. Can only be called by the owner.
+    pub fn revoke_authorization(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        grantee: String,
+        authorization: grants::Authorization,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        let ica_address = STATE.load(deps.storage)?.get_ica_info()?.ica_address;
+        grants::validate_grantee(&grantee, &ica_address)?;
+
+        let msg_type_urls = authorization.msg_type_urls();
+        let msgs = grants::revoke_msgs(&ica_address, &grantee, &msg_type_urls);
+        let change = grants::GrantChange::Revoke { grantee: grantee.clone(), msg_type_urls };
+        send_grant_msgs(deps, env, info, "revoke_authorization", &grantee, msgs, change, timeout_seconds)
+    }
+
+    /// Issues a feegrant allowance from the ICA. Can only be called by the owner.
+    /// The host rejects a grant to a grantee that already has an allowance, so it's revoked first.
+    #[allow(clippy::too_many_arguments)]
+    pub fn grant_allowance(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        grantee: String,
+        spend_limit: Vec<cosmwasm_std::Coin>,
+        expiration: Option<cosmwasm_std::Timestamp>,
+        allowed_messages: Vec<String>,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        let ica_address = STATE.load(deps.storage)?.get_ica_info()?.ica_address;
+        grants::validate_grantee(&grantee, &ica_address)?;
+        if expiration.is_some_and(|expiration| expiration <= env.block.time) {
+            return Err(ContractError::InvalidExpiration {});
+        }
+
+        let allowance = grants::FeeAllowance {
+            grantee: grantee.clone(),
+            spend_limit,
+            expiration,
+            allowed_messages,
+            granted_at: env.block.time,
+        };
+        let mut msgs = vec![];
+        if state::FEE_ALLOWANCES.has(deps.storage, &grantee) {
+            msgs.push(grants::revoke_allowance_msg(&ica_address, &grantee));
+        }
+        msgs.push(grants::grant_allowance_msg(&ica_address, &allowance));
+        let change = grants::GrantChange::GrantAllowance { allowance };
+        send_grant_msgs(deps, env, info, "grant_allowance", &grantee, msgs, change, timeout_seconds)
+    }
+
+    /// Revokes a feegrant allowance issued by the ICA. Can only be called by the owner.
+    pub fn revoke_allowance(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        grantee: String,
+        timeout_seconds: Option<u64>,
+    ) -> Result<Response, ContractError> {
+        cw_ownable::assert_owner(deps.storage, &info.sender)?;
+        decommission::ensure_active(deps.storage)?;
+        let ica_address = STATE.load(deps.storage)?.get_ica_info()?.ica_address;
+        grants::validate_grantee(&grantee, &ica_address)?;
+
+        let msgs = vec![grants::revoke_allowance_msg(&ica_address, &grantee)];
+        let change = grants::GrantChange::RevokeAllowance { grantee: grantee.clone() };
+        send_grant_msgs(deps, env, info, "revoke_allowance", &grantee, msgs, change, timeout_seconds)
+    }
+
+    /// Sets the cached list of the messages the host allows. Can only be called by the owner or the admin.
+    pub fn set_host_allow_messages(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        allow_messages: Option<Vec<String>>,
+    ) -> Result<Response, ContractError> {
+        if cw_ownable::assert_owner(deps.storage, &info.sender).is_err() {
+            STATE.load(deps.storage)?.verify_admin(&info.sender)?;
+        }
+
+        let mut event = Event::new("OUTPOST:set_host_allow_messages").add_attribute("info.sender", info.sender.to_string());
+        match allow_messages {
+            Some(allow_messages) => {
+                event = event.add_attribute("allow_messages", allow_messages.join(","));
+                let params = HostParams {
+                    host_enabled: true,
+                    allow_messages,
+                    source: HostParamsSource::Admin { sender: info.sender.to_string() },
+                    updated_at: env.block.time,
+                };
+                state::HOST_PARAMS.save(deps.storage, &params)?;
+            }
+            None => state::HOST_PARAMS.remove(deps.storage),
+        }
+
+        Ok(Response::new().add_event(event))
+    }
+}
+
+
+
+mod query {
+    use std::error::Error;
+
+    use cosmwasm_std::StdError;
+
+    use super::*;
+
+    /// Returns the saved contract state.
+    pub fn state(deps: Deps) -> StdResult<ContractState> {
+        STATE.load(deps.storage)
+    }
+
+    /// Returns the saved channel state if it exists.
+    pub fn channel(deps: Deps) -> StdResult<ChannelState> {
+        CHANNEL_STATE.load(deps.storage)
+    }
+
+    /// Returns the saved callback counter.
+    pub fn callback_counter(deps: Deps) -> StdResult<CallbackCounter> {
+        CALLBACK_COUNTER.load(deps.storage)
+    }
+
+    /// Returns the progress of the decommissioning, if it was requested.
+    pub fn decommission(deps: Deps) -> StdResult<Option<crate::types::decommission::Decommission>> {
+        state::DECOMMISSION.may_load(deps.storage)
+    }
+
+    /// Returns the files handed over to other ICAs, ordered by path.
+    pub fn file_transfers(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<crate::types::file_transfer::FileTransfer>> {
+        let limit = limit.unwrap_or(30).min(100) as usize;
+        let start = start_after.as_deref().map(cw_storage_plus::Bound::exclusive);
+        state::FILE_TRANSFERS
+            .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+            .take(limit)
+            .map(|entry| entry.map(|(_, transfer)| transfer))
+            .collect()
+    }
+
+    /// Returns the storage purchases, newest first.
+    pub fn storage_purchases(
+        deps: Deps,
+        start_before: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<crate::types::storage_purchase::StoragePurchase>> {
+        let limit = limit.unwrap_or(30).min(100) as usize;
+        let end = start_before.map(cw_storage_plus::Bound::exclusive);
+        state::STORAGE_PURCHASES
+            .range(deps.storage, None, end, cosmwasm_std::Order::Descending)
+            .take(limit)
+            .map(|entry| entry.map(|(_, purchase)| purchase))
+            .collect()
+    }
+
+    /// Returns the latest answers to host queries, ordered by query key.
+    pub fn host_query_results(
+        deps: Deps,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<crate::types::host_query::HostQueryResult>> {
+        let limit = limit.unwrap_or(30).min(100) as usize;
+        let start = start_after.as_deref().map(cw_storage_plus::Bound::exclusive);
+        state::HOST_QUERY_RESULTS
+            .range(deps.storage, start, None, cosmwasm_std::Order::Ascending)
+            .take(limit)
+            .map(|entry| entry.map(|(_, result)| result))
+            .collect()
+    }
+
+    /// Return the outpost owner
+    pub fn get_owner(deps: Deps) -> StdResult<String> {
+        let ownership = cw_ownable::get_ownership(deps.storage)?;
+
+        if let Some(owner) = ownership.owner {
+            Ok(owner.to_string())
+        } else {
+            Err(StdError::generic_err("No owner found"))
+        }
+    }
+}
+
+mod reply {
+    use cosmwasm_std::SubMsgResult;
+    use prost::Message;
+
+    use crate::ibc::types::{packet::send_response::MsgIbcSendResponse, stargate::transfer::MsgTransferResponse};
+
+    use super::*;
+
+    /// Records a failed callback notification. The notification is best effort, so the error is not propagated.
+    pub fn callback_notification(msg: Reply) -> Result<Response, ContractError> {
+        let SubMsgResult::Err(err) = msg.result else {
+            return Ok(Response::default());
+        };
+
+        Ok(Response::new().add_attribute("callback_notification_error", err))
+    }
+
+    /// Records the sequence of a tracked packet, so its acknowledgement can be matched to what it was for.
+    /// ICS-20 transfers are recorded on the transfer channel, every other packet on the ICA channel.
+    pub fn send_packet(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+        let kind = state::PENDING_PACKETS
+            .pop_front(deps.storage)?
+            .ok_or_else(|| cosmwasm_std::StdError::not_found("pending packet"))?;
+
+        let data = msg
+            .result
+            .into_result()
+            .map_err(cosmwasm_std::StdError::generic_err)?
+            .data
+            .ok_or(ContractError::MissingPacketSequence {})?;
+        let (channel_id, sequence) = match kind {
+            _ if kind.is_transfer() => (
+                state::TRANSFER_CHANNEL.load(deps.storage)?,
+                MsgTransferResponse::decode(data.as_slice()).map(|response| response.sequence),
+            ),
+            _ => (
+                STATE.load(deps.storage)?.get_ica_info()?.channel_id,
+                MsgIbcSendResponse::decode(data.as_slice()).map(|response| response.sequence),
+            ),
+        };
+        let sequence = sequence.map_err(|_| ContractError::MissingPacketSequence {})?;
+        state::PACKET_LEDGER.save(deps.storage, (&channel_id, sequence), &kind)?;
+
+        Ok(Response::new().add_attribute("packet_sequence", sequence.to_string()))
+    }
+}
+
+mod sudo {
+    use crate::types::{ica_funding, state::PacketKind, storage_purchase};
+
+    use super::*;
+
+    /// Settles a transfer sent from this chain, refunding its sender if it failed.
+    pub fn transfer_complete(
+        deps: DepsMut,
+        env: Env,
+        channel_id: String,
+        sequence: u64,
+        success: bool,
+    ) -> Result<Response, ContractError> {
+        let key = (channel_id.as_str(), sequence);
+        if let Some(PacketKind::PurchaseFunding { id }) = state::PACKET_LEDGER.may_load(deps.storage, key)? {
+            state::PACKET_LEDGER.remove(deps.storage, key);
+            return purchase_funded(deps, env, id, success);
+        }
+
+        let Some((transfer, refund)) = ica_funding::on_transfer_result(deps.storage, &channel_id, sequence, success)? else {
+            return Ok(Response::default());
+        };
+
+        let event = Event::new("OUTPOST:fund_ica_complete")
+            .add_attribute("channel_id", channel_id)
+            .add_attribute("sequence", sequence.to_string())
+            .add_attribute("sender", transfer.sender)
+            .add_attribute("amount", transfer.amount.to_string())
+            .add_attribute("success", success.to_string());
+        Ok(Response::new().add_messages(refund).add_event(event))
+    }
+
+    /// Buys the storage plan once its funds arrived on the ICA, or refunds the buyer if they didn't.
+    fn purchase_funded(deps: DepsMut, env: Env, id: u64, success: bool) -> Result<Response, ContractError> {
+        let (purchase, msg) = storage_purchase::on_funding_result(deps, &env, id, success)?;
+
+        let event = Event::new("OUTPOST:storage_purchase")
+            .add_attribute("purchase_id", id.to_string())
+            .add_attribute("status", purchase.status.name());
+        Ok(Response::new().add_submessages(msg).add_event(event))
+    }
+}
+
+mod migrate {
+    use cosmwasm_std::Attribute;
+
+    use super::{keys, state, ContractError, Deps, DepsMut, Env, Response};
+
+    /// A state migration introduced by a release of this contract.
+    struct MigrationStep {
+        /// The release that introduced the state change. The step runs for any contract older than this.
+        version: semver::Version,
+        /// Short description of the step, reported in the migration response.
+        description: &'static str,
+        /// Performs the migration and returns attributes describing what changed.
+        run: fn(DepsMut, &Env) -> Result<Vec<Attribute>, ContractError>,
+    }
+
+    /// Every state migration, ordered by version. Contracts skipping releases run each missed step in turn.
+    const MIGRATIONS: &[MigrationStep] = &[
+        MigrationStep {
+            version: semver::Version::new(0, 1, 1),
+            description: "backfill channel open init flag and callback counter",
+            run: v0_1_1::migrate,
+        },
+        MigrationStep {
+            version: semver::Version::new(0, 2, 0),
+            description: "backfill channel close init flag",
+            run: v0_2_0::migrate,
+        },
+    ];
+
+    /// Runs every migration step newer than the stored cw2 version, checks the resulting state,
+    /// and records the new version.
+    pub fn run(mut deps: DepsMut, env: &Env) -> Result<Response, ContractError> {
+        let prev_version = validate_semver(deps.as_ref())?;
+
+        let mut response = Response::new()
+            .add_attribute("previous_version", prev_version.to_string())
+            .add_attribute("new_version", keys::CONTRACT_VERSION);
+
+        for step in MIGRATIONS.iter().filter(|step| step.version > prev_version) {
+            let changes = (step.run)(deps.branch(), env)?;
+            response = response
+                .add_attribute("migration_step", format!("{}: {}", step.version, step.description))
+                .add_attributes(changes);
+        }
+
+        validate_channel_encoding(deps.as_ref())?;
+        validate_state_invariants(deps.as_ref())?;
+
+        cw2::set_contract_version(deps.storage, keys::CONTRACT_NAME, keys::CONTRACT_VERSION)?;
+
+        Ok(response)
+    }
+
+    /// Validate that the contract version is semver compliant
+    /// and greater than the previous version. Returns the previous version.
+    pub fn validate_semver(deps: Deps) -> Result<semver::Version, ContractError> {
+        let prev_cw2_version = cw2::get_contract_version(deps.storage)?;
+        if prev_cw2_version.contract != keys::CONTRACT_NAME {
+            return Err(ContractError::InvalidMigrationVersion {
+                expected: keys::CONTRACT_NAME.to_string(),
+                actual: prev_cw2_version.contract,
+            });
+        }
+
+        let version: semver::Version = keys::CONTRACT_VERSION.parse()?;
+        let prev_version: semver::Version = prev_cw2_version.version.parse()?;
+        if prev_version >= version {
+            return Err(ContractError::InvalidMigrationVersion {
+                expected: format!("> {prev_version}"),
+                actual: keys::CONTRACT_VERSION.to_string(),
+            });
+        }
+        Ok(prev_version)
+    }
+
+    /// Validate that the channel encoding is protobuf if set.
+    pub fn validate_channel_encoding(deps: Deps) -> Result<(), ContractError> {
+        // Reject the migration if the channel encoding is not protobuf
+        if let Some(ica_info) = state::STATE.load(deps.storage)?.ica_info {
+            if !matches!(
+                ica_info.encoding,
+                crate::ibc::types::metadata::TxEncoding::Protobuf
+            ) {
+                return Err(ContractError::UnsupportedPacketEncoding(
+                    ica_info.encoding.to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate that the migrated state is consistent:
+    /// - the outpost still has an owner.
+    /// - an open channel is the one recorded in the ICA info.
+    pub fn validate_state_invariants(deps: Deps) -> Result<(), ContractError> {
+        if cw_ownable::get_ownership(deps.storage)?.owner.is_none() {
+            return Err(ContractError::NoOwner {});
+        }
+
+        let contract_state = state::STATE.load(deps.storage)?;
+        if let Some(channel_state) = state::CHANNEL_STATE.may_load(deps.storage)? {
+            if channel_state.is_open() {
+                let ica_info = contract_state.get_ica_info()?;
+                if ica_info.channel_id != channel_state.channel.endpoint.channel_id {
+                    return Err(ContractError::InvalidChannelInContractState {});
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 0.1.0 tracked whether channel open init was allowed inside [`state::ContractState`], and didn't
+    /// count callbacks.
+    mod v0_1_1 {
+        use cosmwasm_std::{Attribute, DepsMut, Env};
+
+        use crate::types::{
+            state::{CallbackCounter, ALLOW_CHANNEL_OPEN_INIT, CALLBACK_COUNTER, STATE},
+            ContractError,
+        };
+
+        pub fn migrate(deps: DepsMut, _env: &Env) -> Result<Vec<Attribute>, ContractError> {
+            let mut changes = vec![];
+
+            if !ALLOW_CHANNEL_OPEN_INIT.exists(deps.storage) {
+                let allow_channel_open_init = STATE.load(deps.storage)?.allow_channel_open_init;
+                ALLOW_CHANNEL_OPEN_INIT.save(deps.storage, &allow_channel_open_init)?;
+                changes.push(Attribute::new("backfilled", "allow_channel_open_init"));
+            }
+
+            if !CALLBACK_COUNTER.exists(deps.storage) {
+                CALLBACK_COUNTER.save(deps.storage, &CallbackCounter::default())?;
+                changes.push(Attribute::new("backfilled", "callback_counter"));
+            }
+
+            Ok(changes)
+        }
+    }
+
+    /// 0.2.0 reserves closing the channel to the contract itself, and keeps the instantiation callback
+    /// to notify the factory of channel changes.
+    mod v0_2_0 {
+        use cosmwasm_std::{Attribute, DepsMut, Env};
+
+        use crate::types::{
+            callback::Callback,
+            state::{ALLOW_CHANNEL_CLOSE_INIT, CALLBACK, STATE},
+            ContractError,
+        };
+
+        pub fn migrate(deps: DepsMut, _env: &Env) -> Result<Vec<Attribute>, ContractError> {
+            let mut changes = vec![];
+
+            if !ALLOW_CHANNEL_CLOSE_INIT.exists(deps.storage) {
+                ALLOW_CHANNEL_CLOSE_INIT.save(deps.storage, &false)?;
+                changes.push(Attribute::new("backfilled", "allow_channel_close_init"));
+            }
+
+            // Outposts created by the factory list it as their admin. Users who instantiated their
+            // outpost directly are the admin themselves, and have no callback contract.
+            if !CALLBACK.exists(deps.storage) {
+                let admin = STATE.load(deps.storage)?.admin;
+                let owner = cw_ownable::get_ownership(deps.storage)?.owner;
+                let admin_is_contract = deps.querier.query_wasm_contract_info(&admin).is_ok();
+                if let Some(owner) = owner.filter(|_| admin_is_contract) {
+                    let callback = Callback {
+                        contract: admin.to_string(),
+                        msg: None,
+                        outpost_owner: owner.to_string(),
+                        // Outposts from before 0.2.0 are their owner's default outpost
+                        outpost_name: None,
+                        on_channel_open_msg: None,
+                    };
+                    CALLBACK.save(deps.storage, &callback)?;
+                    changes.push(Attribute::new("backfilled", "callback"));
+                }
+            }
+
+            Ok(changes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{
+        to_json_binary, ContractInfoResponse, ContractResult, Storage, SubMsgResult, SystemError,
+        SystemResult, WasmQuery,
+    };
+
+    use crate::types::callback::Callback;
+    use crate::types::msg::options::ChannelOpenInitOptions;
+    use crate::types::state::{CallbackCounter, ALLOW_CHANNEL_CLOSE_INIT, ALLOW_CHANNEL_OPEN_INIT};
+
+    use super::*;
+
+    /// Raw storage of an outpost running 0.1.0 with an open channel, as written by that release.
+    fn store_v0_1_0_snapshot(storage: &mut dyn Storage) {
+        storage.set(b"contract_info", br#"{"contract":"crates.io:storage-outpost","version":"0.1.0"}"#);
+        storage.set(b"ownership", br#"{"owner":"alice","pending_owner":null,"pending_expiry":null}"#);
+        storage.set(
+            b"state",
+            br#"{"admin":"factory","ica_info":{"ica_address":"jkl1ica","channel_id":"channel-7","encoding":"proto3"},"allow_channel_open_init":false}"#,
+        );
+        storage.set(
+            b"ica_channel",
+            br#"{"channel":{"endpoint":{"port_id":"wasm.outpost","channel_id":"channel-7"},"counterparty_endpoint":{"port_id":"icahost","channel_id":"channel-3"},"order":"ORDER_ORDERED","version":"ics27-1","connection_id":"connection-0"},"channel_status":"STATE_OPEN"}"#,
+        );
+    }
+
+    /// An outpost owned by alice with ICA jkl1ica on channel-7, upgraded from 0.1.0 to the current storage layout.
+    /// Feature tests start from here, only the migration tests use the raw snapshot.
+    fn open_outpost(mut deps: DepsMut) {
+        store_v0_1_0_snapshot(deps.storage);
+        migrate::run(deps.branch(), &mock_env()).unwrap();
+    }
+
+    fn attribute<'a>(response: &'a Response, key: &str) -> Vec<&'a str> {
+        response
+            .attributes
+            .iter()
+            .filter(|attr| attr.key == key)
+            .map(|attr| attr.value.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn migrate_from_v0_1_0_runs_every_step() {
+        let mut deps = mock_dependencies();
+        store_v0_1_0_snapshot(deps.as_mut().storage);
+
+        let response = migrate::run(deps.as_mut(), &mock_env()).unwrap();
+
+        assert_eq!(attribute(&response, "previous_version"), vec!["0.1.0"]);
+        assert_eq!(attribute(&response, "new_version"), vec![CONTRACT_VERSION]);
+        assert_eq!(attribute(&response, "migration_step").len(), 2);
+        assert_eq!(
+            attribute(&response, "backfilled"),
+            vec!["allow_channel_open_init", "callback_counter", "allow_channel_close_init"]
+        );
+
+        assert!(!ALLOW_CHANNEL_OPEN_INIT.load(&deps.storage).unwrap());
+        assert!(!ALLOW_CHANNEL_CLOSE_INIT.load(&deps.storage).unwrap());
+        assert_eq!(CALLBACK_COUNTER.load(&deps.storage).unwrap(), CallbackCounter::default());
+        assert_eq!(cw2::get_contract_version(&deps.storage).unwrap().version, CONTRACT_VERSION);
+
+        // Existing state is untouched
+        let ica_info = STATE.load(&deps.storage).unwrap().get_ica_info().unwrap();
+        assert_eq!(ica_info.ica_address, "jkl1ica");
+        assert!(CHANNEL_STATE.load(&deps.storage).unwrap().is_open());
+    }
+
+    #[test]
+    fn migrate_from_v0_1_1_skips_older_steps() {
+        let mut deps = mock_dependencies();
+        store_v0_1_0_snapshot(deps.as_mut().storage);
+        deps.storage.set(b"contract_info", br#"{"contract":"crates.io:storage-outpost","version":"0.1.1"}"#);
+        ALLOW_CHANNEL_OPEN_INIT.save(deps.as_mut().storage, &false).unwrap();
+        let counter = CallbackCounter { success: 4, error: 1, timeout: 0 };
+        CALLBACK_COUNTER.save(deps.as_mut().storage, &counter).unwrap();
+
+        let response = migrate::run(deps.as_mut(), &mock_env()).unwrap();
+
+        assert_eq!(attribute(&response, "migration_step").len(), 1);
+        assert_eq!(attribute(&response, "backfilled"), vec!["allow_channel_close_init"]);
+        assert_eq!(CALLBACK_COUNTER.load(&deps.storage).unwrap(), counter);
+    }
+
+    #[test]
+    fn migrate_rejects_same_or_older_version() {
+        let mut deps = mock_dependencies();
+        store_v0_1_0_snapshot(deps.as_mut().storage);
+        cw2::set_contract_version(deps.as_mut().storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+
+        let err = migrate::run(deps.as_mut(), &mock_env()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMigrationVersion { .. }));
+
+        cw2::set_contract_version(deps.as_mut().storage, "crates.io:cw-ica-controller", "0.1.0").unwrap();
+        let err = migrate::run(deps.as_mut(), &mock_env()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMigrationVersion { .. }));
+    }
+
+    #[test]
+    fn migrate_rejects_inconsistent_state() {
+        let mut deps = mock_dependencies();
+        store_v0_1_0_snapshot(deps.as_mut().storage);
+        // The open channel doesn't match the ICA info
+        deps.storage.set(
+            b"state",
+            br#"{"admin":"factory","ica_info":{"ica_address":"jkl1ica","channel_id":"channel-8","encoding":"proto3"}}"#,
+        );
+
+        let err = migrate::run(deps.as_mut(), &mock_env()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidChannelInContractState {}));
+
+        // Nothing was written since the migration failed
+        assert!(cw2::get_contract_version(&deps.storage).unwrap().version == "0.1.0");
+    }
+
+    #[test]
+    fn migrate_rejects_proto3json_channels() {
+        let mut deps = mock_dependencies();
+        store_v0_1_0_snapshot(deps.as_mut().storage);
+        deps.storage.set(
+            b"state",
+            br#"{"admin":"factory","ica_info":{"ica_address":"jkl1ica","channel_id":"channel-7","encoding":"proto3json"}}"#,
+        );
+
+        let err = migrate::run(deps.as_mut(), &mock_env()).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPacketEncoding(_)));
+    }
+
+    #[test]
+    fn migrate_keeps_factory_as_callback() {
+        let mut deps = mock_dependencies();
+        store_v0_1_0_snapshot(deps.as_mut().storage);
+        // Only the factory is a contract
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::ContractInfo { contract_addr } if contract_addr == "factory" => {
+                let mut info = ContractInfoResponse::default();
+                info.code_id = 1;
+                info.creator = "deployer".to_string();
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+
+        let response = migrate::run(deps.as_mut(), &mock_env()).unwrap();
+
+        assert!(attribute(&response, "backfilled").contains(&"callback"));
+        assert_eq!(
+            CALLBACK.load(&deps.storage).unwrap(),
+            Callback {
+                contract: "factory".to_string(),
+                msg: None,
+                outpost_owner: "alice".to_string(),
+                outpost_name: None,
+                on_channel_open_msg: None,
+            }
+        );
+    }
+
+    #[test]
+    fn admin_can_only_retry_channel_with_saved_options() {
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let options = ChannelOpenInitOptions {
+            connection_id: "connection-0".to_string(),
+            counterparty_connection_id: "connection-0".to_string(),
+            counterparty_port_id: None,
+            tx_encoding: None,
+            channel_ordering: None,
+        };
+        CHANNEL_OPEN_INIT_OPTIONS.save(deps.as_mut().storage, &options).unwrap();
+        ALLOW_CHANNEL_OPEN_INIT.save(deps.as_mut().storage, &false).unwrap();
+
+        let err = execute::create_channel(deps.as_mut(), mock_env(), mock_info("mallory", &[]), None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let err = execute::create_channel(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("factory", &[]),
+            Some(options.clone()),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let response =
+            execute::create_channel(deps.as_mut(), mock_env(), mock_info("factory", &[]), None)
+                .unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert!(ALLOW_CHANNEL_OPEN_INIT.load(&deps.storage).unwrap());
+
+        // The owner may still change the options
+        execute::create_channel(deps.as_mut(), mock_env(), mock_info("alice", &[]), Some(options))
+            .unwrap();
+    }
+
+    #[test]
+    fn failed_callback_notification_is_swallowed() {
+        let msg = Reply {
+            id: keys::CALLBACK_NOTIFICATION_REPLY_ID,
+            result: SubMsgResult::Err("unknown variant `outpost_channel_opened`".to_string()),
+        };
+
+        let response = reply::callback_notification(msg).unwrap();
+        assert_eq!(
+            attribute(&response, "callback_notification_error"),
+            vec!["unknown variant `outpost_channel_opened`"]
+        );
+    }
+
+    /// Sends the decommission cleanup packet and records it under the given sequence, as wasmd would.
+    fn start_decommission(deps: DepsMut, sequence: u64) -> Response {
+        let return_funds = crate::types::msg::options::ReturnFunds {
+            transfer_channel_id: "channel-0".to_string(),
+            amount: vec![cosmwasm_std::coin(100, "ujkl")],
+            receiver: None,
+        };
+        let mut deps = deps;
+        let response = execute::decommission(
+            deps.branch(),
+            mock_env(),
+            mock_info("alice", &[]),
+            vec![],
+            Some(return_funds),
+            None,
+        )
+        .unwrap();
+
+        packet_sent(deps, sequence);
+        response
+    }
+
+    /// Replies to a tracked packet with the sequence the host chain gave it.
+    fn packet_sent(deps: DepsMut, sequence: u64) {
+        let data = prost::Message::encode_to_vec(&crate::ibc::types::packet::send_response::MsgIbcSendResponse { sequence });
+        let msg = Reply {
+            id: keys::SEND_PACKET_REPLY_ID,
+            result: SubMsgResult::Ok(cosmwasm_std::SubMsgResponse { events: vec![], data: Some(data.into()) }),
+        };
+        reply::send_packet(deps, msg).unwrap();
+    }
+
+    /// Replies to a tracked ICS-20 transfer with the sequence it was given.
+    fn transfer_sent(deps: DepsMut, sequence: u64) {
+        use crate::ibc::types::stargate::transfer::MsgTransferResponse;
+
+        let data = prost::Message::encode_to_vec(&MsgTransferResponse { sequence });
+        let msg = Reply {
+            id: keys::SEND_PACKET_REPLY_ID,
+            result: SubMsgResult::Ok(cosmwasm_std::SubMsgResponse { events: vec![], data: Some(data.into()) }),
+        };
+        reply::send_packet(deps, msg).unwrap();
+    }
+
+    /// A mock querier that also answers the denom trace stargate query with the given `(hash, path, base_denom)`s.
+    struct DenomTraceQuerier {
+        base: cosmwasm_std::testing::MockQuerier,
+        traces: Vec<(&'static str, &'static str, &'static str)>,
+    }
+
+    impl cosmwasm_std::Querier for DenomTraceQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> cosmwasm_std::QuerierResult {
+            use crate::ibc::types::stargate::query::response::{DenomTrace, QueryDenomTraceResponse};
+            use cosmos_sdk_proto::ibc::applications::transfer::v1::QueryDenomTraceRequest;
+
+            match cosmwasm_std::from_json(bin_request) {
+                Ok(cosmwasm_std::QueryRequest::<cosmwasm_std::Empty>::Stargate { path, data })
+                    if path == "/ibc.applications.transfer.v1.Query/DenomTrace" =>
+                {
+                    let request: QueryDenomTraceRequest = prost::Message::decode(data.as_slice()).unwrap();
+                    match self.traces.iter().find(|(hash, ..)| *hash == request.hash) {
+                        Some((_, path, base_denom)) => {
+                            let denom_trace = DenomTrace { path: path.to_string(), base_denom: base_denom.to_string() };
+                            SystemResult::Ok(ContractResult::Ok(to_json_binary(&QueryDenomTraceResponse { denom_trace }).unwrap()))
+                        }
+                        None => SystemResult::Ok(ContractResult::Err("denomination trace not found".to_string())),
+                    }
+                }
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    /// Switches the channel to proto3json, which only 'SendCosmosMsgs' supports.
+    fn use_proto3json(storage: &mut dyn cosmwasm_std::Storage) {
+        state::STATE
+            .update(storage, |mut contract_state| -> StdResult<_> {
+                contract_state.ica_info.as_mut().unwrap().encoding = crate::ibc::types::metadata::TxEncoding::Proto3Json;
+                Ok(contract_state)
+            })
+            .unwrap();
+    }
+
+    /// Decodes the transaction in the ICA packet the response sends first.
+    fn sent_tx(response: &Response) -> cosmos_sdk_proto::cosmos::tx::v1beta1::TxBody {
+        sent_tx_at(response, 0)
+    }
+
+    /// Decodes the transaction in the ICA packet sent by the response's message at `index`.
+    fn sent_tx_at(response: &Response, index: usize) -> cosmos_sdk_proto::cosmos::tx::v1beta1::TxBody {
+        let cosmwasm_std::CosmosMsg::Ibc(cosmwasm_std::IbcMsg::SendPacket { data, .. }) = &response.messages[index].msg else {
+            panic!("expected an ICA packet");
+        };
+        let packet: crate::ibc::types::packet::IcaPacketData = cosmwasm_std::from_json(data).unwrap();
+        prost::Message::decode(packet.data.as_slice()).unwrap()
+    }
+
+    /// The packet the outpost sent on channel-7 with the given sequence, to acknowledge or time out.
+    fn ica_packet(sequence: u64) -> cosmwasm_std::IbcPacket {
+        cosmwasm_std::IbcPacket::new(
+            Binary::default(),
+            cosmwasm_std::IbcEndpoint { port_id: "wasm.outpost".to_string(), channel_id: "channel-7".to_string() },
+            cosmwasm_std::IbcEndpoint { port_id: "icahost".to_string(), channel_id: "channel-3".to_string() },
+            sequence,
+            mock_env().block.time.into(),
+        )
+    }
+
+    fn ack(packet: cosmwasm_std::IbcPacket, data: &crate::ibc::types::packet::acknowledgement::AcknowledgementData) -> cosmwasm_std::IbcPacketAckMsg {
+        cosmwasm_std::IbcPacketAckMsg::new(
+            cosmwasm_std::IbcAcknowledgement::new(to_json_binary(data).unwrap()),
+            packet,
+            cosmwasm_std::Addr::unchecked("relayer"),
+        )
+    }
+
+    #[test]
+    fn decommission_cleans_up_then_closes_channel() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::decommission::DecommissionStage;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let callback = Callback {
+            contract: "factory".to_string(),
+            msg: None,
+            outpost_owner: "alice".to_string(),
+            outpost_name: None,
+            on_channel_open_msg: None,
+        };
+        CALLBACK.save(deps.as_mut().storage, &callback).unwrap();
+
+        let err = execute::decommission(deps.as_mut(), mock_env(), mock_info("mallory", &[]), vec![], None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(_)));
+        let return_funds = |amount, receiver: &str| crate::types::msg::options::ReturnFunds {
+            transfer_channel_id: "channel-0".to_string(),
+            amount,
+            receiver: Some(receiver.to_string()),
+        };
+        let err = execute::decommission(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![], Some(return_funds(vec![], "alice")), None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NoFunds {}));
+        let err = execute::decommission(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![], Some(return_funds(cosmwasm_std::coins(100, "ujkl"), "Alice")), None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Std(_)));
+
+        let response = start_decommission(deps.as_mut(), 4);
+        assert_eq!(response.messages[0].id, keys::SEND_PACKET_REPLY_ID);
+        let decommission = query::decommission(deps.as_ref()).unwrap().unwrap();
+        assert_eq!(decommission.stage, DecommissionStage::CleaningUp);
+
+        // Nothing else may be sent meanwhile
+        let err = execute::send_cosmos_msgs(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![], None, None, false)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::DecommissionInProgress {}));
+
+        // Acknowledgements of other packets are ignored
+        let ack_data = AcknowledgementData::Result(Binary::default());
+        let response = crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(3), &ack_data)).unwrap();
+        assert!(response.messages.is_empty());
+
+        let response = crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(4), &ack_data)).unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            CosmosMsg::Ibc(cosmwasm_std::IbcMsg::CloseChannel { channel_id: "channel-7".to_string() })
+        );
+        assert!(ALLOW_CHANNEL_CLOSE_INIT.load(&deps.storage).unwrap());
+
+        let channel = CHANNEL_STATE.load(&deps.storage).unwrap().channel;
+        let response = crate::ibc::handshake::ibc_channel_close(
+            deps.as_mut(),
+            mock_env(),
+            cosmwasm_std::IbcChannelCloseMsg::CloseInit { channel },
+        )
+        .unwrap();
+        // The factory is told to unregister the outpost
+        assert_eq!(response.messages, vec![callback.notification(&OutpostFactoryExecuteMsg::OutpostDecommissioned {}).unwrap()]);
+
+        assert!(!CHANNEL_STATE.load(&deps.storage).unwrap().is_open());
+        let decommission = query::decommission(deps.as_ref()).unwrap().unwrap();
+        assert_eq!(decommission.stage, DecommissionStage::Decommissioned);
+
+        let err = execute::send_cosmos_msgs(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![], None, None, false)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Decommissioned {}));
+        let err = execute::create_channel(deps.as_mut(), mock_env(), mock_info("alice", &[]), None).unwrap_err();
+        assert!(matches!(err, ContractError::Decommissioned {}));
+    }
+
+    #[test]
+    fn failed_decommission_can_be_retried() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::decommission::DecommissionStage;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+
+        start_decommission(deps.as_mut(), 1);
+        let ack_data = AcknowledgementData::Error("insufficient funds".to_string());
+        let response = crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(1), &ack_data)).unwrap();
+        assert!(response.messages.is_empty());
+
+        let decommission = query::decommission(deps.as_ref()).unwrap().unwrap();
+        assert_eq!(decommission.stage, DecommissionStage::Failed);
+        assert_eq!(decommission.error.as_deref(), Some("insufficient funds"));
+
+        // The channel can't be closed by anyone else in the meantime
+        let channel = CHANNEL_STATE.load(&deps.storage).unwrap().channel;
+        let err = crate::ibc::handshake::ibc_channel_close(
+            deps.as_mut(),
+            mock_env(),
+            cosmwasm_std::IbcChannelCloseMsg::CloseInit { channel },
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::ChannelCloseInitNotAllowed {}));
+
+        start_decommission(deps.as_mut(), 2);
+        let timeout = cosmwasm_std::IbcPacketTimeoutMsg::new(ica_packet(2), cosmwasm_std::Addr::unchecked("relayer"));
+        crate::ibc::relay::ibc_packet_timeout(deps.as_mut(), mock_env(), timeout).unwrap();
+        let decommission = query::decommission(deps.as_ref()).unwrap().unwrap();
+        assert_eq!(decommission.stage, DecommissionStage::Failed);
+
+        // Without a channel or anything to clean up, the outpost is retired straight away
+        let response = execute::decommission(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![], None, None).unwrap();
+        assert!(response.messages.is_empty());
+        let decommission = query::decommission(deps.as_ref()).unwrap().unwrap();
+        assert_eq!(decommission.stage, DecommissionStage::Decommissioned);
+    }
+
+    #[test]
+    fn file_transfers_are_tracked_per_batch() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::file_transfer::FileTransferStatus;
+        use crate::types::msg::options::FileDestination;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+
+        let paths = vec!["s/home/a.txt".to_string(), "s/home/b.txt".to_string(), "s/home/c.txt".to_string()];
+        let destination = FileDestination::Ica { address: "jkl1newica".to_string() };
+
+        let err = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("bob", &[]), paths.clone(), destination.clone(), None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(_)));
+        let err = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), paths.clone(), destination.clone(), Some(0), None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidBatchSize { .. }));
+        for address in ["", "jkl1ica", "jkl1NEWICA", "osmo1newica"] {
+            let destination = FileDestination::Ica { address: address.to_string() };
+            let err = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), paths.clone(), destination, None, None)
+                .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidAddress {}));
+        }
+        assert!(query::file_transfers(deps.as_ref(), None, None).unwrap().is_empty());
+
+        // Two files fit in the first packet, the last one goes in a second packet
+        let response = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), paths.clone(), destination.clone(), Some(2), None)
+            .unwrap();
+        assert_eq!(response.messages.len(), 2);
+        assert!(response.messages.iter().all(|msg| msg.id == keys::SEND_PACKET_REPLY_ID));
+        packet_sent(deps.as_mut(), 4);
+        packet_sent(deps.as_mut(), 5);
+
+        // The files can't be sent again while in flight
+        let err = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![paths[2].clone()], destination.clone(), None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::FileAlreadyTransferred(_)));
+
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(4), &AcknowledgementData::Result(Binary::default())))
+            .unwrap();
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(5), &AcknowledgementData::Error("out of gas".to_string())))
+            .unwrap();
+
+        let transfers = query::file_transfers(deps.as_ref(), None, None).unwrap();
+        let statuses: Vec<_> = transfers.iter().map(|transfer| (transfer.path.as_str(), transfer.status.clone())).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("s/home/a.txt", FileTransferStatus::Transferred),
+                ("s/home/b.txt", FileTransferStatus::Transferred),
+                ("s/home/c.txt", FileTransferStatus::Failed { error: "out of gas".to_string() }),
+            ]
+        );
+        assert!(transfers.iter().all(|transfer| transfer.new_owner == "jkl1newica"));
+        let page = query::file_transfers(deps.as_ref(), Some("s/home/a.txt".to_string()), Some(1)).unwrap();
+        assert_eq!(page[0].path, "s/home/b.txt");
+
+        // Transferred files are gone, failed ones can be sent again
+        let err = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![paths[0].clone()], destination.clone(), None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::FileAlreadyTransferred(_)));
+        let response = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![paths[2].clone()], destination.clone(), None, None)
+            .unwrap();
+        assert_eq!(response.messages.len(), 1);
+
+        // 'MsgChangeOwner' can only be sent as protobuf, and nothing is tracked for a rejected transfer
+        use_proto3json(deps.as_mut().storage);
+        let err = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec!["s/home/d.txt".to_string()], destination, None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPacketEncoding(_)));
+        assert!(!state::FILE_TRANSFERS.has(deps.as_ref().storage, "s/home/d.txt"));
+    }
+
+    #[test]
+    fn file_destination_is_resolved_through_the_factory() {
+        use crate::types::msg::{options::FileDestination, DirectoryEntry};
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "factory" => {
+                let entry = DirectoryEntry {
+                    user_address: "bob".to_string(),
+                    outpost_name: "bob".to_string(),
+                    outpost_address: "bob_outpost".to_string(),
+                    ica_address: "jkl1bobica".to_string(),
+                };
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&entry).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+        let destination = FileDestination::User { user_address: "bob".to_string(), outpost_name: None };
+
+        // Outposts that weren't created by a factory can't look users up
+        let err = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec!["s/home/a.txt".to_string()], destination.clone(), None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::NoFactory {}));
+
+        let callback = Callback {
+            contract: "factory".to_string(),
+            msg: None,
+            outpost_owner: "alice".to_string(),
+            outpost_name: None,
+            on_channel_open_msg: None,
+        };
+        CALLBACK.save(deps.as_mut().storage, &callback).unwrap();
+        let response = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec!["s/home/a.txt".to_string()], destination, None, None)
+            .unwrap();
+        assert_eq!(attribute_of(&response, "new_owner"), "jkl1bobica");
+
+        // The ICA an outpost reports is checked too
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "alice_outpost" => {
+                let mut contract_state = ContractState::new(cosmwasm_std::Addr::unchecked("factory"));
+                contract_state.set_ica_info("jkl1ica", "channel-7", crate::ibc::types::metadata::TxEncoding::Protobuf);
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&contract_state).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::Unknown {}),
+        });
+        let destination = FileDestination::Outpost { address: "alice_outpost".to_string() };
+        let err = execute::transfer_files(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec!["s/home/b.txt".to_string()], destination, None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidAddress {}));
+    }
+
+    fn attribute_of<'a>(response: &'a Response, key: &str) -> &'a str {
+        response.events[0].attributes.iter().find(|attr| attr.key == key).map(|attr| attr.value.as_str()).unwrap()
+    }
+
+    #[test]
+    fn failed_ica_funding_is_refunded() {
+        use crate::ibc::types::stargate::transfer::MsgTransfer;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let funds = vec![cosmwasm_std::coin(100, "ujkl"), cosmwasm_std::coin(5, "uatom")];
+
+        let err = execute::set_transfer_channel(deps.as_mut(), mock_info("bob", &[]), "channel-0".to_string()).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(_)));
+        let err = execute::fund_ica(deps.as_mut(), mock_env(), mock_info("bob", &funds), None, None).unwrap_err();
+        assert!(matches!(err, ContractError::NoTransferChannel {}));
+
+        execute::set_transfer_channel(deps.as_mut(), mock_info("alice", &[]), "channel-0".to_string()).unwrap();
+        let err = execute::fund_ica(deps.as_mut(), mock_env(), mock_info("bob", &[]), None, None).unwrap_err();
+        assert!(matches!(err, ContractError::NoFunds {}));
+        let memo = crate::ibc::types::memo::PacketMemo::default().with_ibc_callback("bob");
+        let err = execute::fund_ica(deps.as_mut(), mock_env(), mock_info("bob", &funds), Some(memo), None).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMemo(_)));
+        for memo in [r#"{"forward":{"receiver":"osmo1bob","channel":"channel-1"}}"#, r#"{"wasm":{"contract":"jkl1ica","msg":"e30="}}"#] {
+            let memo = cosmwasm_std::from_json(memo).unwrap();
+            let err = execute::fund_ica(deps.as_mut(), mock_env(), mock_info("bob", &funds), Some(memo), None).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidMemo(_)));
+        }
+
+        // Anyone may fund the ICA, each coin goes in its own transfer
+        let response = execute::fund_ica(deps.as_mut(), mock_env(), mock_info("bob", &funds), None, None).unwrap();
+        assert_eq!(response.messages.len(), 2);
+        let cosmwasm_std::CosmosMsg::Stargate { type_url, value } = &response.messages[0].msg else {
+            panic!("expected a stargate MsgTransfer");
+        };
+        assert_eq!(type_url, "/ibc.applications.transfer.v1.MsgTransfer");
+        let transfer: MsgTransfer = prost::Message::decode(value.as_slice()).unwrap();
+        assert_eq!(transfer.source_channel, "channel-0");
+        assert_eq!(transfer.receiver, "jkl1ica");
+        assert_eq!(transfer.memo, format!(r#"{{"ibc_callback":"{}"}}"#, mock_env().contract.address));
+
+        for sequence in [11, 12] {
+            transfer_sent(deps.as_mut(), sequence);
+        }
+        let funding = crate::types::ica_funding::ica_funding(deps.as_ref().storage).unwrap();
+        assert_eq!(funding.transfer_channel_id.as_deref(), Some("channel-0"));
+        assert_eq!(funding.pending.iter().map(|transfer| transfer.sequence).collect::<Vec<_>>(), vec![11, 12]);
+
+        // ibc-hooks calls back through sudo
+        let ack: SudoMsg = cosmwasm_std::from_json(
+            br#"{"ibc_lifecycle_complete":{"ibc_ack":{"channel":"channel-0","sequence":11,"ack":"{\"result\":\"AQ==\"}","success":true}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(ack, SudoMsg::IbcLifecycleComplete(IbcLifecycleComplete::IbcAck { sequence: 11, success: true, .. })));
+        let response = sudo::transfer_complete(deps.as_mut(), mock_env(), "channel-0".to_string(), 11, true).unwrap();
+        assert!(response.messages.is_empty());
+
+        let response = sudo::transfer_complete(deps.as_mut(), mock_env(), "channel-0".to_string(), 12, false).unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            cosmwasm_std::CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+                to_address: "bob".to_string(),
+                amount: vec![cosmwasm_std::coin(5, "uatom")],
+            })
+        );
+
+        // Settled transfers are never refunded twice
+        let response = sudo::transfer_complete(deps.as_mut(), mock_env(), "channel-0".to_string(), 12, false).unwrap();
+        assert!(response.messages.is_empty());
+        assert!(crate::types::ica_funding::ica_funding(deps.as_ref().storage).unwrap().pending.is_empty());
+    }
+
+    #[test]
+    fn ica_funds_are_withdrawn_over_the_transfer_channel() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let transfer_channel = cosmwasm_std::IbcChannel::new(
+            cosmwasm_std::IbcEndpoint { port_id: "transfer".to_string(), channel_id: "channel-0".to_string() },
+            cosmwasm_std::IbcEndpoint { port_id: "transfer".to_string(), channel_id: "channel-9".to_string() },
+            cosmwasm_std::IbcOrder::Unordered,
+            "ics20-1",
+            "connection-0",
+        );
+        deps.querier.update_ibc("transfer", &[transfer_channel]);
+        let amount = vec![cosmwasm_std::coin(100, "ujkl")];
+
+        let err = execute::withdraw_from_ica(deps.as_mut(), mock_env(), mock_info("bob", &[]), amount.clone(), None, None).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(_)));
+        let err = execute::withdraw_from_ica(deps.as_mut(), mock_env(), mock_info("alice", &[]), amount.clone(), None, None).unwrap_err();
+        assert!(matches!(err, ContractError::NoTransferChannel {}));
+
+        execute::set_transfer_channel(deps.as_mut(), mock_info("alice", &[]), "channel-0".to_string()).unwrap();
+        let response = execute::withdraw_from_ica(deps.as_mut(), mock_env(), mock_info("alice", &[]), amount.clone(), None, None).unwrap();
+        assert_eq!(response.events[0].attributes[2].value, "channel-9");
+
+        // The ICA sends the funds back to the owner from the host's end of the channel
+        let tx = sent_tx(&response);
+        let transfer: cosmos_sdk_proto::ibc::applications::transfer::v1::MsgTransfer = tx.messages[0].to_msg().unwrap();
+        assert_eq!((transfer.source_channel.as_str(), transfer.sender.as_str(), transfer.receiver.as_str()), ("channel-9", "jkl1ica", "alice"));
+
+        packet_sent(deps.as_mut(), 8);
+        let funding = crate::types::ica_funding::ica_funding(deps.as_ref().storage).unwrap();
+        assert_eq!(funding.withdrawals[0].amount, amount);
+
+        let response = crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(8), &AcknowledgementData::Result(Binary::default())))
+            .unwrap();
+        assert_eq!(response.events[1].ty, "withdraw_from_ica");
+        assert!(crate::types::ica_funding::ica_funding(deps.as_ref().storage).unwrap().withdrawals.is_empty());
+    }
+
+    type StorageDeps = cosmwasm_std::OwnedDeps<cosmwasm_std::testing::MockStorage, cosmwasm_std::testing::MockApi, DenomTraceQuerier>;
+
+    /// An open outpost whose transfer channel, channel-0, connects to channel-9 on Jackal.
+    /// "ibc/JKL" is JKL arriving over channel-0, "ibc/ATOM" ATOM arriving over channel-4.
+    fn storage_outpost() -> StorageDeps {
+        let mut deps = mock_dependencies();
+        let traces = vec![("JKL", "transfer/channel-0", "ujkl"), ("ATOM", "transfer/channel-4", "uatom")];
+        let mut deps = cosmwasm_std::OwnedDeps {
+            storage: deps.storage,
+            api: deps.api,
+            querier: DenomTraceQuerier { base: std::mem::take(&mut deps.querier), traces },
+            custom_query_type: std::marker::PhantomData,
+        };
+        open_outpost(deps.as_mut());
+        let transfer_channel = cosmwasm_std::IbcChannel::new(
+            cosmwasm_std::IbcEndpoint { port_id: "transfer".to_string(), channel_id: "channel-0".to_string() },
+            cosmwasm_std::IbcEndpoint { port_id: "transfer".to_string(), channel_id: "channel-9".to_string() },
+            cosmwasm_std::IbcOrder::Unordered,
+            "ics20-1",
+            "connection-0",
+        );
+        deps.querier.base.update_ibc("transfer", &[transfer_channel]);
+        execute::set_transfer_channel(deps.as_mut(), mock_info("alice", &[]), "channel-0".to_string()).unwrap();
+        deps
+    }
+
+    /// Has alice buy 1 TB for 30 days, paid with the funds.
+    fn buy_one_tb(deps: DepsMut, funds: &[cosmwasm_std::Coin]) -> Result<Response, ContractError> {
+        execute::buy_storage(deps, mock_env(), mock_info("alice", funds), 1_000_000_000_000, 30, None, None, None, None)
+    }
+
+    /// A purchase of 1 TB paid with 1000 JKL whose funds arrived on the ICA, bought in the packet with the sequence.
+    fn funded_purchase(deps: &mut StorageDeps, transfer_sequence: u64, packet_sequence: u64) {
+        buy_one_tb(deps.as_mut(), &cosmwasm_std::coins(1_000, "ibc/JKL")).unwrap();
+        transfer_sent(deps.as_mut(), transfer_sequence);
+        sudo::transfer_complete(deps.as_mut(), mock_env(), "channel-0".to_string(), transfer_sequence, true).unwrap();
+        packet_sent(deps.as_mut(), packet_sequence);
+    }
+
+    #[test]
+    fn storage_payments_are_checked() {
+        let mut deps = storage_outpost();
+        let payment = cosmwasm_std::coins(1_000, "ibc/JKL");
+
+        // The plan is paid with the denom the coin arrives as on Jackal: JKL going home is unwound,
+        // anything else becomes a voucher of the host's end of the channel
+        let host_denom = |denom: &str| crate::types::ica_funding::host_denom(deps.as_ref(), denom).unwrap();
+        let voucher = |path: &str| format!("ibc/{}", hex::encode_upper(<sha2::Sha256 as sha2::Digest>::digest(path.as_bytes())));
+        assert_eq!(host_denom("ibc/JKL"), "ujkl");
+        assert_eq!(host_denom("ibc/ATOM"), voucher("transfer/channel-9/transfer/channel-4/uatom"));
+        assert_eq!(host_denom("uosmo"), voucher("transfer/channel-9/uosmo"));
+        let err = execute::buy_storage(deps.as_mut(), mock_env(), mock_info("alice", &payment), 1_000_000_000_000, 30, None, Some("uusdc".to_string()), None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPaymentDenom { .. }));
+        let err = buy_one_tb(deps.as_mut(), &[]).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFunds {}));
+
+        // The purchase couldn't be sent on a proto3json channel, so the funds aren't taken
+        use_proto3json(deps.as_mut().storage);
+        let err = buy_one_tb(deps.as_mut(), &payment).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPacketEncoding(_)));
+        assert!(!state::STORAGE_PURCHASES.has(deps.as_ref().storage, 0));
+    }
+
+    #[test]
+    fn storage_is_bought_once_the_funds_arrive() {
+        use crate::types::storage::MsgBuyStorage;
+        use crate::types::storage_purchase::PurchaseStatus;
+
+        let mut deps = storage_outpost();
+        let response = buy_one_tb(deps.as_mut(), &cosmwasm_std::coins(1_000, "ibc/JKL")).unwrap();
+        assert_eq!(response.data, Some(to_json_binary(&0).unwrap()));
+        transfer_sent(deps.as_mut(), 21);
+        assert_eq!(state::STORAGE_PURCHASES.load(deps.as_ref().storage, 0).unwrap().status, PurchaseStatus::Funding);
+
+        // The ICA buys the plan for itself once the funds arrived
+        let response = sudo::transfer_complete(deps.as_mut(), mock_env(), "channel-0".to_string(), 21, true).unwrap();
+        let tx = sent_tx(&response);
+        assert_eq!(tx.messages[0].type_url, "/canine_chain.storage.MsgBuyStorage");
+        let buy: MsgBuyStorage = prost::Message::decode(tx.messages[0].value.as_slice()).unwrap();
+        assert_eq!(
+            (buy.creator.as_str(), buy.for_address.as_str(), buy.bytes, buy.payment_denom.as_str()),
+            ("jkl1ica", "jkl1ica", 1_000_000_000_000, "ujkl")
+        );
+        assert_eq!(state::STORAGE_PURCHASES.load(deps.as_ref().storage, 0).unwrap().status, PurchaseStatus::Buying);
+    }
+
+    #[test]
+    fn storage_purchases_complete_once_acknowledged() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::storage_purchase::PurchaseStatus;
+
+        let mut deps = storage_outpost();
+        funded_purchase(&mut deps, 21, 30);
+        funded_purchase(&mut deps, 22, 31);
+
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(30), &AcknowledgementData::Result(Binary::default())))
+            .unwrap();
+        let purchases = query::storage_purchases(deps.as_ref(), None, None).unwrap();
+        assert_eq!(purchases.iter().map(|purchase| purchase.id).collect::<Vec<_>>(), vec![1, 0]);
+        assert_eq!((&purchases[0].status, &purchases[1].status), (&PurchaseStatus::Buying, &PurchaseStatus::Completed));
+    }
+
+    #[test]
+    fn failed_storage_purchases_are_refunded() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::storage_purchase::PurchaseStatus;
+
+        let mut deps = storage_outpost();
+        funded_purchase(&mut deps, 21, 30);
+
+        // A failed purchase has the ICA send the funds back
+        let response = crate::ibc::relay::ibc_packet_ack(
+            deps.as_mut(),
+            mock_env(),
+            ack(ica_packet(30), &AcknowledgementData::Error("insufficient funds".to_string())),
+        )
+        .unwrap();
+        assert_eq!(response.messages[0].id, keys::SEND_PACKET_REPLY_ID);
+        packet_sent(deps.as_mut(), 31);
+        let funding = crate::types::ica_funding::ica_funding(deps.as_ref().storage).unwrap();
+        assert_eq!(funding.withdrawals[0].amount, cosmwasm_std::coins(1_000, "ujkl"));
+        assert_eq!(
+            state::STORAGE_PURCHASES.load(deps.as_ref().storage, 0).unwrap().status,
+            PurchaseStatus::Failed { error: "insufficient funds".to_string(), refunded: false }
+        );
+
+        // The purchase is refunded once the refund is acknowledged
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(31), &AcknowledgementData::Result(Binary::default())))
+            .unwrap();
+        assert_eq!(
+            state::STORAGE_PURCHASES.load(deps.as_ref().storage, 0).unwrap().status,
+            PurchaseStatus::Failed { error: "insufficient funds".to_string(), refunded: true }
+        );
+    }
+
+    #[test]
+    fn storage_purchases_are_refunded_when_the_funding_times_out() {
+        use crate::types::storage_purchase::PurchaseStatus;
+
+        let mut deps = storage_outpost();
+        let payment = cosmwasm_std::coins(1_000, "ibc/JKL");
+        buy_one_tb(deps.as_mut(), &payment).unwrap();
+        transfer_sent(deps.as_mut(), 21);
+
+        // ibc-hooks reports a timeout as a failed transfer, the funds are back on this chain
+        let response = sudo::transfer_complete(deps.as_mut(), mock_env(), "channel-0".to_string(), 21, false).unwrap();
+        assert_eq!(
+            response.messages[0].msg,
+            cosmwasm_std::CosmosMsg::Bank(cosmwasm_std::BankMsg::Send { to_address: "alice".to_string(), amount: payment })
+        );
+        assert!(matches!(
+            state::STORAGE_PURCHASES.load(deps.as_ref().storage, 0).unwrap().status,
+            PurchaseStatus::Failed { refunded: true, .. }
+        ));
+        assert_eq!(response.messages.len(), 1);
+    }
+
+    #[test]
+    fn host_queries_are_answered_through_the_ack() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::host_query::{proto, HostQuery, HostQueryCallbackMsg, HostQueryResponse};
+        use prost::Message;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let queries = vec![
+            HostQuery::PubKey { address: "jkl1ica".to_string() },
+            HostQuery::StoragePaymentInfo { address: "jkl1ica".to_string() },
+        ];
+
+        let err = execute::query_host(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![], None, None).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHostQueryCount { .. }));
+        let response = execute::query_host(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            queries.clone(),
+            Some("watcher".to_string()),
+            None,
+        )
+        .unwrap();
+        let tx = sent_tx(&response);
+        let msg = proto::MsgModuleQuerySafe::decode(tx.messages[0].value.as_slice()).unwrap();
+        assert_eq!(msg.signer, "jkl1ica");
+        assert_eq!(msg.requests[1].path, "/canine_chain.storage.Query/StoragePaymentInfo");
+        packet_sent(deps.as_mut(), 40);
+
+        let responses = vec![
+            proto::QueryPubKeyResponse {
+                pub_key: Some(proto::PubKey { address: "jkl1ica".to_string(), key: "04ab".to_string() }),
+            }
+            .encode_to_vec(),
+            proto::QueryStoragePaymentInfoResponse {
+                storage_payment_info: Some(proto::StoragePaymentInfo {
+                    space_available: 1_000,
+                    space_used: 10,
+                    ..Default::default()
+                }),
+            }
+            .encode_to_vec(),
+        ];
+        let tx_msg_data = proto::TxMsgData {
+            msg_responses: vec![cosmos_sdk_proto::Any {
+                type_url: "/ibc.applications.interchain_accounts.host.v1.MsgModuleQuerySafeResponse".to_string(),
+                value: proto::MsgModuleQuerySafeResponse { height: 77, responses }.encode_to_vec(),
+            }],
+        };
+        let ack_data = AcknowledgementData::Result(Binary::from(tx_msg_data.encode_to_vec()));
+        let response = crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(40), &ack_data)).unwrap();
+
+        let result = query::host_query_results(deps.as_ref(), None, None).unwrap();
+        assert_eq!(result.iter().map(|result| result.height).collect::<Vec<_>>(), vec![77, 77]);
+        let pub_key = state::HOST_QUERY_RESULTS.load(deps.as_ref().storage, &queries[0].key()).unwrap();
+        assert_eq!(pub_key.response, HostQueryResponse::PubKey { key: "04ab".to_string() });
+        let cosmwasm_std::CosmosMsg::Wasm(cosmwasm_std::WasmMsg::Execute { contract_addr, msg, .. }) = &response.messages[0].msg else {
+            panic!("expected the callback");
+        };
+        assert_eq!((contract_addr.as_str(), response.messages[0].id), ("watcher", keys::CALLBACK_NOTIFICATION_REPLY_ID));
+        let HostQueryCallbackMsg::HostQueryResult { results, error } = cosmwasm_std::from_json(msg).unwrap();
+        assert_eq!((results.len(), error), (2, None));
+
+        // A garbled answer is reported to the callback without failing the ack
+        execute::query_host(deps.as_mut(), mock_env(), mock_info("alice", &[]), queries, Some("watcher".to_string()), None)
+            .unwrap();
+        packet_sent(deps.as_mut(), 41);
+        let ack_data = AcknowledgementData::Result(Binary::from(b"garbage".to_vec()));
+        let response = crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(41), &ack_data)).unwrap();
+        let cosmwasm_std::CosmosMsg::Wasm(cosmwasm_std::WasmMsg::Execute { msg, .. }) = &response.messages[0].msg else {
+            panic!("expected the callback");
+        };
+        let HostQueryCallbackMsg::HostQueryResult { results, error } = cosmwasm_std::from_json(msg).unwrap();
+        assert!(results.is_empty() && error.is_some());
+
+        // 'MsgModuleQuerySafe' can only be sent as protobuf
+        use_proto3json(deps.as_mut().storage);
+        let err = execute::query_host(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![HostQuery::IcaHostParams {}], None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPacketEncoding(_)));
+    }
+
+    #[test]
+    fn packets_are_checked_against_the_host_allow_list() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::host_params::HostParamsSource;
+        use crate::types::host_query::{proto, HostQuery};
+        use prost::Message;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let bank_send = cosmwasm_std::CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+            to_address: "jkl1bob".to_string(),
+            amount: cosmwasm_std::coins(1, "ujkl"),
+        });
+        let stargate_msg = |type_url: &str| cosmwasm_std::CosmosMsg::Stargate { type_url: type_url.to_string(), value: Binary::default() };
+        let send = |deps: DepsMut, messages| execute::send_cosmos_msgs(deps, mock_env(), mock_info("alice", &[]), messages, None, None, false);
+
+        // Nothing is checked until the params are cached
+        send(deps.as_mut(), vec![stargate_msg("/cosmos.staking.v1beta1.MsgDelegate")]).unwrap();
+        // Messages the ICA can't sign are rejected, not panicked on
+        let custom = cosmwasm_std::from_json::<cosmwasm_std::CosmosMsg>(br#"{"custom":{}}"#).unwrap();
+        let err = send(deps.as_mut(), vec![bank_send.clone(), custom]).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedCosmosMsg(_)));
+
+        let allow_messages = |deps: DepsMut, sender: &str, allow_messages: Option<Vec<&str>>| {
+            let allow_messages = allow_messages.map(|messages| messages.into_iter().map(str::to_string).collect());
+            execute::set_host_allow_messages(deps, mock_env(), mock_info(sender, &[]), allow_messages)
+        };
+        let err = allow_messages(deps.as_mut(), "mallory", Some(vec!["*"])).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        allow_messages(
+            deps.as_mut(),
+            "factory",
+            Some(vec!["/cosmos.bank.v1beta1.MsgSend", crate::types::host_query::MSG_MODULE_QUERY_SAFE_TYPE_URL]),
+        )
+        .unwrap();
+
+        send(deps.as_mut(), vec![bank_send.clone()]).unwrap();
+        let delegate = stargate_msg("/cosmos.staking.v1beta1.MsgDelegate");
+        let err = send(deps.as_mut(), vec![delegate.clone(), bank_send.clone(), delegate.clone()]).unwrap_err();
+        assert_eq!(err.to_string(), "the host does not allow these messages: /cosmos.staking.v1beta1.MsgDelegate");
+        let err = execute::withdraw_from_ica(deps.as_mut(), mock_env(), mock_info("alice", &[]), cosmwasm_std::coins(1, "ujkl"), None, None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::MessagesNotAllowed(messages) if messages == ["/ibc.applications.transfer.v1.MsgTransfer"]));
+
+        // The host's answer replaces the cached params
+        execute::query_host(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![HostQuery::IcaHostParams {}], None, None).unwrap();
+        packet_sent(deps.as_mut(), 50);
+        let params = proto::QueryParamsResponse {
+            params: Some(proto::Params { host_enabled: true, allow_messages: vec!["*".to_string()] }),
+        };
+        let tx_msg_data = proto::TxMsgData {
+            msg_responses: vec![cosmos_sdk_proto::Any {
+                type_url: "/ibc.applications.interchain_accounts.host.v1.MsgModuleQuerySafeResponse".to_string(),
+                value: proto::MsgModuleQuerySafeResponse { height: 12, responses: vec![params.encode_to_vec()] }.encode_to_vec(),
+            }],
+        };
+        let ack_data = AcknowledgementData::Result(Binary::from(tx_msg_data.encode_to_vec()));
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(50), &ack_data)).unwrap();
+        let params = state::HOST_PARAMS.load(deps.as_ref().storage).unwrap();
+        assert_eq!(params.source, HostParamsSource::HostQuery { height: 12 });
+        send(deps.as_mut(), vec![delegate.clone()]).unwrap();
+
+        // A disabled host takes nothing, until the cache is cleared
+        allow_messages(deps.as_mut(), "alice", Some(vec![])).unwrap();
+        state::HOST_PARAMS
+            .update(deps.as_mut().storage, |mut params| -> StdResult<_> {
+                params.host_enabled = false;
+                Ok(params)
+            })
+            .unwrap();
+        let err = send(deps.as_mut(), vec![bank_send.clone()]).unwrap_err();
+        assert!(matches!(err, ContractError::HostDisabled {}));
+        allow_messages(deps.as_mut(), "alice", None).unwrap();
+        send(deps.as_mut(), vec![bank_send]).unwrap();
+    }
+
+    #[test]
+    fn jackal_signer_fields_are_filled_with_the_ica() {
+        use crate::helpers::filetree_helpers::{hash_and_hex, merkle_path, owner_address};
+        use crate::types::filetree::{MsgChangeOwner, MsgPostFile};
+        use prost::Message;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let stargate_msg = |type_url: &str, value: Vec<u8>| cosmwasm_std::CosmosMsg::Stargate { type_url: type_url.to_string(), value: Binary(value) };
+        let send = |deps: DepsMut, messages| {
+            execute::send_cosmos_msgs(deps, mock_env(), mock_info("alice", &[]), messages, None, None, true)
+        };
+
+        let post_file = MsgPostFile { contents: "fid".to_string(), ..Default::default() };
+        let change_owner = MsgChangeOwner {
+            creator: "jkl1ica".to_string(),
+            address: merkle_path("s/home/a.txt"),
+            new_owner: "owner".to_string(),
+            ..Default::default()
+        };
+        let response = send(
+            deps.as_mut(),
+            vec![
+                stargate_msg("/canine_chain.filetree.MsgPostFile", post_file.encode_to_vec()),
+                stargate_msg("/canine_chain.filetree.MsgChangeOwner", change_owner.encode_to_vec()),
+                stargate_msg("/cosmos.bank.v1beta1.MsgSend", vec![1, 2, 3]),
+            ],
+        )
+        .unwrap();
+        let tx = sent_tx(&response);
+        let post_file = MsgPostFile::decode(tx.messages[0].value.as_slice()).unwrap();
+        assert_eq!((post_file.creator.as_str(), post_file.account), ("jkl1ica", hash_and_hex("jkl1ica")));
+        let change_owner = MsgChangeOwner::decode(tx.messages[1].value.as_slice()).unwrap();
+        assert_eq!(change_owner.file_owner, owner_address(&merkle_path("s/home/a.txt"), "jkl1ica"));
+        assert_eq!(tx.messages[2].value, vec![1, 2, 3]);
+
+        // Messages signed for another account never leave the outpost
+        let post_file = MsgPostFile { creator: "jkl1bob".to_string(), ..Default::default() };
+        let err = send(deps.as_mut(), vec![stargate_msg("/canine_chain.filetree.MsgPostFile", post_file.encode_to_vec())]).unwrap_err();
+        assert!(matches!(err, ContractError::SignerMismatch { field, found, .. } if field == "creator" && found == "jkl1bob"));
+    }
+
+    #[test]
+    fn jackal_msgs_are_signed_by_the_ica() {
+        use crate::helpers::filetree_helpers::{hash_and_hex, merkle_helper};
+        use crate::types::filetree::{MsgDeleteFile, MsgPostFile};
+        use prost::Message;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+
+        let msg: ExecuteMsg = cosmwasm_std::from_json(
+            br#"{"send_jackal_msgs":{"messages":[
+                {"filetree":{"post_file":{"path":"s/home/a.txt","contents":"fid","viewers":"{}","editors":"{}","tracking_number":"uuid"}}},
+                {"filetree":{"delete_file":{"path":"s/home/b.txt"}}}
+            ]}}"#,
+        )
+        .unwrap();
+        let ExecuteMsg::SendJackalMsgs { messages, packet_memo, timeout_seconds } = msg else {
+            panic!("expected SendJackalMsgs");
+        };
+        let err = execute::send_jackal_msgs(deps.as_mut(), mock_env(), mock_info("bob", &[]), messages.clone(), None, None).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(_)));
+        let response =
+            execute::send_jackal_msgs(deps.as_mut(), mock_env(), mock_info("alice", &[]), messages, packet_memo, timeout_seconds).unwrap();
+
+        let tx = sent_tx(&response);
+        assert_eq!(tx.messages[0].type_url, "/canine_chain.filetree.MsgPostFile");
+        let post_file = MsgPostFile::decode(tx.messages[0].value.as_slice()).unwrap();
+        assert_eq!((post_file.creator.as_str(), post_file.account), ("jkl1ica", hash_and_hex("jkl1ica")));
+        assert_eq!((post_file.hash_parent, post_file.hash_child), merkle_helper("s/home/a.txt"));
+        let delete_file = MsgDeleteFile::decode(tx.messages[1].value.as_slice()).unwrap();
+        assert_eq!(delete_file.hash_path, crate::helpers::filetree_helpers::merkle_path("s/home/b.txt"));
+
+        // The host can only decode them as protobuf
+        use_proto3json(deps.as_mut().storage);
+        let err = execute::send_jackal_msgs(deps.as_mut(), mock_env(), mock_info("alice", &[]), vec![], None, None).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPacketEncoding(_)));
+    }
+
+    #[test]
+    fn rns_names_are_checked_before_sending() {
+        use crate::helpers::rns_helpers::validate_name;
+        use crate::types::jackal_msg::{JackalMsg, RnsMsg};
+        use crate::types::rns::{MsgBid, MsgDelRecord};
+        use prost::Message;
+
+        for name in ["alice.jkl", "a-1.jkl", "0.jkl"] {
+            validate_name(name).unwrap();
+        }
+        for name in ["alice", "alice.com", "Alice.jkl", ".jkl", "-alice.jkl", "app.alice.jkl", "al ice.jkl"] {
+            assert!(matches!(validate_name(name), Err(ContractError::InvalidRnsName { .. })), "{name}");
+        }
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let send = |deps: DepsMut, messages: Vec<RnsMsg>| {
+            let messages = messages.into_iter().map(JackalMsg::Rns).collect();
+            execute::send_jackal_msgs(deps, mock_env(), mock_info("alice", &[]), messages, None, None)
+        };
+
+        let err = send(deps.as_mut(), vec![RnsMsg::RemoveRecord { name: "alice.jkl".to_string(), record: "App".to_string() }]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid rns name App: only lowercase letters, digits and hyphens are allowed");
+
+        let response = send(
+            deps.as_mut(),
+            vec![
+                RnsMsg::RemoveRecord { name: "alice.jkl".to_string(), record: "app".to_string() },
+                RnsMsg::Bid { name: "bob.jkl".to_string(), bid: cosmwasm_std::coin(100, "ujkl") },
+                RnsMsg::Renew { name: "alice.jkl".to_string(), years: 2, data: r#"{"avatar":"a.png"}"#.to_string() },
+            ],
+        )
+        .unwrap();
+        let tx = sent_tx(&response);
+        let del_record = MsgDelRecord::decode(tx.messages[0].value.as_slice()).unwrap();
+        assert_eq!((del_record.creator.as_str(), del_record.name.as_str()), ("jkl1ica", "app.alice.jkl"));
+        assert_eq!(tx.messages[1].type_url, "/canine_chain.rns.MsgBid");
+        let bid = MsgBid::decode(tx.messages[1].value.as_slice()).unwrap().bid.unwrap();
+        assert_eq!((bid.denom.as_str(), bid.amount.as_str()), ("ujkl", "100"));
+        // Renewing keeps the data it's given
+        let renew = crate::types::rns::MsgRegister::decode(tx.messages[2].value.as_slice()).unwrap();
+        assert_eq!((renew.years, renew.data.as_str()), (2, r#"{"avatar":"a.png"}"#));
+    }
+
+    #[test]
+    fn new_viewers_are_notified() {
+        use crate::helpers::filetree_helpers::merkle_path;
+        use crate::types::jackal_msg::{shared_file_notification, FiletreeMsg, JackalMsg, NotificationsMsg};
+        use crate::types::notifications::{MsgBlockSenders, MsgCreateNotifications};
+        use prost::Message;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let messages = vec![
+            JackalMsg::Filetree(FiletreeMsg::AddViewers {
+                path: "s/home/a.txt".to_string(),
+                viewer_ids: vec!["id1".to_string(), "id2".to_string()],
+                viewer_keys: vec!["key1".to_string(), "key2".to_string()],
+                notify: vec!["jkl1bob".to_string(), "jkl1carol".to_string()],
+            }),
+            JackalMsg::Notifications(NotificationsMsg::BlockSenders { senders: vec!["jkl1spam".to_string(), "jkl1scam".to_string()] }),
+        ];
+        let response = execute::send_jackal_msgs(deps.as_mut(), mock_env(), mock_info("alice", &[]), messages, None, None).unwrap();
+        assert_eq!(response.messages.len(), 2);
+
+        // The notifications go in their own packet, so a recipient without a counter can't fail the first one
+        let tx = sent_tx(&response);
+        let type_urls: Vec<_> = tx.messages.iter().map(|msg| msg.type_url.as_str()).collect();
+        assert_eq!(type_urls, vec!["/canine_chain.filetree.MsgAddViewers", "/canine_chain.notifications.MsgBlockSenders"]);
+        let block = MsgBlockSenders::decode(tx.messages[1].value.as_slice()).unwrap();
+        assert_eq!(block.sender_ids, "jkl1spam,jkl1scam");
+        let tx = sent_tx_at(&response, 1);
+        let type_urls: Vec<_> = tx.messages.iter().map(|msg| msg.type_url.as_str()).collect();
+        assert_eq!(type_urls, vec!["/canine_chain.notifications.MsgCreateNotifications"; 2]);
+        let notification = MsgCreateNotifications::decode(tx.messages[1].value.as_slice()).unwrap();
+        assert_eq!(notification.address, "jkl1carol");
+        assert_eq!(notification.notification, shared_file_notification("jkl1ica", &merkle_path("s/home/a.txt")));
+        cosmwasm_std::from_json::<serde::de::IgnoredAny>(notification.notification.as_bytes()).unwrap();
+
+        // Nothing to notify, nothing extra to send
+        let messages = vec![JackalMsg::Notifications(NotificationsMsg::SetCounter {})];
+        let response = execute::send_jackal_msgs(deps.as_mut(), mock_env(), mock_info("alice", &[]), messages, None, None).unwrap();
+        assert_eq!(response.messages.len(), 1);
+
+        // Notifications are JSON objects
+        for notification in ["", "hello", r#"["a"]"#, r#"{"msg":"#] {
+            let messages = vec![JackalMsg::Notifications(NotificationsMsg::Notify {
+                to: "jkl1bob".to_string(),
+                notification: notification.to_string(),
+            })];
+            let err = execute::send_jackal_msgs(deps.as_mut(), mock_env(), mock_info("alice", &[]), messages, None, None).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidNotification(_)));
+        }
+    }
+
+    #[test]
+    fn ica_takes_part_in_governance() {
+        use crate::types::gov::{MsgSubmitProposal, ProposalMsg};
+        use cosmos_sdk_proto::cosmos::gov::v1::{MsgDeposit, MsgVoteWeighted};
+        use cosmwasm_std::{Decimal, VoteOption, WeightedVoteOption};
+        use prost::Message;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+
+        let mut options = vec![
+            WeightedVoteOption { option: VoteOption::Yes, weight: Decimal::percent(70) },
+            WeightedVoteOption { option: VoteOption::Abstain, weight: Decimal::percent(20) },
+        ];
+        let err = execute::vote_weighted(deps.as_mut(), mock_env(), mock_info("alice", &[]), 4, options.clone(), None).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidVoteWeights {}));
+        options[1].weight = Decimal::percent(30);
+        let err = execute::vote_weighted(deps.as_mut(), mock_env(), mock_info("bob", &[]), 4, options.clone(), None).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(_)));
+
+        let response = execute::vote_weighted(deps.as_mut(), mock_env(), mock_info("alice", &[]), 4, options, None).unwrap();
+        assert_eq!(response.events[0].ty, "OUTPOST:vote_weighted");
+        assert_eq!(attribute_of(&response, "proposal_id"), "4");
+        let tx = sent_tx(&response);
+        assert_eq!(tx.messages[0].type_url, "/cosmos.gov.v1.MsgVoteWeighted");
+        let vote = MsgVoteWeighted::decode(tx.messages[0].value.as_slice()).unwrap();
+        assert_eq!((vote.proposal_id, vote.voter.as_str(), vote.options.len()), (4, "jkl1ica", 2));
+
+        let response = execute::submit_proposal(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            "Fund the DAO".to_string(),
+            "Send the DAO 10jkl".to_string(),
+            vec![ProposalMsg { type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(), value: cosmwasm_std::Binary::from(b"send".to_vec()) }],
+            cosmwasm_std::coins(100, "ujkl"),
+            None,
+            None,
+        )
+        .unwrap();
+        let tx = sent_tx(&response);
+        assert_eq!(tx.messages[0].type_url, "/cosmos.gov.v1.MsgSubmitProposal");
+        let proposal = MsgSubmitProposal::decode(tx.messages[0].value.as_slice()).unwrap();
+        assert_eq!((proposal.proposer.as_str(), proposal.title.as_str()), ("jkl1ica", "Fund the DAO"));
+        assert_eq!(proposal.messages[0].value, b"send".to_vec());
+        assert_eq!(proposal.initial_deposit[0].amount, "100");
+
+        let err = execute::deposit(deps.as_mut(), mock_env(), mock_info("alice", &[]), 5, vec![], None).unwrap_err();
+        assert!(matches!(err, ContractError::NoFunds {}));
+        let response = execute::deposit(deps.as_mut(), mock_env(), mock_info("alice", &[]), 5, cosmwasm_std::coins(50, "ujkl"), None).unwrap();
+        let deposit = MsgDeposit::decode(sent_tx(&response).messages[0].value.as_slice()).unwrap();
+        assert_eq!((deposit.proposal_id, deposit.depositor.as_str()), (5, "jkl1ica"));
+
+        // Proposals and deposits are only encoded as protobuf
+        state::STATE
+            .update(deps.as_mut().storage, |mut contract_state| -> StdResult<_> {
+                contract_state.ica_info.as_mut().unwrap().encoding = crate::ibc::types::metadata::TxEncoding::Proto3Json;
+                Ok(contract_state)
+            })
+            .unwrap();
+        let err = execute::deposit(deps.as_mut(), mock_env(), mock_info("alice", &[]), 5, cosmwasm_std::coins(50, "ujkl"), None).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPacketEncoding(_)));
+
+        // The sender is checked before the ICA
+        state::STATE
+            .update(deps.as_mut().storage, |mut contract_state| -> StdResult<_> {
+                contract_state.ica_info = None;
+                Ok(contract_state)
+            })
+            .unwrap();
+        let err = execute::deposit(deps.as_mut(), mock_env(), mock_info("bob", &[]), 5, cosmwasm_std::coins(50, "ujkl"), None).unwrap_err();
+        assert!(matches!(err, ContractError::OwnershipError(_)));
+    }
+
+    #[test]
+    fn grants_to_invalid_grantees_are_rejected() {
+        use crate::types::grants::Authorization;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let now = mock_env().block.time;
+
+        for grantee in ["jkl1ica", "jkl1HOT", "jkl1hôt", "jkl1"] {
+            let err = execute::grant_authorization(deps.as_mut(), mock_env(), mock_info("alice", &[]), grantee.to_string(), Authorization::Filetree {}, None, None)
+                .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidAddress {}));
+            let err = execute::revoke_authorization(deps.as_mut(), mock_env(), mock_info("alice", &[]), grantee.to_string(), Authorization::Filetree {}, None)
+                .unwrap_err();
+            assert!(matches!(err, ContractError::InvalidAddress {}));
+            let err = execute::revoke_allowance(deps.as_mut(), mock_env(), mock_info("alice", &[]), grantee.to_string(), None).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidAddress {}));
+        }
+        let err = execute::grant_authorization(deps.as_mut(), mock_env(), mock_info("alice", &[]), "jkl1hot".to_string(), Authorization::Filetree {}, Some(now), None)
+            .unwrap_err();
+        assert!(matches!(err, ContractError::InvalidExpiration {}));
+
+        // Authz and feegrant messages can only be sent as protobuf
+        use_proto3json(deps.as_mut().storage);
+        let err = execute::revoke_allowance(deps.as_mut(), mock_env(), mock_info("alice", &[]), "jkl1hot".to_string(), None).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedPacketEncoding(_)));
+    }
+
+    #[test]
+    fn authz_grants_are_recorded_once_acknowledged() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::grants::{active_grants, Authorization, MsgGrant, FILETREE_MSG_TYPE_URLS};
+        use prost::Message;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let now = mock_env().block.time;
+
+        let response =
+            execute::grant_authorization(deps.as_mut(), mock_env(), mock_info("alice", &[]), "jkl1hot".to_string(), Authorization::Filetree {}, Some(now.plus_days(30)), None)
+                .unwrap();
+        assert_eq!(attribute_of(&response, "grantee"), "jkl1hot");
+        let tx = sent_tx(&response);
+        assert_eq!(tx.messages.len(), FILETREE_MSG_TYPE_URLS.len());
+        let grant = MsgGrant::decode(tx.messages[1].value.as_slice()).unwrap();
+        assert_eq!((grant.granter.as_str(), grant.grantee.as_str()), ("jkl1ica", "jkl1hot"));
+        let grant = grant.grant.unwrap();
+        assert_eq!(grant.expiration.unwrap().seconds as u64, now.plus_days(30).seconds());
+        let authorization: cosmos_sdk_proto::cosmos::authz::v1beta1::GenericAuthorization =
+            Message::decode(grant.authorization.unwrap().value.as_slice()).unwrap();
+        assert_eq!(authorization.msg, "/canine_chain.filetree.MsgPostFile");
+
+        // Nothing is recorded until the host acknowledges the grants
+        packet_sent(deps.as_mut(), 4);
+        assert!(active_grants(deps.as_ref().storage, None, now).unwrap().authorizations.is_empty());
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(4), &AcknowledgementData::Result(Binary::default()))).unwrap();
+        let grants = active_grants(deps.as_ref().storage, Some("jkl1hot"), now).unwrap();
+        assert_eq!(grants.authorizations.len(), FILETREE_MSG_TYPE_URLS.len());
+        let grants = active_grants(deps.as_ref().storage, Some("jkl1cold"), now).unwrap();
+        assert!(grants.authorizations.is_empty() && grants.allowances.is_empty());
+    }
+
+    #[test]
+    fn failed_revocations_keep_the_grant() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::grants::{self, active_grants, Authorization, AuthzGrant, GrantChange, FILETREE_MSG_TYPE_URLS};
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let now = mock_env().block.time;
+        let grants = FILETREE_MSG_TYPE_URLS
+            .iter()
+            .map(|msg_type_url| AuthzGrant {
+                grantee: "jkl1hot".to_string(),
+                msg_type_url: msg_type_url.to_string(),
+                expiration: None,
+                granted_at: now,
+            })
+            .collect();
+        grants::apply(deps.as_mut().storage, GrantChange::Grant { grants }, now).unwrap();
+
+        let delete_file = Authorization::Generic { msg_type_url: "/canine_chain.filetree.MsgDeleteFile".to_string() };
+        execute::revoke_authorization(deps.as_mut(), mock_env(), mock_info("alice", &[]), "jkl1hot".to_string(), delete_file.clone(), None).unwrap();
+        packet_sent(deps.as_mut(), 4);
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(4), &AcknowledgementData::Error("oops".to_string()))).unwrap();
+        assert_eq!(active_grants(deps.as_ref().storage, None, now).unwrap().authorizations.len(), 5);
+
+        execute::revoke_authorization(deps.as_mut(), mock_env(), mock_info("alice", &[]), "jkl1hot".to_string(), delete_file, None).unwrap();
+        packet_sent(deps.as_mut(), 5);
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(5), &AcknowledgementData::Result(Binary::default()))).unwrap();
+        let grants = active_grants(deps.as_ref().storage, None, now).unwrap();
+        assert_eq!(grants.authorizations.len(), 4);
+        assert!(grants.authorizations.iter().all(|grant| grant.msg_type_url != "/canine_chain.filetree.MsgDeleteFile"));
+    }
+
+    #[test]
+    fn fee_allowances_are_revoked_before_being_replaced() {
+        use crate::ibc::types::packet::acknowledgement::AcknowledgementData;
+        use crate::types::grants::active_grants;
+        use cosmwasm_std::coins;
+        use cosmos_sdk_proto::cosmos::feegrant::v1beta1::MsgGrantAllowance;
+        use prost::Message;
+
+        let mut deps = mock_dependencies();
+        open_outpost(deps.as_mut());
+        let now = mock_env().block.time;
+
+        let allowed_messages = vec!["/canine_chain.filetree.MsgPostFile".to_string()];
+        let response = execute::grant_allowance(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            "jkl1hot".to_string(),
+            coins(1_000, "ujkl"),
+            None,
+            allowed_messages,
+            None,
+        )
+        .unwrap();
+        let tx = sent_tx(&response);
+        assert_eq!(tx.messages.len(), 1);
+        let grant = MsgGrantAllowance::decode(tx.messages[0].value.as_slice()).unwrap();
+        assert_eq!(grant.allowance.unwrap().type_url, "/cosmos.feegrant.v1beta1.AllowedMsgAllowance");
+        packet_sent(deps.as_mut(), 4);
+        crate::ibc::relay::ibc_packet_ack(deps.as_mut(), mock_env(), ack(ica_packet(4), &AcknowledgementData::Result(Binary::default()))).unwrap();
+        let grants = active_grants(deps.as_ref().storage, Some("jkl1hot"), now).unwrap();
+        assert_eq!(grants.allowances[0].spend_limit, coins(1_000, "ujkl"));
+
+        // The existing allowance is revoked before it's replaced
+        let response = execute::grant_allowance(deps.as_mut(), mock_env(), mock_info("alice", &[]), "jkl1hot".to_string(), vec![], None, vec![], None).unwrap();
+        let type_urls: Vec<_> = sent_tx(&response).messages.into_iter().map(|msg| msg.type_url).collect();
+        assert_eq!(type_urls, vec!["/cosmos.feegrant.v1beta1.MsgRevokeAllowance", "/cosmos.feegrant.v1beta1.MsgGrantAllowance"]);
     }
 }
@@ -35,6 +35,11 @@ pub fn merkle_path(path: &str) -> String {
     total
 }
 
+/// the key filetree stores a file under for one owner: Hex[ hash( "o" + merkle_path + Hex[ hash(owner address) ] ) ]
+pub fn owner_address(merkle_path: &str, owner_address: &str) -> String {
+    hash_and_hex(&format!("o{}{}", merkle_path, hash_and_hex(owner_address)))
+}
+
 /// return the merkle path of the parent and the hash_and_hex of the child
 pub fn merkle_helper(arg_hashpath: &str) -> (String, String) {
     let trim_path = arg_hashpath.trim_end_matches('/');
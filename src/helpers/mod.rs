@@ -1,2 +1,3 @@
-//! This module contains helper functions to prepare canine-chain filetree and storage module msgs
+//! This module contains helper functions to prepare canine-chain filetree, storage and rns module msgs
 pub mod filetree_helpers;
+pub mod rns_helpers;
@@ -0,0 +1,104 @@
+//! # rns_helpers
+//!
+//! helper functions to check rns names before they are sent to canine-chain, so a malformed name
+//! doesn't cost a failed ICA packet
+//! full documentation for rns module here https://github.com/JackalLabs/canine-chain/tree/master/x/rns
+
+use crate::types::ContractError;
+
+/// the top level domains canine-chain registers names under
+pub const SUPPORTED_TLDS: &[&str] = &["jkl"];
+
+/// check a name with its top level domain, e.g. `alice.jkl`
+pub fn validate_name(name: &str) -> Result<(), ContractError> {
+    let invalid = |reason: &str| ContractError::InvalidRnsName {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    };
+
+    let Some((label, tld)) = name.split_once('.') else {
+        return Err(invalid("missing top level domain"));
+    };
+    if !SUPPORTED_TLDS.contains(&tld) {
+        return Err(invalid("unsupported top level domain"));
+    }
+    validate_label(label).map_err(invalid)
+}
+
+/// check a sub-domain record, e.g. `app` for `app.alice.jkl`
+pub fn validate_record(record: &str) -> Result<(), ContractError> {
+    validate_label(record).map_err(|reason| ContractError::InvalidRnsName {
+        name: record.to_string(),
+        reason: reason.to_string(),
+    })
+}
+
+/// the full name of a sub-domain record: `record.name`
+pub fn record_name(record: &str, name: &str) -> String {
+    format!("{record}.{name}")
+}
+
+/// labels are lowercase letters, digits and inner hyphens
+fn validate_label(label: &str) -> Result<(), &'static str> {
+    if label.is_empty() {
+        return Err("empty label");
+    }
+    if !label.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-') {
+        return Err("only lowercase letters, digits and hyphens are allowed");
+    }
+    if label.starts_with('-') || label.ends_with('-') {
+        return Err("labels can't start or end with a hyphen");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rejection(result: Result<(), ContractError>) -> String {
+        match result {
+            Err(ContractError::InvalidRnsName { reason, .. }) => reason,
+            other => panic!("expected an invalid rns name, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn labels_are_lowercase_with_inner_hyphens() {
+        assert!(validate_label("alice").is_ok());
+        assert!(validate_label("my-app-2").is_ok());
+        assert!(validate_label("0").is_ok());
+
+        assert_eq!(validate_label(""), Err("empty label"));
+        for label in ["Alice", "ALICE", "alicé", "al_ice", "al ice", "a.b"] {
+            assert_eq!(validate_label(label), Err("only lowercase letters, digits and hyphens are allowed"), "{label}");
+        }
+        for label in ["-alice", "alice-", "-"] {
+            assert_eq!(validate_label(label), Err("labels can't start or end with a hyphen"), "{label}");
+        }
+    }
+
+    #[test]
+    fn names_are_one_label_and_a_supported_tld() {
+        assert!(validate_name("alice.jkl").is_ok());
+        assert!(validate_name("my-app.jkl").is_ok());
+
+        assert_eq!(rejection(validate_name("alice")), "missing top level domain");
+        for name in ["alice.", "alice.com", "alice.JKL", "app.alice.jkl", "alice.jkl.jkl"] {
+            assert_eq!(rejection(validate_name(name)), "unsupported top level domain", "{name}");
+        }
+        assert_eq!(rejection(validate_name(".jkl")), "empty label");
+        assert_eq!(rejection(validate_name("Alice.jkl")), "only lowercase letters, digits and hyphens are allowed");
+        assert_eq!(rejection(validate_name("-alice.jkl")), "labels can't start or end with a hyphen");
+        assert_eq!(rejection(validate_name("alice-.jkl")), "labels can't start or end with a hyphen");
+    }
+
+    #[test]
+    fn records_are_single_labels() {
+        assert!(validate_record("app").is_ok());
+        assert_eq!(rejection(validate_record("")), "empty label");
+        assert_eq!(rejection(validate_record("app.alice")), "only lowercase letters, digits and hyphens are allowed");
+        assert_eq!(rejection(validate_record("app-")), "labels can't start or end with a hyphen");
+        assert_eq!(record_name("app", "alice.jkl"), "app.alice.jkl");
+    }
+}
@@ -8,6 +8,8 @@ use cosmwasm_std::{
 
 use super::types::{keys, metadata};
 use crate::types::{
+    callback::{callback_notification, channel_open_callback},
+    msg::OutpostFactoryExecuteMsg,
     state::{ChannelState, CHANNEL_STATE, STATE, ALLOW_CHANNEL_OPEN_INIT},
     ContractError,
 };
@@ -60,7 +62,7 @@ pub fn ibc_channel_close(
     msg: IbcChannelCloseMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     match msg {
-        IbcChannelCloseMsg::CloseInit { .. } => unimplemented!(),
+        IbcChannelCloseMsg::CloseInit { channel } => ibc_channel_close::init(deps, channel),
         IbcChannelCloseMsg::CloseConfirm { channel } => ibc_channel_close::confirm(deps, channel),
     }
 }
@@ -146,6 +148,15 @@ mod ibc_channel_open {
         if metadata.address.is_empty() {
             return Err(ContractError::InvalidAddress {});
         }
+        // Let the callback contract know the ICA is ready
+        let notification = callback_notification(
+            deps.storage,
+            &OutpostFactoryExecuteMsg::OutpostChannelOpened {
+                ica_address: metadata.address.clone(),
+            },
+        )?;
+        let on_channel_open = channel_open_callback(deps.storage)?;
+
         // save the address to the contract state
         STATE.update(
             deps.storage,
@@ -163,12 +174,41 @@ mod ibc_channel_open {
         CHANNEL_STATE.save(deps.storage, &ChannelState::new_open_channel(channel))?;
 
         // Return the response, emit events if needed. Core IBC modules will emit the events regardless.
-        Ok(IbcBasicResponse::default())
+        Ok(IbcBasicResponse::default()
+            .add_submessages(notification)
+            .add_submessages(on_channel_open))
     }
 }
 
 mod ibc_channel_close {
+    use crate::types::{decommission, state::ALLOW_CHANNEL_CLOSE_INIT};
+
     use super::*;
+
+    /// Handles the `ChanCloseInit` for the IBC module.
+    /// Only the contract itself may close its channel, which it does to decommission the outpost.
+    pub fn init(deps: DepsMut, channel: IbcChannel) -> Result<IbcBasicResponse, ContractError> {
+        if !ALLOW_CHANNEL_CLOSE_INIT.may_load(deps.storage)?.unwrap_or_default() {
+            return Err(ContractError::ChannelCloseInitNotAllowed {});
+        }
+        ALLOW_CHANNEL_CLOSE_INIT.save(deps.storage, &false)?;
+
+        let mut channel_state = CHANNEL_STATE.load(deps.storage)?;
+        if channel_state.channel != channel {
+            return Err(ContractError::InvalidChannelInContractState {});
+        }
+        channel_state.close();
+        CHANNEL_STATE.save(deps.storage, &channel_state)?;
+
+        let notification = if decommission::is_closing_channel(deps.storage)? {
+            decommission::finish(deps.storage)?
+        } else {
+            callback_notification(deps.storage, &OutpostFactoryExecuteMsg::OutpostChannelClosed {})?
+        };
+
+        Ok(IbcBasicResponse::default().add_submessages(notification))
+    }
+
     /// Handles the `ChanCloseConfirm` for the IBC module.
     pub fn confirm(deps: DepsMut, channel: IbcChannel) -> Result<IbcBasicResponse, ContractError> {
         // Validate that this is the stored channel
@@ -181,7 +221,10 @@ mod ibc_channel_close {
         channel_state.close();
         CHANNEL_STATE.save(deps.storage, &channel_state)?;
 
+        let notification =
+            callback_notification(deps.storage, &OutpostFactoryExecuteMsg::OutpostChannelClosed {})?;
+
         // Return the response, emit events if needed
-        Ok(IbcBasicResponse::default())
+        Ok(IbcBasicResponse::default().add_submessages(notification))
     }
 }
@@ -10,7 +10,11 @@ use cosmwasm_std::{
 };
 
 use crate::types::{
-    state::{CALLBACK_COUNTER, CHANNEL_STATE},
+    callback::callback_notification,
+    decommission, file_transfer, grants, host_query,
+    storage_purchase::{self, PurchaseStatus},
+    msg::OutpostFactoryExecuteMsg,
+    state::{PacketKind, CALLBACK_COUNTER, CHANNEL_STATE, PACKET_LEDGER},
     ContractError,
 };
 
@@ -20,13 +24,13 @@ use super::types::{events, packet::acknowledgement::AcknowledgementData};
 #[entry_point]
 pub fn ibc_packet_ack(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     ack: IbcPacketAckMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
     // This lets the ICA controller know whether or not the sent transactions succeeded.
     match from_binary(&ack.acknowledgement.data)? {
-        AcknowledgementData::Result(res) => ibc_packet_ack::success(deps, ack.original_packet, res),
-        AcknowledgementData::Error(err) => ibc_packet_ack::error(deps, ack.original_packet, err),
+        AcknowledgementData::Result(res) => ibc_packet_ack::success(deps, env, ack.original_packet, res),
+        AcknowledgementData::Error(err) => ibc_packet_ack::error(deps, env, ack.original_packet, err),
     }
 }
 
@@ -35,8 +39,40 @@ pub fn ibc_packet_ack(
 pub fn ibc_packet_timeout(
     deps: DepsMut,
     _env: Env,
-    _msg: IbcPacketTimeoutMsg,
+    msg: IbcPacketTimeoutMsg,
 ) -> Result<IbcBasicResponse, ContractError> {
+    let mut response = IbcBasicResponse::default();
+
+    // Nothing in the packet was executed on the host
+    match take_packet_kind(deps.storage, &msg.packet)? {
+        Some(PacketKind::Decommission) => {
+            decommission::fail(deps.storage, "cleanup packet timed out".to_string())?;
+        }
+        Some(PacketKind::FileTransfer { paths }) => {
+            file_transfer::on_packet_result(deps.storage, &paths, Err("packet timed out".to_string()))?;
+        }
+        Some(PacketKind::Withdrawal { receiver, amount, .. }) => {
+            response = response.add_event(events::withdrawal::failed(&msg.packet, &receiver, &amount, "packet timed out"));
+        }
+        // The channel is closing, so the funds stay on the ICA until the owner withdraws them
+        Some(PacketKind::StoragePurchase { id }) => {
+            let status = PurchaseStatus::Failed {
+                error: "packet timed out".to_string(),
+                refunded: false,
+            };
+            storage_purchase::set_status(deps.storage, id, status)?;
+        }
+        Some(PacketKind::HostQuery { callback, .. }) => {
+            if let Some(callback) = callback {
+                response = response.add_submessage(host_query::callback_msg(callback, vec![], Some("packet timed out".to_string()))?);
+            }
+        }
+        // The grants are unchanged on the host, like everything else in the packet
+        Some(PacketKind::Grant { .. }) => {}
+        // Transfers from this chain are settled through `sudo`, never on the ICA channel
+        Some(PacketKind::FundIca { .. } | PacketKind::PurchaseFunding { .. }) | None => {}
+    }
+
     // Increment the callback counter.
     CALLBACK_COUNTER.update(deps.storage, |mut cc| -> Result<_, ContractError> {
         cc.timeout();
@@ -51,7 +87,10 @@ pub fn ibc_packet_timeout(
         },
     )?;
 
-    Ok(IbcBasicResponse::default())
+    let notification =
+        callback_notification(deps.storage, &OutpostFactoryExecuteMsg::OutpostChannelClosed {})?;
+
+    Ok(response.add_submessages(notification))
 }
 
 /// Handles the `PacketReceive` for the IBC module.
@@ -66,6 +105,19 @@ pub fn ibc_packet_receive(
     unreachable!("ICA controller cannot receive packets")
 }
 
+/// Removes the packet from the packet ledger and returns why it was tracked, if it was.
+fn take_packet_kind(
+    storage: &mut dyn cosmwasm_std::Storage,
+    packet: &cosmwasm_std::IbcPacket,
+) -> Result<Option<PacketKind>, ContractError> {
+    let key = (packet.src.channel_id.as_str(), packet.sequence);
+    let kind = PACKET_LEDGER.may_load(storage, key)?;
+    if kind.is_some() {
+        PACKET_LEDGER.remove(storage, key);
+    }
+    Ok(kind)
+}
+
 mod ibc_packet_ack {
     use cosmwasm_std::{Binary, IbcPacket};
 
@@ -77,6 +129,7 @@ mod ibc_packet_ack {
     /// transaction was successfully executed on the host chain.
     pub fn success(
         deps: DepsMut,
+        env: Env,
         packet: IbcPacket,
         res: Binary,
     ) -> Result<IbcBasicResponse, ContractError> {
@@ -85,13 +138,51 @@ mod ibc_packet_ack {
             counter.success();
             Ok(counter)
         })?;
-        Ok(IbcBasicResponse::default().add_event(events::packet_ack::success(&packet, &res)))
+        let mut response = IbcBasicResponse::default().add_event(events::packet_ack::success(&packet, &res));
+
+        match take_packet_kind(deps.storage, &packet)? {
+            // The files are cleaned up and the funds are on their way back, the channel can go
+            Some(PacketKind::Decommission) => {
+                let close_channel_msg = decommission::close_channel(deps.storage, packet.src.channel_id.clone())?;
+                response = response.add_message(close_channel_msg);
+            }
+            Some(PacketKind::FileTransfer { paths }) => {
+                file_transfer::on_packet_result(deps.storage, &paths, Ok(()))?;
+            }
+            Some(PacketKind::Withdrawal { receiver, amount, purchase_id }) => {
+                if let Some(id) = purchase_id {
+                    storage_purchase::on_refund_sent(deps.storage, id)?;
+                }
+                response = response.add_event(events::withdrawal::sent(&packet, &receiver, &amount));
+            }
+            Some(PacketKind::StoragePurchase { id }) => {
+                storage_purchase::set_status(deps.storage, id, PurchaseStatus::Completed)?;
+            }
+            Some(PacketKind::HostQuery { queries, callback }) => {
+                // An undecodable answer must not fail the ack, or the relayer could never deliver it
+                let (results, error) = match host_query::save_results(deps.storage, queries, &res, env.block.time) {
+                    Ok(results) => (results, None),
+                    Err(err) => (vec![], Some(err.to_string())),
+                };
+                if let Some(error) = &error {
+                    response = response.add_attribute("host_query_error", error);
+                }
+                if let Some(callback) = callback {
+                    response = response.add_submessage(host_query::callback_msg(callback, results, error)?);
+                }
+            }
+            Some(PacketKind::Grant { change }) => grants::apply(deps.storage, change, env.block.time)?,
+            Some(PacketKind::FundIca { .. } | PacketKind::PurchaseFunding { .. }) | None => {}
+        }
+
+        Ok(response)
     }
 
     /// Handles the unsuccessful acknowledgement of an ica packet. This means that the
     /// transaction failed to execute on the host chain.
     pub fn error(
-        deps: DepsMut,
+        mut deps: DepsMut,
+        env: Env,
         packet: IbcPacket,
         err: String,
     ) -> Result<IbcBasicResponse, ContractError> {
@@ -100,6 +191,27 @@ mod ibc_packet_ack {
             counter.error();
             Ok(counter)
         })?;
-        Ok(IbcBasicResponse::default().add_event(events::packet_ack::error(&packet, &err)))
+        // ICA txs are atomic, so nothing in the packet was executed and the owner can try again
+        let mut response = IbcBasicResponse::default().add_event(events::packet_ack::error(&packet, &err));
+        match take_packet_kind(deps.storage, &packet)? {
+            Some(PacketKind::Decommission) => decommission::fail(deps.storage, err.clone())?,
+            Some(PacketKind::FileTransfer { paths }) => {
+                file_transfer::on_packet_result(deps.storage, &paths, Err(err.clone()))?;
+            }
+            Some(PacketKind::Withdrawal { receiver, amount, .. }) => {
+                response = response.add_event(events::withdrawal::failed(&packet, &receiver, &amount, &err));
+            }
+            Some(PacketKind::StoragePurchase { id }) => {
+                let refund_msg = storage_purchase::on_purchase_error(deps.branch(), &env, id, err.clone())?;
+                response = response.add_submessages(refund_msg);
+            }
+            Some(PacketKind::HostQuery { callback, .. }) => {
+                if let Some(callback) = callback {
+                    response = response.add_submessage(host_query::callback_msg(callback, vec![], Some(err.clone()))?);
+                }
+            }
+            Some(PacketKind::Grant { .. } | PacketKind::FundIca { .. } | PacketKind::PurchaseFunding { .. }) | None => {}
+        }
+        Ok(response)
     }
 }
@@ -38,6 +38,32 @@ pub mod packet_ack {
     }
 }
 
+/// contains the events emitted when the packet withdrawing the ICA's funds is acknowledged or times out.
+pub mod withdrawal {
+    use cosmwasm_std::Coin;
+
+    use super::*;
+
+    const EVENT_TYPE: &str = "withdraw_from_ica";
+
+    /// returns an event for a withdrawal whose transfers were sent from the host chain.
+    pub fn sent(packet: &IbcPacket, receiver: &str, amount: &[Coin]) -> Event {
+        Event::new(EVENT_TYPE)
+            .add_attributes(attributes::from_packet(packet))
+            .add_attribute(attributes::RECEIVER, receiver)
+            .add_attribute(attributes::AMOUNT, coins_to_string(amount))
+    }
+
+    /// returns an event for a withdrawal that failed on the host chain, the funds are still on the ICA.
+    pub fn failed(packet: &IbcPacket, receiver: &str, amount: &[Coin], err: &str) -> Event {
+        sent(packet, receiver, amount).add_attribute(attributes::ERROR, err)
+    }
+
+    fn coins_to_string(amount: &[Coin]) -> String {
+        amount.iter().map(Coin::to_string).collect::<Vec<_>>().join(",")
+    }
+}
+
 mod attributes {
     use super::*;
     use cosmwasm_std::Attribute;
@@ -49,6 +75,9 @@ mod attributes {
 
     pub const ERROR: &str = "error";
 
+    pub const RECEIVER: &str = "receiver";
+    pub const AMOUNT: &str = "amount";
+
     /// returns the attributes for uniquely identifying a packet.
     pub fn from_packet(packet: &IbcPacket) -> Vec<Attribute> {
         vec![
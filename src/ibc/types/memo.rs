@@ -0,0 +1,314 @@
+//! # Memo
+//!
+//! Typed builders for the packet memos read by IBC middleware:
+//! - [packet-forward-middleware](https://github.com/cosmos/ibc-apps/tree/main/middleware/packet-forward-middleware)
+//!   routes, under `forward`.
+//! - [ibc-hooks](https://github.com/cosmos/ibc-apps/tree/main/modules/ibc-hooks) contract calls under `wasm`,
+//!   and acknowledgement callbacks under `ibc_callback`.
+//! - [ADR-8](https://ibc.cosmos.network/architecture/adr-008-app-caller-cbs) callback requests, under
+//!   `src_callback` and `dest_callback`.
+//!
+//! The memo is assembled by hand rather than through a JSON value type, so that no floating point
+//! code ends up in the contract. Embedded contract messages are checked to be JSON objects first.
+//!
+//! ```
+//! use storage_outpost::ibc::types::memo::{Forward, PacketMemo};
+//!
+//! let memo = PacketMemo::default()
+//!     .with_forward(Forward::new("jkl1receiver", "channel-2"))
+//!     .to_json_string()
+//!     .unwrap();
+//! assert_eq!(memo, r#"{"forward":{"receiver":"jkl1receiver","port":"transfer","channel":"channel-2"}}"#);
+//! ```
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{from_json, to_json_string, Binary, Uint64};
+
+use crate::types::ContractError;
+
+use super::keys::TRANSFER_PORT_ID;
+
+/// A packet memo. Empty memos serialize to `{}`.
+#[cw_serde]
+#[derive(Default)]
+pub struct PacketMemo {
+    /// The packet-forward-middleware route taken once the packet arrives.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward: Option<Box<Forward>>,
+    /// The contract ibc-hooks calls with the transferred funds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasm: Option<WasmHook>,
+    /// The contract ibc-hooks calls back with the packet's acknowledgement or timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ibc_callback: Option<String>,
+    /// The ADR-8 callback requested on the sending chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub src_callback: Option<CallbackRequest>,
+    /// The ADR-8 callback requested on the receiving chain.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dest_callback: Option<CallbackRequest>,
+}
+
+/// A packet-forward-middleware hop.
+#[cw_serde]
+pub struct Forward {
+    /// The address receiving the funds on the next chain.
+    pub receiver: String,
+    /// The port to forward on. If not specified, [`TRANSFER_PORT_ID`] is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<String>,
+    /// The channel to forward on, on the chain the packet arrived on.
+    pub channel: String,
+    /// How long the forwarded packet may take, e.g. "10m".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<String>,
+    /// How many times the forward is retried on timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u8>,
+    /// The memo of the forwarded packet, e.g. the next hop.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<Box<PacketMemo>>,
+}
+
+/// An ibc-hooks contract call.
+#[cw_serde]
+pub struct WasmHook {
+    /// The contract to call. ibc-hooks requires the transfer's receiver to be this contract too.
+    pub contract: String,
+    /// The JSON encoded execute message, which must be a JSON object.
+    pub msg: Binary,
+}
+
+/// An ADR-8 callback request.
+#[cw_serde]
+pub struct CallbackRequest {
+    /// The contract to call back.
+    pub address: String,
+    /// The most gas the callback may use. If not specified, the chain's maximum is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<Uint64>,
+}
+
+impl Forward {
+    /// Creates a new hop forwarding to the receiver over the channel's transfer port.
+    pub fn new(receiver: impl Into<String>, channel: impl Into<String>) -> Self {
+        Self {
+            receiver: receiver.into(),
+            port: None,
+            channel: channel.into(),
+            timeout: None,
+            retries: None,
+            next: None,
+        }
+    }
+
+    /// Sets the memo of the forwarded packet.
+    pub fn with_next(mut self, next: PacketMemo) -> Self {
+        self.next = Some(Box::new(next));
+        self
+    }
+
+    fn to_json_string(&self) -> Result<String, ContractError> {
+        if self.receiver.is_empty() {
+            return Err(ContractError::InvalidMemo("forward receiver is empty".to_string()));
+        }
+        if !self.channel.starts_with("channel-") {
+            return Err(ContractError::InvalidMemo(format!("invalid forward channel: {}", self.channel)));
+        }
+
+        let mut fields = vec![
+            json_field("receiver", &self.receiver)?,
+            json_field("port", self.port.as_deref().unwrap_or(TRANSFER_PORT_ID))?,
+            json_field("channel", &self.channel)?,
+        ];
+        if let Some(timeout) = &self.timeout {
+            fields.push(json_field("timeout", timeout)?);
+        }
+        if let Some(retries) = self.retries {
+            fields.push(json_field("retries", &retries)?);
+        }
+        if let Some(next) = &self.next {
+            fields.push(format!(r#""next":{}"#, next.to_json_string()?));
+        }
+        Ok(json_object(&fields))
+    }
+}
+
+impl WasmHook {
+    /// Creates a new contract call with the given execute message.
+    pub fn new(contract: impl Into<String>, msg: &impl serde::Serialize) -> Result<Self, ContractError> {
+        Ok(Self {
+            contract: contract.into(),
+            msg: cosmwasm_std::to_json_binary(msg)?,
+        })
+    }
+
+    fn to_json_string(&self) -> Result<String, ContractError> {
+        if self.contract.is_empty() {
+            return Err(ContractError::InvalidMemo("wasm contract is empty".to_string()));
+        }
+        let msg = std::str::from_utf8(&self.msg)
+            .map_err(|_| ContractError::InvalidMemo("wasm msg is not utf-8".to_string()))?;
+        ensure_json_object(msg)?;
+
+        Ok(json_object(&[json_field("contract", &self.contract)?, format!(r#""msg":{}"#, msg.trim())]))
+    }
+}
+
+impl CallbackRequest {
+    fn to_json_string(&self) -> Result<String, ContractError> {
+        if self.address.is_empty() {
+            return Err(ContractError::InvalidMemo("callback address is empty".to_string()));
+        }
+
+        let mut fields = vec![json_field("address", &self.address)?];
+        if let Some(gas_limit) = self.gas_limit {
+            fields.push(json_field("gas_limit", &gas_limit)?);
+        }
+        Ok(json_object(&fields))
+    }
+}
+
+impl PacketMemo {
+    /// Routes the packet on with packet-forward-middleware.
+    pub fn with_forward(mut self, forward: Forward) -> Self {
+        self.forward = Some(Box::new(forward));
+        self
+    }
+
+    /// Has ibc-hooks call a contract with the transferred funds.
+    pub fn with_wasm(mut self, wasm: WasmHook) -> Self {
+        self.wasm = Some(wasm);
+        self
+    }
+
+    /// Has ibc-hooks call the contract back with the packet's acknowledgement or timeout.
+    pub fn with_ibc_callback(mut self, contract: impl Into<String>) -> Self {
+        self.ibc_callback = Some(contract.into());
+        self
+    }
+
+    /// Requests an ADR-8 callback on the sending chain.
+    pub fn with_src_callback(mut self, address: impl Into<String>, gas_limit: Option<u64>) -> Self {
+        self.src_callback = Some(CallbackRequest {
+            address: address.into(),
+            gas_limit: gas_limit.map(Uint64::new),
+        });
+        self
+    }
+
+    /// Requests an ADR-8 callback on the receiving chain.
+    pub fn with_dest_callback(mut self, address: impl Into<String>, gas_limit: Option<u64>) -> Self {
+        self.dest_callback = Some(CallbackRequest {
+            address: address.into(),
+            gas_limit: gas_limit.map(Uint64::new),
+        });
+        self
+    }
+
+    /// Validates the memo and returns its JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ContractError::InvalidMemo`] if a part of the memo is incomplete, or an embedded contract
+    /// message is not a JSON object.
+    pub fn to_json_string(&self) -> Result<String, ContractError> {
+        let mut fields = vec![];
+        if let Some(forward) = &self.forward {
+            fields.push(format!(r#""forward":{}"#, forward.to_json_string()?));
+        }
+        if let Some(wasm) = &self.wasm {
+            fields.push(format!(r#""wasm":{}"#, wasm.to_json_string()?));
+        }
+        if let Some(ibc_callback) = &self.ibc_callback {
+            fields.push(json_field("ibc_callback", ibc_callback)?);
+        }
+        if let Some(src_callback) = &self.src_callback {
+            fields.push(format!(r#""src_callback":{}"#, src_callback.to_json_string()?));
+        }
+        if let Some(dest_callback) = &self.dest_callback {
+            fields.push(format!(r#""dest_callback":{}"#, dest_callback.to_json_string()?));
+        }
+        Ok(json_object(&fields))
+    }
+}
+
+/// Checks a memo given as a string. Memos that look like JSON, i.e. that start with `{`, must be valid JSON
+/// objects, since middleware would otherwise skip or reject them. Any other memo is plain text.
+///
+/// # Errors
+///
+/// Returns [`ContractError::InvalidMemo`] if the memo starts with `{` but isn't a JSON object.
+pub fn validate(memo: &str) -> Result<(), ContractError> {
+    if memo.trim_start().starts_with('{') {
+        ensure_json_object(memo)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn ensure_json_object(json: &str) -> Result<(), ContractError> {
+    if !json.trim_start().starts_with('{') {
+        return Err(ContractError::InvalidMemo(format!("not a JSON object: {json}")));
+    }
+    from_json::<serde::de::IgnoredAny>(json.as_bytes())
+        .map_err(|err| ContractError::InvalidMemo(err.to_string()))?;
+    Ok(())
+}
+
+fn json_field(key: &str, value: &(impl serde::Serialize + ?Sized)) -> Result<String, ContractError> {
+    Ok(format!(r#""{key}":{}"#, to_json_string(value)?))
+}
+
+fn json_object(fields: &[String]) -> String {
+    format!("{{{}}}", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_memos_are_serialized_in_order() {
+        #[cosmwasm_schema::cw_serde]
+        enum HookMsg {
+            Deposit { to: String },
+        }
+
+        let next = PacketMemo::default()
+            .with_wasm(WasmHook::new("jkl1contract", &HookMsg::Deposit { to: "jkl1user".to_string() }).unwrap());
+        let mut forward = Forward::new("jkl1contract", "channel-2").with_next(next);
+        forward.timeout = Some("10m".to_string());
+        forward.retries = Some(2);
+        let memo = PacketMemo::default()
+            .with_forward(forward)
+            .with_ibc_callback("wasm1outpost")
+            .with_src_callback("wasm1outpost", Some(200_000));
+
+        assert_eq!(
+            memo.to_json_string().unwrap(),
+            concat!(
+                r#"{"forward":{"receiver":"jkl1contract","port":"transfer","channel":"channel-2","timeout":"10m","retries":2,"#,
+                r#""next":{"wasm":{"contract":"jkl1contract","msg":{"deposit":{"to":"jkl1user"}}}}},"#,
+                r#""ibc_callback":"wasm1outpost","src_callback":{"address":"wasm1outpost","gas_limit":"200000"}}"#,
+            )
+        );
+        assert_eq!(PacketMemo::default().to_json_string().unwrap(), "{}");
+    }
+
+    #[test]
+    fn invalid_memos_are_rejected() {
+        let hook = WasmHook {
+            contract: "jkl1contract".to_string(),
+            msg: Binary::from(br#"["not", "an", "object"]"#.as_slice()),
+        };
+        let err = PacketMemo::default().with_wasm(hook).to_json_string().unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMemo(_)));
+
+        let err = PacketMemo::default().with_forward(Forward::new("jkl1user", "transfer")).to_json_string().unwrap_err();
+        assert!(matches!(err, ContractError::InvalidMemo(_)));
+
+        assert!(validate("plain text memo").is_ok());
+        assert!(validate(r#"{"wasm":{"contract":"jkl1contract","msg":{}}}"#).is_ok());
+        assert!(validate(r#"{"wasm":{"contract":"#).is_err());
+    }
+}
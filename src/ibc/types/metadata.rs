@@ -228,6 +228,7 @@ mod tests {
             connection_id: "connection-0".to_string(),
             counterparty_connection_id: "connection-1".to_string(),
             counterparty_port_id: Some(super::super::keys::HOST_PORT_ID.to_string()),
+            tx_encoding: None,
             channel_ordering: None,
         };
 
@@ -257,6 +258,7 @@ mod tests {
             connection_id: "connection-0".to_string(),
             counterparty_connection_id: "connection-1".to_string(),
             counterparty_port_id: Some(super::super::keys::HOST_PORT_ID.to_string()),
+            tx_encoding: None,
             channel_ordering: None,
         };
 
@@ -2,6 +2,7 @@
 
 pub mod events;
 pub mod keys;
+pub mod memo;
 pub mod metadata;
 pub mod packet;
 pub mod stargate;
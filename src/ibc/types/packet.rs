@@ -3,7 +3,7 @@
 //! This module contains the packet data to be send to the ica host and acknowledgement data types.
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{to_json_binary, CosmosMsg, Env, IbcMsg, IbcTimeout, StdError, StdResult};
+use cosmwasm_std::{to_json_binary, CosmosMsg, Env, IbcMsg, IbcTimeout, StdResult};
 
 pub use cosmos_sdk_proto::ibc::applications::interchain_accounts::v1::CosmosTx;
 use cosmos_sdk_proto::traits::Message;
@@ -98,12 +98,12 @@ impl IcaPacketData {
     ///
     /// # Errors
     ///
-    /// Returns an error if the [`CosmosMsg`] cannot be serialized to [`cosmos_sdk_proto::Any`]
-    /// when using the [`TxEncoding::Protobuf`] encoding.
+    /// Returns an error if the [`CosmosMsg`] is not supported for the given encoding, or cannot be
+    /// serialized to [`cosmos_sdk_proto::Any`] when using the [`TxEncoding::Protobuf`] encoding.
     ///
     /// # Panics
     ///
-    /// Panics if the [`CosmosMsg`] is not supported for the given encoding.
+    /// Panics if a variant of a supported [`CosmosMsg`] is not supported for the given encoding.
     ///
     /// The supported [`CosmosMsg`]s for [`TxEncoding::Protobuf`] are listed in [`convert_to_proto_any`].
     /// The supported [`CosmosMsg`]s for [`TxEncoding::Proto3Json`] are listed in [`convert_to_proto3json`].
@@ -112,14 +112,13 @@ impl IcaPacketData {
         encoding: &TxEncoding,
         memo: Option<String>,
         ica_address: &str,
-    ) -> StdResult<Self> {
+    ) -> Result<Self, ContractError> {
         match encoding {
             TxEncoding::Protobuf => {
                 let proto_anys = messages.into_iter().try_fold(
                     vec![],
-                    |mut acc, msg| -> StdResult<Vec<cosmos_sdk_proto::Any>> {
-                        let proto_any = convert_to_proto_any(msg, ica_address.to_string())
-                            .map_err(|e| StdError::generic_err(e.to_string()))?;
+                    |mut acc, msg| -> Result<Vec<cosmos_sdk_proto::Any>, ContractError> {
+                        let proto_any = convert_to_proto_any(msg, ica_address.to_string())?;
                         acc.push(proto_any);
                         Ok(acc)
                     },
@@ -130,12 +129,37 @@ impl IcaPacketData {
                 let json_strings = messages
                     .into_iter()
                     .map(|msg| convert_to_proto3json(msg, ica_address.to_string()))
-                    .collect::<Vec<String>>();
+                    .collect::<Result<Vec<String>, ContractError>>()?;
                 Ok(Self::from_json_strings(&json_strings, memo))
             }
         }
     }
 
+    /// Returns the type urls of the messages in the packet, in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the packet's data is neither a [`CosmosTx`] nor a proto3json transaction.
+    pub fn type_urls(&self) -> StdResult<Vec<String>> {
+        /// A proto3json message, of which only the type url is read
+        #[derive(serde::Deserialize)]
+        struct JsonAny {
+            #[serde(rename = "@type")]
+            type_url: String,
+        }
+        /// A proto3json transaction
+        #[derive(serde::Deserialize)]
+        struct JsonTx {
+            messages: Vec<JsonAny>,
+        }
+
+        if let Ok(cosmos_tx) = CosmosTx::decode(self.data.as_slice()) {
+            return Ok(cosmos_tx.messages.into_iter().map(|msg| msg.type_url).collect());
+        }
+        let json_tx: JsonTx = cosmwasm_std::from_json(&self.data)?;
+        Ok(json_tx.messages.into_iter().map(|msg| msg.type_url).collect())
+    }
+
     /// Creates an [`IbcMsg::SendPacket`] message from the [`IcaPacketData`]
     ///
     /// # Errors
@@ -175,4 +199,16 @@ pub mod acknowledgement {
         /// It is a string of the error message (not base64 encoded).
         Error(String),
     }
-}
\ No newline at end of file
+}
+/// contains wasmd's response to an [`IbcMsg::SendPacket`], which cosmos-sdk-proto doesn't ship
+pub mod send_response {
+    /// MsgIbcSendResponse is the data wasmd returns for an [`IbcMsg::SendPacket`](cosmwasm_std::IbcMsg::SendPacket)
+    /// sent as a submessage. It carries the sequence the packet was sent with.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MsgIbcSendResponse {
+        /// The sequence of the sent packet on its channel
+        #[prost(uint64, tag = "1")]
+        pub sequence: u64,
+    }
+}
@@ -156,11 +156,99 @@ pub mod channel {
     }
     
 }
+
+/// Contains the ICS-20 transfer messages.
+/// `IbcMsg::Transfer` can't carry a memo before CosmWasm 2.0, which ibc-hooks needs to call the contract
+/// back with the transfer's acknowledgement, and cosmos-sdk-proto's `MsgTransfer` predates the memo too.
+pub mod transfer {
+    use super::*;
+
+    use cosmos_sdk_proto::{cosmos::base::v1beta1::Coin, ibc::core::client::v1::Height};
+    use cosmwasm_std::{CosmosMsg, Timestamp};
+
+    use super::super::keys;
+
+    /// MsgTransfer defines a msg to transfer fungible tokens (i.e Coins) between ICS20 enabled chains.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MsgTransfer {
+        /// the port on which the packet will be sent
+        #[prost(string, tag = "1")]
+        pub source_port: ::prost::alloc::string::String,
+        /// the channel by which the packet will be sent
+        #[prost(string, tag = "2")]
+        pub source_channel: ::prost::alloc::string::String,
+        /// the tokens to be transferred
+        #[prost(message, optional, tag = "3")]
+        pub token: ::core::option::Option<Coin>,
+        /// the sender address
+        #[prost(string, tag = "4")]
+        pub sender: ::prost::alloc::string::String,
+        /// the recipient address on the destination chain
+        #[prost(string, tag = "5")]
+        pub receiver: ::prost::alloc::string::String,
+        /// Timeout height relative to the current block height.
+        /// The timeout is disabled when set to 0.
+        #[prost(message, optional, tag = "6")]
+        pub timeout_height: ::core::option::Option<Height>,
+        /// Timeout timestamp in absolute nanoseconds since unix epoch.
+        /// The timeout is disabled when set to 0.
+        #[prost(uint64, tag = "7")]
+        pub timeout_timestamp: u64,
+        /// optional memo
+        #[prost(string, tag = "8")]
+        pub memo: ::prost::alloc::string::String,
+    }
+
+    /// MsgTransferResponse defines the Msg/Transfer response type.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MsgTransferResponse {
+        /// sequence number of the transfer packet sent
+        #[prost(uint64, tag = "1")]
+        pub sequence: u64,
+    }
+
+    /// The type url of [`MsgTransfer`].
+    pub const MSG_TRANSFER_TYPE_URL: &str = "/ibc.applications.transfer.v1.MsgTransfer";
+
+    /// Creates a new MsgTransfer of the given coin from the transfer port.
+    pub fn new_transfer_cosmos_msg(
+        sender: impl Into<String>,
+        channel_id: impl Into<String>,
+        token: cosmwasm_std::Coin,
+        receiver: impl Into<String>,
+        timeout: Timestamp,
+        memo: impl Into<String>,
+    ) -> CosmosMsg {
+        let msg_transfer = MsgTransfer {
+            source_port: keys::TRANSFER_PORT_ID.into(),
+            source_channel: channel_id.into(),
+            token: Some(Coin {
+                denom: token.denom,
+                amount: token.amount.to_string(),
+            }),
+            sender: sender.into(),
+            receiver: receiver.into(),
+            timeout_height: None,
+            timeout_timestamp: timeout.nanos(),
+            memo: memo.into(),
+        };
+
+        CosmosMsg::Stargate {
+            type_url: MSG_TRANSFER_TYPE_URL.into(),
+            value: Binary(msg_transfer.encode_to_vec()),
+        }
+    }
+}
+
 /// Contains the stargate query methods.
 pub mod query {
     use super::*;
 
-    use cosmos_sdk_proto::ibc::core::connection::v1::QueryConnectionRequest;
+    use cosmos_sdk_proto::ibc::{
+        applications::transfer::v1::QueryDenomTraceRequest, core::connection::v1::QueryConnectionRequest,
+    };
     use cosmwasm_std::{Empty, QuerierWrapper, QueryRequest};
 
     /// Queries the counterparty connection id using stargate queries.
@@ -180,8 +268,22 @@ pub mod query {
         Ok(response.connection.counterparty.connection_id)
     }
 
+    /// Queries the trace of an ICS-20 voucher denom, e.g. "ibc/27394FB0...", using stargate queries.
+    pub fn denom_trace(querier: &QuerierWrapper, denom: &str) -> Result<response::DenomTrace, ContractError> {
+        let request = QueryDenomTraceRequest {
+            hash: denom.trim_start_matches("ibc/").to_string(),
+        };
+        let query: QueryRequest<Empty> = QueryRequest::Stargate {
+            path: "/ibc.applications.transfer.v1.Query/DenomTrace".into(),
+            data: Binary(request.encode_to_vec()),
+        };
+
+        let response: response::QueryDenomTraceResponse = querier.query(&query)?;
+        Ok(response.denom_trace)
+    }
+
     /// Contains the types used in query responses.
-    mod response {
+    pub mod response {
         /// QueryConnectionResponse is the response type for the Query/Connection RPC
         /// method. Besides the connection end, it includes a proof and the height from
         /// which the proof was retrieved.
@@ -243,5 +345,24 @@ pub mod query {
             /// list of features compatible with the specified identifier
             pub features: Vec<String>,
         }
+
+        /// QueryDenomTraceResponse is the response type for the Query/DenomTrace RPC
+        /// method.
+        #[derive(Clone, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+        pub struct QueryDenomTraceResponse {
+            /// denom_trace returns the requested denomination trace information.
+            pub denom_trace: DenomTrace,
+        }
+
+        /// DenomTrace contains the base denomination for ICS20 fungible tokens and the
+        /// source tracing information path.
+        #[derive(Clone, PartialEq, Debug, serde::Deserialize, serde::Serialize)]
+        pub struct DenomTrace {
+            /// path defines the chain of port/channel identifiers used for tracing the
+            /// source of the fungible token.
+            pub path: String,
+            /// base denomination of the relayed fungible token.
+            pub base_denom: String,
+        }
     }
 }
@@ -4,23 +4,169 @@
 //! along with the msg that we will ask that contract to execute
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Binary;
+use cosmwasm_std::{to_json_binary, Binary, CosmosMsg, StdResult, Storage, SubMsg, WasmMsg};
+
+use super::{keys::CALLBACK_NOTIFICATION_REPLY_ID, msg::OutpostFactoryExecuteMsg, state::CALLBACK};
 
 /// The message to instantiate the ICA controller contract.
 #[cw_serde]
 pub struct Callback {
     /// The contract address that we will call back
     pub contract: String,
-    /// The msg we will make the above contract execute
+    /// The msg we will make the above contract execute once the outpost is instantiated. It is sent as is,
+    /// with the outpost as the sender.
+    /// If not specified, the above contract is the outpost factory and is asked to execute 'MapUserOutpost'.
+    /// The factory is also notified whenever the outpost's channel opens or closes.
     pub msg: Option<Binary>,
 
     /// The owner of the outpost. We need this because the info.sender that instantiates the outpost is the factory address--not the user address
     /// But we want the user to be the owner
     pub outpost_owner: String,
-    
+
+    /// The name the owner gave the outpost. Owners can have several outposts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outpost_name: Option<String>,
+
+    /// The msg we will make the above contract execute once the outpost's channel opens, e.g. to start using
+    /// the ICA straight away. Errors are ignored so they can't fail the handshake.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_channel_open_msg: Option<Binary>,
 }
 
+impl Callback {
+    /// Returns true if the callback contract is the outpost factory, i.e. no custom msg was given.
+    pub fn is_factory(&self) -> bool {
+        self.msg.is_none()
+    }
+
+    /// Builds the message the callback contract executes once the outpost is instantiated.
+    pub fn instantiate_msg(&self) -> StdResult<CosmosMsg> {
+        let msg = match &self.msg {
+            Some(msg) => msg.clone(),
+            None => to_json_binary(&OutpostFactoryExecuteMsg::MapUserOutpost {
+                outpost_owner: self.outpost_owner.clone(),
+                outpost_name: self.outpost_name.clone(),
+            })?,
+        };
+
+        Ok(WasmMsg::Execute {
+            contract_addr: self.contract.clone(),
+            msg,
+            funds: vec![],
+        }
+        .into())
+    }
+
+    /// Builds a message the callback contract executes during the outpost's lifecycle.
+    /// Errors of the called contract are caught in the reply entry point.
+    pub fn lifecycle_msg(&self, msg: Binary) -> SubMsg {
+        SubMsg::reply_on_error(
+            WasmMsg::Execute {
+                contract_addr: self.contract.clone(),
+                msg,
+                funds: vec![],
+            },
+            CALLBACK_NOTIFICATION_REPLY_ID,
+        )
+    }
 
+    /// Builds the message notifying the outpost factory of a change in the outpost's lifecycle.
+    pub fn notification(&self, msg: &OutpostFactoryExecuteMsg) -> StdResult<SubMsg> {
+        Ok(self.lifecycle_msg(to_json_binary(msg)?))
+    }
+}
+
+/// Returns the notification for the outpost factory, if this outpost was instantiated by one.
+pub fn callback_notification(
+    storage: &dyn Storage,
+    msg: &OutpostFactoryExecuteMsg,
+) -> StdResult<Option<SubMsg>> {
+    CALLBACK
+        .may_load(storage)?
+        .filter(Callback::is_factory)
+        .map(|callback| callback.notification(msg))
+        .transpose()
+}
+
+/// Returns the msg to send the callback contract once the channel opens, if one was given at instantiation.
+pub fn channel_open_callback(storage: &dyn Storage) -> StdResult<Option<SubMsg>> {
+    Ok(CALLBACK.may_load(storage)?.and_then(|callback| {
+        callback
+            .on_channel_open_msg
+            .clone()
+            .map(|msg| callback.lifecycle_msg(msg))
+    }))
+}
 
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{from_json, testing::MockStorage};
 
+    use super::*;
 
+    fn callback(msg: Option<Binary>) -> Callback {
+        Callback {
+            contract: "dapp".to_string(),
+            msg,
+            outpost_owner: "alice".to_string(),
+            outpost_name: None,
+            on_channel_open_msg: Some(Binary::from(br#"{"start":{}}"#)),
+        }
+    }
+
+    fn executed_msg(msg: CosmosMsg) -> Binary {
+        let CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) = msg else {
+            panic!("expected a wasm execute msg");
+        };
+        assert_eq!(contract_addr, "dapp");
+        msg
+    }
+
+    #[test]
+    fn factory_callbacks_map_the_outpost() {
+        let msg = executed_msg(callback(None).instantiate_msg().unwrap());
+
+        assert_eq!(
+            from_json::<OutpostFactoryExecuteMsg>(&msg).unwrap(),
+            OutpostFactoryExecuteMsg::MapUserOutpost {
+                outpost_owner: "alice".to_string(),
+                outpost_name: None,
+            }
+        );
+    }
+
+    #[test]
+    fn custom_callbacks_are_sent_as_is() {
+        let custom = Binary::from(br#"{"register_outpost":{"app_id":7}}"#);
+        let msg = executed_msg(callback(Some(custom.clone())).instantiate_msg().unwrap());
+
+        assert_eq!(msg, custom);
+    }
+
+    #[test]
+    fn only_factories_are_notified_of_channel_changes() {
+        let mut storage = MockStorage::new();
+        let closed = OutpostFactoryExecuteMsg::OutpostChannelClosed {};
+        assert!(callback_notification(&storage, &closed).unwrap().is_none());
+
+        CALLBACK.save(&mut storage, &callback(None)).unwrap();
+        assert!(callback_notification(&storage, &closed).unwrap().is_some());
+
+        CALLBACK
+            .save(&mut storage, &callback(Some(Binary::from(br#"{"register":{}}"#))))
+            .unwrap();
+        assert!(callback_notification(&storage, &closed).unwrap().is_none());
+
+        // The channel open msg is sent to factories and other contracts alike
+        let on_channel_open = channel_open_callback(&storage).unwrap().unwrap();
+        assert_eq!(on_channel_open.id, CALLBACK_NOTIFICATION_REPLY_ID);
+        assert_eq!(
+            on_channel_open.msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "dapp".to_string(),
+                msg: Binary::from(br#"{"start":{}}"#),
+                funds: vec![],
+            })
+        );
+    }
+}
@@ -1,19 +1,39 @@
 //! This module contains the helpers to convert [`CosmosMsg`] to [`cosmos_sdk_proto::Any`] or json string.
 
-use cosmos_sdk_proto::{prost::EncodeError, Any};
-use cosmwasm_std::{BankMsg, Coin, CosmosMsg, IbcMsg};
+use cosmos_sdk_proto::Any;
+use cosmwasm_std::{BankMsg, Coin, CosmosMsg, Empty, IbcMsg};
+
+use super::ContractError;
+
+/// A [`CosmosMsg::Custom`] message that [`convert_to_proto_any`] can convert,
+/// e.g. a [`JackalMsg`](crate::types::jackal_msg::JackalMsg).
+pub trait ToProtoAny {
+    /// Converts the message, sent by `from_address`, to a [`cosmos_sdk_proto::Any`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on serialization failure, or if the message can't be sent to the host.
+    fn to_proto_any(self, from_address: String) -> Result<Any, ContractError>;
+}
+
+impl ToProtoAny for Empty {
+    fn to_proto_any(self, _from_address: String) -> Result<Any, ContractError> {
+        Err(ContractError::UnsupportedCosmosMsg("custom".to_string()))
+    }
+}
 
 /// `convert_to_proto_any` converts a [`CosmosMsg`] to a [`cosmos_sdk_proto::Any`].
 ///
-/// `from_address` is not used in [`CosmosMsg::Stargate`]
+/// `from_address` is not used in [`CosmosMsg::Stargate`], see [`crate::types::signer`] to fill in the
+/// signer of Jackal messages.
 ///
 /// # Errors
 ///
-/// Returns an error on serialization failure.
+/// Returns an error on serialization failure, or if the [`CosmosMsg`] is not supported.
 ///
 /// # Panics
 ///
-/// Panics if the [`CosmosMsg`] is not supported.
+/// Panics if a variant of a supported [`CosmosMsg`] is not, e.g. [`BankMsg::Burn`].
 ///
 /// ## List of supported [`CosmosMsg`]
 ///
@@ -28,13 +48,14 @@ use cosmwasm_std::{BankMsg, Coin, CosmosMsg, IbcMsg};
 /// - [`CosmosMsg::Wasm`] with [`cosmwasm_std::WasmMsg::ClearAdmin`]
 /// - [`CosmosMsg::Gov`] with [`cosmwasm_std::GovMsg::Vote`]
 /// - [`CosmosMsg::Gov`] with [`cosmwasm_std::GovMsg::VoteWeighted`]
-/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Delegate`]
-/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Undelegate`]
-/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Redelegate`]
-/// - [`CosmosMsg::Distribution`] with [`cosmwasm_std::DistributionMsg::WithdrawDelegatorReward`]
-/// - [`CosmosMsg::Distribution`] with [`cosmwasm_std::DistributionMsg::SetWithdrawAddress`]
-pub fn convert_to_proto_any(msg: CosmosMsg, from_address: String) -> Result<Any, EncodeError> {
-    match msg {
+/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Delegate`] (`staking` feature)
+/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Undelegate`] (`staking` feature)
+/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Redelegate`] (`staking` feature)
+/// - [`CosmosMsg::Distribution`] with [`cosmwasm_std::DistributionMsg::WithdrawDelegatorReward`] (`staking` feature)
+/// - [`CosmosMsg::Distribution`] with [`cosmwasm_std::DistributionMsg::SetWithdrawAddress`] (`staking` feature)
+/// - [`CosmosMsg::Custom`] with a [`ToProtoAny`] message
+pub fn convert_to_proto_any<T: ToProtoAny + std::fmt::Debug>(msg: CosmosMsg<T>, from_address: String) -> Result<Any, ContractError> {
+    let any = match msg {
         CosmosMsg::Stargate { type_url, value } => Ok(Any {
             type_url,
             value: value.to_vec(),
@@ -49,8 +70,10 @@ pub fn convert_to_proto_any(msg: CosmosMsg, from_address: String) -> Result<Any,
         CosmosMsg::Distribution(distribution_msg) => {
             convert_to_any::distribution(distribution_msg, from_address)
         }
-        _ => panic!("Unsupported CosmosMsg"),
-    }
+        CosmosMsg::Custom(custom_msg) => return custom_msg.to_proto_any(from_address),
+        _ => return Err(ContractError::UnsupportedCosmosMsg(format!("{msg:?}"))),
+    };
+    Ok(any?)
 }
 
 mod convert_to_any {
@@ -336,9 +359,12 @@ mod convert_to_any {
 /// `convert_to_proto3json` converts a [`CosmosMsg`] to a json string formatted with
 /// [`proto3json`](crate::ibc::types::metadata::TxEncoding::Proto3Json) encoding format.
 ///
+/// # Errors
+/// Returns an error if the [`CosmosMsg`] is not supported.
+/// Notably, [`CosmosMsg::Stargate`], [`CosmosMsg::Wasm`] and [`CosmosMsg::Custom`] are not supported.
+///
 /// # Panics
-/// Panics if the [`CosmosMsg`] is not supported.
-/// Notably, [`CosmosMsg::Stargate`] and [`CosmosMsg::Wasm`] are not supported.
+/// Panics if a variant of a supported [`CosmosMsg`] is not, e.g. [`BankMsg::Burn`].
 ///
 /// ## List of supported [`CosmosMsg`]
 ///
@@ -346,14 +372,13 @@ mod convert_to_any {
 /// - [`CosmosMsg::Ibc`] with [`IbcMsg::Transfer`]
 /// - [`CosmosMsg::Gov`] with [`cosmwasm_std::GovMsg::Vote`]
 /// - [`CosmosMsg::Gov`] with [`cosmwasm_std::GovMsg::VoteWeighted`]
-/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Delegate`]
-/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Undelegate`]
-/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Redelegate`]
-/// - [`CosmosMsg::Distribution`] with [`cosmwasm_std::DistributionMsg::WithdrawDelegatorReward`]
-/// - [`CosmosMsg::Distribution`] with [`cosmwasm_std::DistributionMsg::SetWithdrawAddress`]
-#[must_use]
-pub fn convert_to_proto3json(msg: CosmosMsg, from_address: String) -> String {
-    match msg {
+/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Delegate`] (`staking` feature)
+/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Undelegate`] (`staking` feature)
+/// - [`CosmosMsg::Staking`] with [`cosmwasm_std::StakingMsg::Redelegate`] (`staking` feature)
+/// - [`CosmosMsg::Distribution`] with [`cosmwasm_std::DistributionMsg::WithdrawDelegatorReward`] (`staking` feature)
+/// - [`CosmosMsg::Distribution`] with [`cosmwasm_std::DistributionMsg::SetWithdrawAddress`] (`staking` feature)
+pub fn convert_to_proto3json(msg: CosmosMsg, from_address: String) -> Result<String, ContractError> {
+    Ok(match msg {
         CosmosMsg::Bank(msg) => convert_to_json::bank(msg, from_address),
         CosmosMsg::Ibc(msg) => convert_to_json::ibc(msg, from_address),
         CosmosMsg::Gov(msg) => convert_to_json::gov(msg, from_address),
@@ -361,8 +386,8 @@ pub fn convert_to_proto3json(msg: CosmosMsg, from_address: String) -> String {
         CosmosMsg::Staking(msg) => convert_to_json::staking(msg, from_address),
         #[cfg(feature = "staking")]
         CosmosMsg::Distribution(msg) => convert_to_json::distribution(msg, from_address),
-        _ => panic!("Unsupported CosmosMsg"),
-    }
+        _ => return Err(ContractError::UnsupportedCosmosMsg(format!("{msg:?}"))),
+    })
 }
 
 mod convert_to_json {
@@ -654,6 +679,42 @@ mod tests {
         assert_eq!(expected, cosmos_tx.messages[0]);
     }
 
+    #[test]
+    fn test_custom_jackal_msg() {
+        use crate::types::jackal_msg::{JackalMsg, StorageMsg};
+        use crate::types::storage::MsgBuyStorage;
+        use cosmos_sdk_proto::traits::Message;
+
+        let msg = cosmwasm_std::CosmosMsg::Custom(JackalMsg::Storage(StorageMsg::BuyStorage {
+            for_address: None,
+            duration_days: 30,
+            bytes: 1_000_000_000_000,
+            payment_denom: "ujkl".to_string(),
+            referral: None,
+        }));
+        let any = super::convert_to_proto_any(msg, "jkl1ica".to_string()).unwrap();
+
+        assert_eq!(any.type_url, "/canine_chain.storage.MsgBuyStorage");
+        let buy_storage = MsgBuyStorage::decode(any.value.as_slice()).unwrap();
+        assert_eq!((buy_storage.creator.as_str(), buy_storage.for_address.as_str()), ("jkl1ica", "jkl1ica"));
+    }
+
+    #[test]
+    fn test_json_type_urls() {
+        let packet = IcaPacketData::from_json_strings(
+            &[
+                r#"{"@type": "/cosmos.bank.v1beta1.MsgSend", "amount": [{"denom": "stake", "amount": "5000"}]}"#.to_string(),
+                r#"{"@type": "/cosmos.gov.v1beta1.MsgVote", "proposal_id": "1", "option": 1}"#.to_string(),
+            ],
+            None,
+        );
+
+        assert_eq!(
+            packet.type_urls().unwrap(),
+            vec!["/cosmos.bank.v1beta1.MsgSend", "/cosmos.gov.v1beta1.MsgVote"]
+        );
+    }
+
     #[test]
     fn test_weighted_vote_option() {
         let test_msg = r#"{"option":"yes","weight":"0.5"}"#;
@@ -672,4 +733,121 @@ mod tests {
         );
         assert_eq!("0.5".to_string(), vote_option.weight.to_string());
     }
+
+    #[cfg(feature = "staking")]
+    #[test]
+    fn test_staking_proto_any() {
+        use cosmos_sdk_proto::cosmos::{
+            base::v1beta1::Coin as ProtoCoin,
+            staking::v1beta1::{MsgBeginRedelegate, MsgDelegate, MsgUndelegate},
+        };
+        use cosmos_sdk_proto::traits::Message;
+        use cosmwasm_std::{coin, CosmosMsg, StakingMsg};
+
+        let proto_coin = Some(ProtoCoin { denom: "ujkl".to_string(), amount: "100".to_string() });
+        let convert = |msg: StakingMsg| super::convert_to_proto_any(CosmosMsg::<cosmwasm_std::Empty>::Staking(msg), "jkl1ica".to_string()).unwrap();
+
+        let any = convert(StakingMsg::Delegate { validator: "jklvaloper1a".to_string(), amount: coin(100, "ujkl") });
+        assert_eq!(any.type_url, "/cosmos.staking.v1beta1.MsgDelegate");
+        assert_eq!(
+            MsgDelegate::decode(any.value.as_slice()).unwrap(),
+            MsgDelegate {
+                delegator_address: "jkl1ica".to_string(),
+                validator_address: "jklvaloper1a".to_string(),
+                amount: proto_coin.clone(),
+            }
+        );
+
+        let any = convert(StakingMsg::Undelegate { validator: "jklvaloper1a".to_string(), amount: coin(100, "ujkl") });
+        assert_eq!(any.type_url, "/cosmos.staking.v1beta1.MsgUndelegate");
+        assert_eq!(
+            MsgUndelegate::decode(any.value.as_slice()).unwrap(),
+            MsgUndelegate {
+                delegator_address: "jkl1ica".to_string(),
+                validator_address: "jklvaloper1a".to_string(),
+                amount: proto_coin.clone(),
+            }
+        );
+
+        let any = convert(StakingMsg::Redelegate {
+            src_validator: "jklvaloper1a".to_string(),
+            dst_validator: "jklvaloper1b".to_string(),
+            amount: coin(100, "ujkl"),
+        });
+        assert_eq!(any.type_url, "/cosmos.staking.v1beta1.MsgBeginRedelegate");
+        assert_eq!(
+            MsgBeginRedelegate::decode(any.value.as_slice()).unwrap(),
+            MsgBeginRedelegate {
+                delegator_address: "jkl1ica".to_string(),
+                validator_src_address: "jklvaloper1a".to_string(),
+                validator_dst_address: "jklvaloper1b".to_string(),
+                amount: proto_coin,
+            }
+        );
+    }
+
+    #[cfg(feature = "staking")]
+    #[test]
+    fn test_distribution_proto_any() {
+        use cosmos_sdk_proto::cosmos::distribution::v1beta1::{MsgSetWithdrawAddress, MsgWithdrawDelegatorReward};
+        use cosmos_sdk_proto::traits::Message;
+        use cosmwasm_std::{CosmosMsg, DistributionMsg};
+
+        let convert = |msg: DistributionMsg| {
+            super::convert_to_proto_any(CosmosMsg::<cosmwasm_std::Empty>::Distribution(msg), "jkl1ica".to_string()).unwrap()
+        };
+
+        let any = convert(DistributionMsg::WithdrawDelegatorReward { validator: "jklvaloper1a".to_string() });
+        assert_eq!(any.type_url, "/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward");
+        assert_eq!(
+            MsgWithdrawDelegatorReward::decode(any.value.as_slice()).unwrap(),
+            MsgWithdrawDelegatorReward {
+                delegator_address: "jkl1ica".to_string(),
+                validator_address: "jklvaloper1a".to_string(),
+            }
+        );
+
+        let any = convert(DistributionMsg::SetWithdrawAddress { address: "jkl1owner".to_string() });
+        assert_eq!(any.type_url, "/cosmos.distribution.v1beta1.MsgSetWithdrawAddress");
+        assert_eq!(
+            MsgSetWithdrawAddress::decode(any.value.as_slice()).unwrap(),
+            MsgSetWithdrawAddress {
+                delegator_address: "jkl1ica".to_string(),
+                withdraw_address: "jkl1owner".to_string(),
+            }
+        );
+    }
+
+    #[cfg(feature = "staking")]
+    #[test]
+    fn test_staking_proto3json() {
+        use cosmwasm_std::{coin, CosmosMsg, DistributionMsg, StakingMsg};
+
+        let convert = |msg: CosmosMsg| super::convert_to_proto3json(msg, "jkl1ica".to_string()).unwrap();
+
+        assert_eq!(
+            convert(CosmosMsg::Staking(StakingMsg::Delegate { validator: "jklvaloper1a".to_string(), amount: coin(100, "ujkl") })),
+            r#"{"@type":"/cosmos.staking.v1beta1.MsgDelegate","delegator_address":"jkl1ica","validator_address":"jklvaloper1a","amount":{"denom":"ujkl","amount":"100"}}"#
+        );
+        assert_eq!(
+            convert(CosmosMsg::Staking(StakingMsg::Undelegate { validator: "jklvaloper1a".to_string(), amount: coin(100, "ujkl") })),
+            r#"{"@type":"/cosmos.staking.v1beta1.MsgUndelegate","delegator_address":"jkl1ica","validator_address":"jklvaloper1a","amount":{"denom":"ujkl","amount":"100"}}"#
+        );
+        assert_eq!(
+            convert(CosmosMsg::Staking(StakingMsg::Redelegate {
+                src_validator: "jklvaloper1a".to_string(),
+                dst_validator: "jklvaloper1b".to_string(),
+                amount: coin(100, "ujkl"),
+            })),
+            r#"{"@type":"/cosmos.staking.v1beta1.MsgBeginRedelegate","delegator_address":"jkl1ica","validator_src_address":"jklvaloper1a","validator_dst_address":"jklvaloper1b","amount":{"denom":"ujkl","amount":"100"}}"#
+        );
+        assert_eq!(
+            convert(CosmosMsg::Distribution(DistributionMsg::WithdrawDelegatorReward { validator: "jklvaloper1a".to_string() })),
+            r#"{"@type":"/cosmos.distribution.v1beta1.MsgWithdrawDelegatorReward","delegator_address":"jkl1ica","validator_address":"jklvaloper1a"}"#
+        );
+        assert_eq!(
+            convert(CosmosMsg::Distribution(DistributionMsg::SetWithdrawAddress { address: "jkl1owner".to_string() })),
+            r#"{"@type":"/cosmos.distribution.v1beta1.MsgSetWithdrawAddress","delegator_address":"jkl1ica","withdraw_address":"jkl1owner"}"#
+        );
+    }
 }
@@ -0,0 +1,96 @@
+//! # Decommission
+//!
+//! Retires an outpost at its owner's request. The owner's cleanup messages (e.g. filetree messages deleting
+//! or handing over files) and the transfers returning the ICA's funds are sent in a single ICA packet.
+//! Once that packet succeeds the channel is closed, the outpost is unregistered from its factory and it
+//! stops sending packets or opening channels for good.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{IbcMsg, StdResult, Storage, SubMsg, Timestamp};
+
+use super::{
+    callback::callback_notification,
+    msg::OutpostFactoryExecuteMsg,
+    state::{ALLOW_CHANNEL_CLOSE_INIT, ALLOW_CHANNEL_OPEN_INIT, DECOMMISSION},
+    ContractError,
+};
+
+/// How far along the decommissioning is.
+#[cw_serde]
+pub enum DecommissionStage {
+    /// The cleanup packet was sent, waiting for its acknowledgement.
+    CleaningUp,
+    /// The channel close was requested.
+    ClosingChannel,
+    /// The outpost is retired and rejects any further packet or channel.
+    Decommissioned,
+    /// The cleanup packet failed or timed out, nothing was changed on the host. The owner may start over.
+    Failed,
+}
+
+/// The owner's request to retire the outpost.
+#[cw_serde]
+pub struct Decommission {
+    /// How far along the decommissioning is.
+    pub stage: DecommissionStage,
+    /// When the owner requested the decommissioning.
+    pub started_at: Timestamp,
+    /// Why the cleanup packet failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Decommission {
+    /// Creates a new Decommission at the given stage
+    pub fn new(stage: DecommissionStage, started_at: Timestamp) -> Self {
+        Self {
+            stage,
+            started_at,
+            error: None,
+        }
+    }
+}
+
+/// Errors if the outpost was decommissioned, or is being decommissioned.
+pub fn ensure_active(storage: &dyn Storage) -> Result<(), ContractError> {
+    match DECOMMISSION.may_load(storage)?.map(|decommission| decommission.stage) {
+        None | Some(DecommissionStage::Failed) => Ok(()),
+        Some(DecommissionStage::Decommissioned) => Err(ContractError::Decommissioned {}),
+        Some(_) => Err(ContractError::DecommissionInProgress {}),
+    }
+}
+
+/// Allows the contract to close its own channel and returns the message doing so.
+pub fn close_channel(storage: &mut dyn Storage, channel_id: String) -> StdResult<IbcMsg> {
+    ALLOW_CHANNEL_CLOSE_INIT.save(storage, &true)?;
+    set_stage(storage, DecommissionStage::ClosingChannel, None)?;
+    Ok(IbcMsg::CloseChannel { channel_id })
+}
+
+/// Marks the decommissioning as failed, so the owner can try again.
+pub fn fail(storage: &mut dyn Storage, error: String) -> StdResult<()> {
+    set_stage(storage, DecommissionStage::Failed, Some(error))
+}
+
+/// Retires the outpost for good and returns the notification unregistering it from the factory.
+pub fn finish(storage: &mut dyn Storage) -> StdResult<Option<SubMsg>> {
+    ALLOW_CHANNEL_OPEN_INIT.save(storage, &false)?;
+    set_stage(storage, DecommissionStage::Decommissioned, None)?;
+    callback_notification(storage, &OutpostFactoryExecuteMsg::OutpostDecommissioned {})
+}
+
+/// Returns true if the contract is waiting for its channel to close to finish decommissioning.
+pub fn is_closing_channel(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(DECOMMISSION
+        .may_load(storage)?
+        .is_some_and(|decommission| decommission.stage == DecommissionStage::ClosingChannel))
+}
+
+fn set_stage(storage: &mut dyn Storage, stage: DecommissionStage, error: Option<String>) -> StdResult<()> {
+    DECOMMISSION.update(storage, |mut decommission| -> StdResult<_> {
+        decommission.stage = stage;
+        decommission.error = error;
+        Ok(decommission)
+    })?;
+    Ok(())
+}
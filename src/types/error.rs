@@ -24,6 +24,9 @@ pub enum ContractError {
     #[error("prost encoding error: {0}")]
     ProstEncodeError(#[from] cosmos_sdk_proto::prost::EncodeError),
 
+    #[error("prost decoding error: {0}")]
+    ProstDecodeError(#[from] cosmos_sdk_proto::prost::DecodeError),
+
     #[error("semver parse error: {0}")]
     SemverError(#[from] semver::Error),
 
@@ -84,6 +87,12 @@ pub enum ContractError {
     #[error("unsupported packet encoding: {0}")]
     UnsupportedPacketEncoding(String),
 
+    #[error("invalid notification, it must be a JSON object: {0}")]
+    InvalidNotification(String),
+
+    #[error("unsupported cosmos message: {0}")]
+    UnsupportedCosmosMsg(String),
+
     #[error("No owner found")]
     NoOwner {},
 
@@ -92,4 +101,84 @@ pub enum ContractError {
 
     #[error("invalid interchain account address")]
     InvalidIcaAddress,
+
+    #[error("unknown reply id: {0}")]
+    UnknownReplyId(u64),
+
+    #[error("MsgChannelCloseInit is not allowed")]
+    ChannelCloseInitNotAllowed {},
+
+    #[error("the channel is not open")]
+    ChannelNotOpen {},
+
+    #[error("the outpost is decommissioned")]
+    Decommissioned {},
+
+    #[error("the outpost is being decommissioned")]
+    DecommissionInProgress {},
+
+    #[error("the ibc send packet response is missing from the reply")]
+    MissingPacketSequence {},
+
+    #[error("no files to transfer")]
+    NoFilesToTransfer {},
+
+    #[error("batch size must be between 1 and {max}")]
+    InvalidBatchSize { max: u32 },
+
+    #[error("file {0} was already transferred or is being transferred")]
+    FileAlreadyTransferred(String),
+
+    #[error("no transfer found for file {0}")]
+    UnknownFileTransfer(String),
+
+    #[error("the outpost is not registered with a factory")]
+    NoFactory {},
+
+    #[error("no transfer channel is set for this contract")]
+    NoTransferChannel {},
+
+    #[error("no funds were sent")]
+    NoFunds {},
+
+    #[error("exactly one coin must be sent")]
+    InvalidFunds {},
+
+    #[error("storage plans must have a positive size and duration")]
+    InvalidStoragePlan {},
+
+    #[error("invalid packet memo: {0}")]
+    InvalidMemo(String),
+
+    #[error("between 1 and {max} host queries must be sent")]
+    InvalidHostQueryCount { max: usize },
+
+    #[error("invalid host query response: {0}")]
+    InvalidHostQueryResponse(String),
+
+    #[error("the host does not accept interchain account packets")]
+    HostDisabled {},
+
+    #[error("the host does not allow these messages: {}", .0.join(", "))]
+    MessagesNotAllowed(Vec<String>),
+
+    #[error("the attached coin arrives on Jackal as {expected}, not {found}")]
+    InvalidPaymentDenom { expected: String, found: String },
+
+    #[error("the expiration must be in the future")]
+    InvalidExpiration {},
+
+    #[error("the weights of a weighted vote must be positive and add up to 1")]
+    InvalidVoteWeights {},
+
+    #[error("invalid rns name {name}: {reason}")]
+    InvalidRnsName { name: String, reason: String },
+
+    #[error("{type_url} must have {field} {expected}, got {found}")]
+    SignerMismatch {
+        type_url: String,
+        field: String,
+        expected: String,
+        found: String,
+    },
 }
@@ -0,0 +1,96 @@
+//! # File transfer
+//!
+//! Hands the owner's filetree files over to the ICA of another outpost, e.g. when moving to an outpost
+//! on new code or a new connection, so the files don't have to be uploaded again.
+//! Files are sent in batches of `MsgChangeOwner`, one ICA packet per batch, and tracked per path.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Storage;
+use prost::Message;
+
+use super::{
+    filetree::MsgChangeOwner,
+    state::FILE_TRANSFERS,
+    ContractError,
+};
+use crate::helpers::filetree_helpers::{merkle_path, owner_address};
+
+/// The type url of [`MsgChangeOwner`] on canine-chain.
+pub const MSG_CHANGE_OWNER_TYPE_URL: &str = "/canine_chain.filetree.MsgChangeOwner";
+
+/// How many files are transferred per packet unless the owner says otherwise.
+pub const DEFAULT_BATCH_SIZE: u32 = 20;
+
+/// The most files transferred per packet, which keeps the host's gas use per tx bounded.
+pub const MAX_BATCH_SIZE: u32 = 50;
+
+/// Where a file stands in its transfer.
+#[cw_serde]
+pub enum FileTransferStatus {
+    /// The packet with the file's `MsgChangeOwner` was sent, waiting for its acknowledgement.
+    Pending,
+    /// The destination ICA owns the file.
+    Transferred,
+    /// The packet failed or timed out, the file still belongs to this outpost's ICA.
+    Failed {
+        /// Why the packet failed
+        error: String,
+    },
+}
+
+/// The transfer of one file to another outpost.
+#[cw_serde]
+pub struct FileTransfer {
+    /// The file's path, e.g. "s/home/photo.jpg".
+    pub path: String,
+    /// The ICA address receiving the file.
+    pub new_owner: String,
+    /// Where the transfer stands.
+    pub status: FileTransferStatus,
+}
+
+/// Builds the `MsgChangeOwner` handing the file at `path` from `ica_address` over to `new_owner`.
+pub fn change_owner_msg(path: &str, ica_address: &str, new_owner: &str) -> cosmos_sdk_proto::Any {
+    let merkle_path = merkle_path(path);
+    let msg = MsgChangeOwner {
+        creator: ica_address.to_string(),
+        file_owner: owner_address(&merkle_path, ica_address),
+        new_owner: owner_address(&merkle_path, new_owner),
+        address: merkle_path,
+    };
+
+    cosmos_sdk_proto::Any {
+        type_url: MSG_CHANGE_OWNER_TYPE_URL.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+/// Errors if the file was already transferred, or its transfer is still in flight.
+pub fn ensure_transferable(storage: &dyn Storage, path: &str) -> Result<(), ContractError> {
+    match FILE_TRANSFERS.may_load(storage, path)?.map(|transfer| transfer.status) {
+        None | Some(FileTransferStatus::Failed { .. }) => Ok(()),
+        Some(_) => Err(ContractError::FileAlreadyTransferred(path.to_string())),
+    }
+}
+
+/// Records the outcome of a batch's packet for each of its files.
+pub fn on_packet_result(
+    storage: &mut dyn Storage,
+    paths: &[String],
+    result: Result<(), String>,
+) -> Result<(), ContractError> {
+    let status = match result {
+        Ok(()) => FileTransferStatus::Transferred,
+        Err(error) => FileTransferStatus::Failed { error },
+    };
+
+    for path in paths {
+        FILE_TRANSFERS.update(storage, path, |transfer| -> Result<_, ContractError> {
+            let mut transfer = transfer.ok_or_else(|| ContractError::UnknownFileTransfer(path.clone()))?;
+            transfer.status = status.clone();
+            Ok(transfer)
+        })?;
+    }
+    Ok(())
+}
+
@@ -3,6 +3,18 @@
 //! Contains all the transaction msgs needed to interact with canine-chain's filetree module.
 //! TODO: add remaining msgs and storage module's transaction msgs
 
+/// The type url of [`MsgPostKey`].
+pub const MSG_POST_KEY_TYPE_URL: &str = "/canine_chain.filetree.MsgPostKey";
+
+/// The type url of [`MsgPostFile`].
+pub const MSG_POST_FILE_TYPE_URL: &str = "/canine_chain.filetree.MsgPostFile";
+
+/// The type url of [`MsgAddViewers`].
+pub const MSG_ADD_VIEWERS_TYPE_URL: &str = "/canine_chain.filetree.MsgAddViewers";
+
+/// The type url of [`MsgDeleteFile`].
+pub const MSG_DELETE_FILE_TYPE_URL: &str = "/canine_chain.filetree.MsgDeleteFile";
+
 /// Post your public key to canine-chain filetree 
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -82,4 +94,82 @@ pub struct MsgPostFileResponse {
     /// fullMerklePath
     #[prost(string, tag = "1")]
     pub path: ::prost::alloc::string::String,
-}
\ No newline at end of file
+}
+
+/// Hand a file over to another account. The file's viewers and editors are kept
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgChangeOwner {
+    /// The current owner's Bech32 address, which broadcasts this message
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// MerklePath( file path )
+    #[prost(string, tag = "2")]
+    pub address: ::prost::alloc::string::String,
+
+    /// owner_address( MerklePath(file path), Hex[ hash( current owner's Bech32 address ) ] )
+    #[prost(string, tag = "3")]
+    pub file_owner: ::prost::alloc::string::String,
+
+    /// owner_address( MerklePath(file path), Hex[ hash( new owner's Bech32 address ) ] )
+    #[prost(string, tag = "4")]
+    pub new_owner: ::prost::alloc::string::String,
+}
+
+/// A successful broadcast guarantees that the file changed owner, so the response is empty
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgChangeOwnerResponse {}
+
+/// Let more accounts decrypt a file
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgAddViewers {
+    /// The file owner's Bech32 address, which broadcasts this message
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// Comma separated Hex[ hash( concatenate( "v", trackingNumber, viewer's Bech32 address ) ) ]
+    #[prost(string, tag = "2")]
+    pub viewer_ids: ::prost::alloc::string::String,
+
+    /// Comma separated ECIES.encrypt( aesIV + aesKey ), one per viewer
+    #[prost(string, tag = "3")]
+    pub viewer_keys: ::prost::alloc::string::String,
+
+    /// MerklePath( file path )
+    #[prost(string, tag = "4")]
+    pub address: ::prost::alloc::string::String,
+
+    /// owner_address( MerklePath(file path), Hex[ hash( owner's Bech32 address ) ] )
+    #[prost(string, tag = "5")]
+    pub file_owner: ::prost::alloc::string::String,
+}
+
+/// A successful broadcast guarantees that the viewers were added, so the response is empty
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgAddViewersResponse {}
+
+/// Delete a file
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgDeleteFile {
+    /// The file owner's Bech32 address, which broadcasts this message
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// MerklePath( file path )
+    #[prost(string, tag = "2")]
+    pub hash_path: ::prost::alloc::string::String,
+
+    /// Hex[ hash( owner's Bech32 address ) ]
+    #[prost(string, tag = "3")]
+    pub account: ::prost::alloc::string::String,
+}
+
+/// A successful broadcast guarantees that the file was deleted, so the response is empty
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgDeleteFileResponse {}
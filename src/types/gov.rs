@@ -0,0 +1,126 @@
+//! # gov
+//!
+//! Contains the gov v1 msgs the ICA uses to take part in canine-chain governance. Votes are sent as
+//! [`GovMsg`](cosmwasm_std::GovMsg)s, see [`convert_to_proto_any`](super::cosmos_msg::convert_to_proto_any).
+//! cosmos-sdk-proto only ships the v0.46 `MsgSubmitProposal`, which lacks the `title` and `summary` of v0.47.
+
+use cosmos_sdk_proto::{
+    cosmos::{base::v1beta1::Coin as ProtoCoin, gov::v1::MsgDeposit},
+    Any,
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Binary, Coin, Decimal, WeightedVoteOption};
+use prost::Message;
+
+use super::ContractError;
+
+/// The type url of [`MsgSubmitProposal`].
+pub const MSG_SUBMIT_PROPOSAL_TYPE_URL: &str = "/cosmos.gov.v1.MsgSubmitProposal";
+
+/// The type url of [`MsgDeposit`].
+pub const MSG_DEPOSIT_TYPE_URL: &str = "/cosmos.gov.v1.MsgDeposit";
+
+/// A message executed by the gov module if a proposal passes, already encoded for the host.
+#[cw_serde]
+pub struct ProposalMsg {
+    /// The type url of the message.
+    pub type_url: String,
+    /// The protobuf encoded message. Its signer is the gov module account.
+    pub value: Binary,
+}
+
+/// MsgSubmitProposal defines an sdk.Msg type that supports submitting arbitrary
+/// proposal Content.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgSubmitProposal {
+    /// messages are the arbitrary messages to be executed if proposal passes.
+    #[prost(message, repeated, tag = "1")]
+    pub messages: ::prost::alloc::vec::Vec<Any>,
+    /// initial_deposit is the deposit value that must be paid at proposal submission.
+    #[prost(message, repeated, tag = "2")]
+    pub initial_deposit: ::prost::alloc::vec::Vec<ProtoCoin>,
+    /// proposer is the account address of the proposer.
+    #[prost(string, tag = "3")]
+    pub proposer: ::prost::alloc::string::String,
+    /// metadata is any arbitrary metadata attached to the proposal.
+    #[prost(string, tag = "4")]
+    pub metadata: ::prost::alloc::string::String,
+    /// title is the title of the proposal.
+    #[prost(string, tag = "5")]
+    pub title: ::prost::alloc::string::String,
+    /// summary is the summary of the proposal
+    #[prost(string, tag = "6")]
+    pub summary: ::prost::alloc::string::String,
+}
+
+/// Returns the `MsgSubmitProposal` the ICA sends to submit a proposal.
+pub fn submit_proposal_msg(
+    proposer: &str,
+    title: String,
+    summary: String,
+    messages: Vec<ProposalMsg>,
+    initial_deposit: Vec<Coin>,
+    metadata: Option<String>,
+) -> Any {
+    let msg = MsgSubmitProposal {
+        messages: messages
+            .into_iter()
+            .map(|msg| Any {
+                type_url: msg.type_url,
+                value: msg.value.to_vec(),
+            })
+            .collect(),
+        initial_deposit: proto_coins(initial_deposit),
+        proposer: proposer.to_string(),
+        metadata: metadata.unwrap_or_default(),
+        title,
+        summary,
+    };
+
+    Any {
+        type_url: MSG_SUBMIT_PROPOSAL_TYPE_URL.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+/// Returns the `MsgDeposit` the ICA sends to add to a proposal's deposit.
+pub fn deposit_msg(depositor: &str, proposal_id: u64, amount: Vec<Coin>) -> Any {
+    let msg = MsgDeposit {
+        proposal_id,
+        depositor: depositor.to_string(),
+        amount: proto_coins(amount),
+    };
+
+    Any {
+        type_url: MSG_DEPOSIT_TYPE_URL.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+/// Checks that the weights of a weighted vote are positive and add up to 1, as the host requires.
+pub fn validate_weighted_vote(options: &[WeightedVoteOption]) -> Result<(), ContractError> {
+    let mut total = Decimal::zero();
+    for option in options {
+        if option.weight.is_zero() {
+            return Err(ContractError::InvalidVoteWeights {});
+        }
+        total = total
+            .checked_add(option.weight)
+            .map_err(|_| ContractError::InvalidVoteWeights {})?;
+    }
+    if total != Decimal::one() {
+        return Err(ContractError::InvalidVoteWeights {});
+    }
+    Ok(())
+}
+
+fn proto_coins(coins: Vec<Coin>) -> Vec<ProtoCoin> {
+    coins
+        .into_iter()
+        .map(|coin| ProtoCoin {
+            denom: coin.denom,
+            amount: coin.amount.to_string(),
+        })
+        .collect()
+}
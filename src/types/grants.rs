@@ -0,0 +1,454 @@
+//! # Grants
+//!
+//! The ICA can let other Jackal accounts act for it, so its files can also be managed from a native
+//! Jackal wallet or a backend key. Authz grants let the grantee execute messages as the ICA, and feegrant
+//! allowances let it pay its fees from the ICA's balance.
+//!
+//! Grants are recorded once the host acknowledges the packet issuing them, and removed once it acknowledges
+//! their revocation. Grants changed on Jackal without the outpost, e.g. by the grantee itself, aren't seen.
+
+use cosmos_sdk_proto::{
+    cosmos::{
+        authz::v1beta1::{GenericAuthorization, MsgRevoke},
+        base::v1beta1::Coin as ProtoCoin,
+        feegrant::v1beta1::{AllowedMsgAllowance, MsgGrantAllowance, MsgRevokeAllowance},
+    },
+    tendermint::google::protobuf::Timestamp as ProtoTimestamp,
+    Any,
+};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Coin, Order, StdResult, Storage, Timestamp};
+use cw_storage_plus::Bound;
+use prost::Message;
+
+use super::{
+    file_transfer::MSG_CHANGE_OWNER_TYPE_URL,
+    filetree::{MSG_ADD_VIEWERS_TYPE_URL, MSG_DELETE_FILE_TYPE_URL, MSG_POST_FILE_TYPE_URL, MSG_POST_KEY_TYPE_URL},
+    state::{AUTHZ_GRANTS, FEE_ALLOWANCES},
+    ContractError,
+};
+
+/// The type url of [`MsgGrant`].
+pub const MSG_GRANT_TYPE_URL: &str = "/cosmos.authz.v1beta1.MsgGrant";
+
+/// The type url of [`MsgRevoke`].
+pub const MSG_REVOKE_TYPE_URL: &str = "/cosmos.authz.v1beta1.MsgRevoke";
+
+/// The type url of [`GenericAuthorization`].
+pub const GENERIC_AUTHORIZATION_TYPE_URL: &str = "/cosmos.authz.v1beta1.GenericAuthorization";
+
+/// The type url of [`MsgGrantAllowance`].
+pub const MSG_GRANT_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.MsgGrantAllowance";
+
+/// The type url of [`MsgRevokeAllowance`].
+pub const MSG_REVOKE_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.MsgRevokeAllowance";
+
+/// The type url of [`BasicAllowance`].
+pub const BASIC_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.BasicAllowance";
+
+/// The type url of [`AllowedMsgAllowance`].
+pub const ALLOWED_MSG_ALLOWANCE_TYPE_URL: &str = "/cosmos.feegrant.v1beta1.AllowedMsgAllowance";
+
+/// The filetree messages covered by [`Authorization::Filetree`].
+pub const FILETREE_MSG_TYPE_URLS: [&str; 5] = [
+    MSG_POST_KEY_TYPE_URL,
+    MSG_POST_FILE_TYPE_URL,
+    MSG_ADD_VIEWERS_TYPE_URL,
+    MSG_DELETE_FILE_TYPE_URL,
+    MSG_CHANGE_OWNER_TYPE_URL,
+];
+
+/// What an authz grant lets the grantee do as the ICA. Each message type is granted separately on the host.
+#[cw_serde]
+pub enum Authorization {
+    /// Execute one message type.
+    Generic {
+        /// The type url of the message, e.g. "/canine_chain.filetree.MsgPostFile".
+        msg_type_url: String,
+    },
+    /// Manage the ICA's files: every message in [`FILETREE_MSG_TYPE_URLS`].
+    Filetree {},
+}
+
+impl Authorization {
+    /// Returns the type urls of the messages the authorization covers.
+    pub fn msg_type_urls(&self) -> Vec<String> {
+        match self {
+            Self::Generic { msg_type_url } => vec![msg_type_url.clone()],
+            Self::Filetree {} => FILETREE_MSG_TYPE_URLS.iter().map(|type_url| type_url.to_string()).collect(),
+        }
+    }
+}
+
+/// An authz grant issued by the ICA.
+#[cw_serde]
+pub struct AuthzGrant {
+    /// Who may execute the message as the ICA.
+    pub grantee: String,
+    /// The type url of the message.
+    pub msg_type_url: String,
+    /// When the grant expires on the host, if it does.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<Timestamp>,
+    /// When the host acknowledged the grant.
+    pub granted_at: Timestamp,
+}
+
+/// A feegrant allowance issued by the ICA.
+#[cw_serde]
+pub struct FeeAllowance {
+    /// Who may pay fees from the ICA's balance.
+    pub grantee: String,
+    /// The most the grantee may spend on fees. Empty if there is no limit.
+    pub spend_limit: Vec<Coin>,
+    /// When the allowance expires on the host, if it does.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration: Option<Timestamp>,
+    /// The type urls of the messages whose fees are paid. Empty if every message's are.
+    pub allowed_messages: Vec<String>,
+    /// When the host acknowledged the allowance.
+    pub granted_at: Timestamp,
+}
+
+/// The grants a packet changes once the host acknowledges it.
+#[cw_serde]
+pub enum GrantChange {
+    /// Authz grants were issued. `granted_at` is set when they are recorded.
+    Grant {
+        /// The grants
+        grants: Vec<AuthzGrant>,
+    },
+    /// Authz grants were revoked.
+    Revoke {
+        /// Who the grants were issued to
+        grantee: String,
+        /// The type urls of the revoked messages
+        msg_type_urls: Vec<String>,
+    },
+    /// A feegrant allowance was issued. `granted_at` is set when it is recorded.
+    GrantAllowance {
+        /// The allowance
+        allowance: FeeAllowance,
+    },
+    /// A feegrant allowance was revoked.
+    RevokeAllowance {
+        /// Who the allowance was issued to
+        grantee: String,
+    },
+}
+
+/// The grants the ICA issued, returned by `GetGrants`.
+#[cw_serde]
+pub struct GrantsResponse {
+    /// The authz grants, ordered by grantee and type url.
+    pub authorizations: Vec<AuthzGrant>,
+    /// The feegrant allowances, ordered by grantee.
+    pub allowances: Vec<FeeAllowance>,
+}
+
+/// MsgGrant is a request type for Grant method. It declares authorization to the grantee
+/// on behalf of the granter with the provided expiration time.
+///
+/// cosmos-sdk-proto's version uses `prost_types::Timestamp`, which isn't a dependency of this crate.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgGrant {
+    /// The account issuing the grant, the ICA.
+    #[prost(string, tag = "1")]
+    pub granter: ::prost::alloc::string::String,
+    /// The account the grant is issued to.
+    #[prost(string, tag = "2")]
+    pub grantee: ::prost::alloc::string::String,
+    /// The grant.
+    #[prost(message, optional, tag = "3")]
+    pub grant: ::core::option::Option<Grant>,
+}
+
+/// Grant gives permissions to execute the provided method with expiration time.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Grant {
+    /// The authorization, e.g. a `GenericAuthorization`.
+    #[prost(message, optional, tag = "1")]
+    pub authorization: ::core::option::Option<Any>,
+    /// When the grant expires, if it does.
+    #[prost(message, optional, tag = "2")]
+    pub expiration: ::core::option::Option<ProtoTimestamp>,
+}
+
+/// BasicAllowance implements Allowance with a one-time grant of coins
+/// that optionally expires. The grantee can use up to SpendLimit to cover fees.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct BasicAllowance {
+    /// The most the grantee may spend on fees. Empty if there is no limit.
+    #[prost(message, repeated, tag = "1")]
+    pub spend_limit: ::prost::alloc::vec::Vec<ProtoCoin>,
+    /// When the allowance expires, if it does.
+    #[prost(message, optional, tag = "2")]
+    pub expiration: ::core::option::Option<ProtoTimestamp>,
+}
+
+/// Checks that the grantee is a Jackal account other than the ICA.
+/// Bech32 addresses are lowercase ASCII, anything else can't be an account on the host.
+pub fn validate_grantee(grantee: &str, ica_address: &str) -> Result<(), ContractError> {
+    let is_bech32_data = |data: &str| !data.is_empty() && data.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit());
+    if !grantee.strip_prefix("jkl1").is_some_and(is_bech32_data) || grantee == ica_address {
+        return Err(ContractError::InvalidAddress {});
+    }
+    Ok(())
+}
+
+/// Returns the `MsgGrant`s issuing the grants from the ICA.
+pub fn grant_msgs(ica_address: &str, grants: &[AuthzGrant]) -> Vec<Any> {
+    grants
+        .iter()
+        .map(|grant| {
+            let authorization = GenericAuthorization { msg: grant.msg_type_url.clone() };
+            let msg = MsgGrant {
+                granter: ica_address.to_string(),
+                grantee: grant.grantee.clone(),
+                grant: Some(Grant {
+                    authorization: Some(any(GENERIC_AUTHORIZATION_TYPE_URL, &authorization)),
+                    expiration: grant.expiration.map(proto_timestamp),
+                }),
+            };
+            any(MSG_GRANT_TYPE_URL, &msg)
+        })
+        .collect()
+}
+
+/// Returns the `MsgRevoke`s revoking the grantee's grants for the messages.
+pub fn revoke_msgs(ica_address: &str, grantee: &str, msg_type_urls: &[String]) -> Vec<Any> {
+    msg_type_urls
+        .iter()
+        .map(|msg_type_url| {
+            let msg = MsgRevoke {
+                granter: ica_address.to_string(),
+                grantee: grantee.to_string(),
+                msg_type_url: msg_type_url.clone(),
+            };
+            any(MSG_REVOKE_TYPE_URL, &msg)
+        })
+        .collect()
+}
+
+/// Returns the `MsgGrantAllowance` issuing the allowance from the ICA.
+/// A `BasicAllowance` is wrapped in an `AllowedMsgAllowance` if the allowance is limited to some messages.
+pub fn grant_allowance_msg(ica_address: &str, allowance: &FeeAllowance) -> Any {
+    let basic = BasicAllowance {
+        spend_limit: allowance
+            .spend_limit
+            .iter()
+            .map(|coin| ProtoCoin {
+                denom: coin.denom.clone(),
+                amount: coin.amount.to_string(),
+            })
+            .collect(),
+        expiration: allowance.expiration.map(proto_timestamp),
+    };
+    let mut fee_allowance = any(BASIC_ALLOWANCE_TYPE_URL, &basic);
+    if !allowance.allowed_messages.is_empty() {
+        let allowed = AllowedMsgAllowance {
+            allowance: Some(fee_allowance),
+            allowed_messages: allowance.allowed_messages.clone(),
+        };
+        fee_allowance = any(ALLOWED_MSG_ALLOWANCE_TYPE_URL, &allowed);
+    }
+
+    let msg = MsgGrantAllowance {
+        granter: ica_address.to_string(),
+        grantee: allowance.grantee.clone(),
+        allowance: Some(fee_allowance),
+    };
+    any(MSG_GRANT_ALLOWANCE_TYPE_URL, &msg)
+}
+
+/// Returns the `MsgRevokeAllowance` revoking the grantee's allowance.
+pub fn revoke_allowance_msg(ica_address: &str, grantee: &str) -> Any {
+    let msg = MsgRevokeAllowance {
+        granter: ica_address.to_string(),
+        grantee: grantee.to_string(),
+    };
+    any(MSG_REVOKE_ALLOWANCE_TYPE_URL, &msg)
+}
+
+/// Records the change once the host acknowledged the packet making it.
+pub fn apply(storage: &mut dyn Storage, change: GrantChange, now: Timestamp) -> StdResult<()> {
+    match change {
+        GrantChange::Grant { grants } => {
+            for grant in grants {
+                let grant = AuthzGrant { granted_at: now, ..grant };
+                AUTHZ_GRANTS.save(storage, (&grant.grantee, &grant.msg_type_url), &grant)?;
+            }
+        }
+        GrantChange::Revoke { grantee, msg_type_urls } => {
+            for msg_type_url in msg_type_urls {
+                AUTHZ_GRANTS.remove(storage, (&grantee, &msg_type_url));
+            }
+        }
+        GrantChange::GrantAllowance { allowance } => {
+            let allowance = FeeAllowance { granted_at: now, ..allowance };
+            FEE_ALLOWANCES.save(storage, &allowance.grantee, &allowance)?;
+        }
+        GrantChange::RevokeAllowance { grantee } => FEE_ALLOWANCES.remove(storage, &grantee),
+    }
+    Ok(())
+}
+
+/// Returns the grants that haven't expired, only the grantee's if one is given.
+pub fn active_grants(storage: &dyn Storage, grantee: Option<&str>, now: Timestamp) -> StdResult<GrantsResponse> {
+    let active = |expiration: &Option<Timestamp>| expiration.is_none_or(|expiration| expiration > now);
+
+    let authorizations = match grantee {
+        Some(grantee) => AUTHZ_GRANTS
+            .prefix(grantee)
+            .range(storage, None, None, Order::Ascending)
+            .map(|entry| entry.map(|(_, grant)| grant))
+            .collect::<StdResult<Vec<_>>>()?,
+        None => AUTHZ_GRANTS
+            .range(storage, None, None, Order::Ascending)
+            .map(|entry| entry.map(|(_, grant)| grant))
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+    let allowances = match grantee {
+        Some(grantee) => FEE_ALLOWANCES.may_load(storage, grantee)?.into_iter().collect(),
+        None => FEE_ALLOWANCES
+            .range(storage, None::<Bound<&str>>, None, Order::Ascending)
+            .map(|entry| entry.map(|(_, allowance)| allowance))
+            .collect::<StdResult<Vec<_>>>()?,
+    };
+
+    Ok(GrantsResponse {
+        authorizations: authorizations.into_iter().filter(|grant| active(&grant.expiration)).collect(),
+        allowances: allowances.into_iter().filter(|allowance| active(&allowance.expiration)).collect(),
+    })
+}
+
+fn any(type_url: &str, msg: &impl Message) -> Any {
+    Any {
+        type_url: type_url.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+fn proto_timestamp(timestamp: Timestamp) -> ProtoTimestamp {
+    ProtoTimestamp {
+        seconds: timestamp.seconds() as i64,
+        nanos: timestamp.subsec_nanos() as i32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{coins, testing::MockStorage};
+
+    use super::*;
+
+    fn authz_grant(grantee: &str, msg_type_url: &str, expiration: Option<Timestamp>) -> AuthzGrant {
+        AuthzGrant {
+            grantee: grantee.to_string(),
+            msg_type_url: msg_type_url.to_string(),
+            expiration,
+            granted_at: Timestamp::default(),
+        }
+    }
+
+    fn fee_allowance(grantee: &str, allowed_messages: &[&str], expiration: Option<Timestamp>) -> FeeAllowance {
+        FeeAllowance {
+            grantee: grantee.to_string(),
+            spend_limit: coins(1_000, "ujkl"),
+            expiration,
+            allowed_messages: allowed_messages.iter().map(|type_url| type_url.to_string()).collect(),
+            granted_at: Timestamp::default(),
+        }
+    }
+
+    #[test]
+    fn grantees_must_be_other_jackal_accounts() {
+        assert!(validate_grantee("jkl1hot", "jkl1ica").is_ok());
+        assert!(validate_grantee("jkl1hot2", "jkl1ica").is_ok());
+
+        for grantee in ["jkl1ica", "jkl1", "jkl1HOT", "jkl1hôt", "jkl1hot-key", "cosmos1hot", "", "JKL1hot"] {
+            let err = validate_grantee(grantee, "jkl1ica").unwrap_err();
+            assert!(matches!(err, ContractError::InvalidAddress {}), "{grantee}");
+        }
+    }
+
+    #[test]
+    fn allowances_limited_to_some_messages_are_wrapped() {
+        let msg = grant_allowance_msg("jkl1ica", &fee_allowance("jkl1hot", &[MSG_POST_FILE_TYPE_URL], None));
+        assert_eq!(msg.type_url, MSG_GRANT_ALLOWANCE_TYPE_URL);
+        let grant = MsgGrantAllowance::decode(msg.value.as_slice()).unwrap();
+        assert_eq!((grant.granter.as_str(), grant.grantee.as_str()), ("jkl1ica", "jkl1hot"));
+
+        let allowance = grant.allowance.unwrap();
+        assert_eq!(allowance.type_url, ALLOWED_MSG_ALLOWANCE_TYPE_URL);
+        let allowed = AllowedMsgAllowance::decode(allowance.value.as_slice()).unwrap();
+        assert_eq!(allowed.allowed_messages, vec![MSG_POST_FILE_TYPE_URL.to_string()]);
+        let basic = allowed.allowance.unwrap();
+        assert_eq!(basic.type_url, BASIC_ALLOWANCE_TYPE_URL);
+        let basic = BasicAllowance::decode(basic.value.as_slice()).unwrap();
+        assert_eq!((basic.spend_limit[0].denom.as_str(), basic.spend_limit[0].amount.as_str()), ("ujkl", "1000"));
+    }
+
+    #[test]
+    fn unlimited_allowances_are_basic() {
+        let expiration = Timestamp::from_seconds(1_700_000_000).plus_nanos(5);
+        let msg = grant_allowance_msg("jkl1ica", &fee_allowance("jkl1hot", &[], Some(expiration)));
+        let allowance = MsgGrantAllowance::decode(msg.value.as_slice()).unwrap().allowance.unwrap();
+        assert_eq!(allowance.type_url, BASIC_ALLOWANCE_TYPE_URL);
+        let basic = BasicAllowance::decode(allowance.value.as_slice()).unwrap();
+        assert_eq!(basic.expiration, Some(ProtoTimestamp { seconds: 1_700_000_000, nanos: 5 }));
+    }
+
+    #[test]
+    fn changes_are_recorded_when_applied() {
+        let mut storage = MockStorage::new();
+        let now = Timestamp::from_seconds(1_000);
+        let grants = vec![authz_grant("jkl1hot", MSG_POST_FILE_TYPE_URL, None), authz_grant("jkl1hot", MSG_DELETE_FILE_TYPE_URL, None)];
+
+        apply(&mut storage, GrantChange::Grant { grants }, now).unwrap();
+        let grant = AUTHZ_GRANTS.load(&storage, ("jkl1hot", MSG_POST_FILE_TYPE_URL)).unwrap();
+        assert_eq!(grant.granted_at, now);
+
+        let msg_type_urls = vec![MSG_DELETE_FILE_TYPE_URL.to_string()];
+        apply(&mut storage, GrantChange::Revoke { grantee: "jkl1hot".to_string(), msg_type_urls }, now).unwrap();
+        let type_urls: Vec<_> = active_grants(&storage, None, now).unwrap().authorizations.into_iter().map(|grant| grant.msg_type_url).collect();
+        assert_eq!(type_urls, vec![MSG_POST_FILE_TYPE_URL.to_string()]);
+
+        // A new allowance replaces the grantee's previous one
+        apply(&mut storage, GrantChange::GrantAllowance { allowance: fee_allowance("jkl1hot", &[MSG_POST_FILE_TYPE_URL], None) }, now).unwrap();
+        let later = now.plus_seconds(60);
+        apply(&mut storage, GrantChange::GrantAllowance { allowance: fee_allowance("jkl1hot", &[], None) }, later).unwrap();
+        let allowance = FEE_ALLOWANCES.load(&storage, "jkl1hot").unwrap();
+        assert_eq!((allowance.granted_at, allowance.allowed_messages.len()), (later, 0));
+
+        apply(&mut storage, GrantChange::RevokeAllowance { grantee: "jkl1hot".to_string() }, later).unwrap();
+        assert!(FEE_ALLOWANCES.may_load(&storage, "jkl1hot").unwrap().is_none());
+    }
+
+    #[test]
+    fn expired_grants_are_filtered_out() {
+        let mut storage = MockStorage::new();
+        let now = Timestamp::from_seconds(1_000);
+        let grants = vec![
+            authz_grant("jkl1fan", MSG_POST_FILE_TYPE_URL, None),
+            authz_grant("jkl1hot", MSG_POST_FILE_TYPE_URL, Some(now.plus_seconds(10))),
+            authz_grant("jkl1hot", MSG_POST_KEY_TYPE_URL, Some(now)),
+        ];
+        apply(&mut storage, GrantChange::Grant { grants }, now).unwrap();
+        apply(&mut storage, GrantChange::GrantAllowance { allowance: fee_allowance("jkl1hot", &[], Some(now.plus_seconds(10))) }, now).unwrap();
+
+        // A grant expiring now is no longer active
+        let grants = active_grants(&storage, None, now).unwrap();
+        let grantees: Vec<_> = grants.authorizations.iter().map(|grant| (grant.grantee.as_str(), grant.msg_type_url.as_str())).collect();
+        assert_eq!(grantees, vec![("jkl1fan", MSG_POST_FILE_TYPE_URL), ("jkl1hot", MSG_POST_FILE_TYPE_URL)]);
+        assert_eq!(grants.allowances.len(), 1);
+
+        let grants = active_grants(&storage, Some("jkl1hot"), now.plus_seconds(10)).unwrap();
+        assert!(grants.authorizations.is_empty() && grants.allowances.is_empty());
+        let grants = active_grants(&storage, None, now.plus_seconds(10)).unwrap();
+        assert_eq!((grants.authorizations.len(), grants.allowances.len()), (1, 0));
+    }
+}
@@ -0,0 +1,88 @@
+//! # Host params
+//!
+//! The outpost keeps a copy of the Jackal host's ICA host params, so packets whose messages the host
+//! doesn't allow are rejected before they are sent. A rejected packet only comes back as an error
+//! acknowledgement after a full relayer round trip, and a timed out packet closes the ordered channel.
+//!
+//! The copy is refreshed with [`HostQuery::IcaHostParams`](super::host_query::HostQuery::IcaHostParams),
+//! or set by the owner or admin with [`ExecuteMsg::SetHostAllowMessages`](super::msg::ExecuteMsg::SetHostAllowMessages).
+//! Nothing is checked until either happened.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Storage, Timestamp};
+
+use crate::ibc::types::packet::IcaPacketData;
+
+use super::{state::HOST_PARAMS, ContractError};
+
+/// The allow list entry allowing every message.
+pub const ALLOW_ALL_MESSAGES: &str = "*";
+
+/// The cached ICA host params of the host.
+#[cw_serde]
+pub struct HostParams {
+    /// Whether the host accepts ICA packets at all.
+    pub host_enabled: bool,
+    /// The type urls of the messages the ICA may execute.
+    pub allow_messages: Vec<String>,
+    /// Where the params came from.
+    pub source: HostParamsSource,
+    /// When the params were cached.
+    pub updated_at: Timestamp,
+}
+
+/// Where the cached [`HostParams`] came from.
+#[cw_serde]
+pub enum HostParamsSource {
+    /// Set by the owner or admin.
+    Admin {
+        /// Who set them.
+        sender: String,
+    },
+    /// Answered by the host to an interchain query.
+    HostQuery {
+        /// The host chain's height when it answered.
+        height: u64,
+    },
+}
+
+impl HostParams {
+    /// Returns the type urls the host doesn't allow, without duplicates.
+    pub fn disallowed<'a>(&self, type_urls: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+        if self.allow_messages.iter().any(|allowed| allowed == ALLOW_ALL_MESSAGES) {
+            return vec![];
+        }
+        let mut disallowed: Vec<String> = type_urls
+            .into_iter()
+            .filter(|type_url| !self.allow_messages.iter().any(|allowed| allowed == type_url))
+            .map(str::to_string)
+            .collect();
+        disallowed.sort();
+        disallowed.dedup();
+        disallowed
+    }
+}
+
+/// Checks that the host allows all of the given message types, if its params are cached.
+pub fn ensure_allowed<'a>(
+    storage: &dyn Storage,
+    type_urls: impl IntoIterator<Item = &'a str>,
+) -> Result<(), ContractError> {
+    let Some(params) = HOST_PARAMS.may_load(storage)? else {
+        return Ok(());
+    };
+    if !params.host_enabled {
+        return Err(ContractError::HostDisabled {});
+    }
+    let disallowed = params.disallowed(type_urls);
+    if !disallowed.is_empty() {
+        return Err(ContractError::MessagesNotAllowed(disallowed));
+    }
+    Ok(())
+}
+
+/// Checks that the host allows all of the packet's messages, if its params are cached.
+pub fn ensure_packet_allowed(storage: &dyn Storage, packet: &IcaPacketData) -> Result<(), ContractError> {
+    let type_urls = packet.type_urls()?;
+    ensure_allowed(storage, type_urls.iter().map(String::as_str))
+}
@@ -0,0 +1,545 @@
+//! # Host queries
+//!
+//! Reads filetree and storage state from Jackal through the ICA. The queries are sent in ibc-go's
+//! `MsgModuleQuerySafe` (ibc-go v8.2+), which the ICA host answers in the packet's acknowledgement.
+//! The latest answer to each query is kept under the query's key, and can be pushed to a contract.
+//! Only queries the host marks as `module_query_safe` can be answered.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_binary, Binary, StdResult, Storage, SubMsg, Timestamp, WasmMsg};
+use prost::Message;
+
+use super::{
+    host_params::{HostParams, HostParamsSource},
+    keys::CALLBACK_NOTIFICATION_REPLY_ID,
+    state::{HOST_PARAMS, HOST_QUERY_RESULTS},
+    ContractError,
+};
+
+/// The type url of `MsgModuleQuerySafe`.
+pub const MSG_MODULE_QUERY_SAFE_TYPE_URL: &str = "/ibc.applications.interchain_accounts.host.v1.MsgModuleQuerySafe";
+
+/// The most queries sent in one packet.
+pub const MAX_QUERIES: usize = 20;
+
+/// A query of Jackal's state.
+#[cw_serde]
+pub enum HostQuery {
+    /// A filetree file, as stored for its owner.
+    File {
+        /// The merkle path of the file.
+        address: String,
+        /// The owner address of the file, see [`owner_address`](crate::helpers::filetree_helpers::owner_address).
+        owner_address: String,
+    },
+    /// The public key an account posted to filetree.
+    PubKey {
+        /// The account's Bech32 address.
+        address: String,
+    },
+    /// The storage plan of an account.
+    StoragePaymentInfo {
+        /// The account's Bech32 address.
+        address: String,
+    },
+    /// The host's ICA host params. The answer refreshes the [cached params](super::host_params).
+    IcaHostParams {},
+}
+
+/// The host's answer to a [`HostQuery`].
+#[cw_serde]
+pub enum HostQueryResponse {
+    /// The answer to [`HostQuery::File`].
+    File {
+        /// The file's contents, e.g. its fid.
+        contents: String,
+        /// The owner address of the file.
+        owner: String,
+        /// The JSON map of the accounts allowed to view the file.
+        viewing_access: String,
+        /// The JSON map of the accounts allowed to edit the file.
+        edit_access: String,
+        /// The file's tracking number.
+        tracking_number: String,
+    },
+    /// The answer to [`HostQuery::PubKey`].
+    PubKey {
+        /// The posted public key.
+        key: String,
+    },
+    /// The answer to [`HostQuery::StoragePaymentInfo`].
+    StoragePaymentInfo {
+        /// When the plan started, in seconds since the unix epoch.
+        start: Option<u64>,
+        /// When the plan ends, in seconds since the unix epoch.
+        end: Option<u64>,
+        /// How many bytes the plan provides.
+        space_available: i64,
+        /// How many bytes are in use.
+        space_used: i64,
+    },
+    /// The answer to [`HostQuery::IcaHostParams`].
+    IcaHostParams {
+        /// Whether the host accepts ICA packets at all.
+        host_enabled: bool,
+        /// The type urls of the messages the ICA may execute.
+        allow_messages: Vec<String>,
+    },
+}
+
+/// The latest answer to a query.
+#[cw_serde]
+pub struct HostQueryResult {
+    /// The query.
+    pub query: HostQuery,
+    /// The answer.
+    pub response: HostQueryResponse,
+    /// The host chain's height when it answered.
+    pub height: u64,
+    /// When the answer was received.
+    pub received_at: Timestamp,
+}
+
+/// The message sent to the callback contract of a `QueryHost`.
+#[cw_serde]
+pub enum HostQueryCallbackMsg {
+    /// The host answered the queries, or failed to.
+    HostQueryResult {
+        /// The answers, in the order of the queries. Empty if the packet failed.
+        results: Vec<HostQueryResult>,
+        /// Why the packet failed, if it did.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+impl HostQuery {
+    /// Returns the key the latest answer to this query is stored under.
+    pub fn key(&self) -> String {
+        match self {
+            Self::File { address, owner_address } => format!("file/{address}/{owner_address}"),
+            Self::PubKey { address } => format!("pub_key/{address}"),
+            Self::StoragePaymentInfo { address } => format!("storage_payment_info/{address}"),
+            Self::IcaHostParams {} => "ica_host_params".to_string(),
+        }
+    }
+
+    fn to_request(&self) -> proto::QueryRequest {
+        let (path, data) = match self {
+            Self::File { address, owner_address } => (
+                "/canine_chain.filetree.Query/File",
+                proto::QueryFile {
+                    address: address.clone(),
+                    owner_address: owner_address.clone(),
+                }
+                .encode_to_vec(),
+            ),
+            Self::PubKey { address } => (
+                "/canine_chain.filetree.Query/PubKey",
+                proto::QueryPubKey { address: address.clone() }.encode_to_vec(),
+            ),
+            Self::StoragePaymentInfo { address } => (
+                "/canine_chain.storage.Query/StoragePaymentInfo",
+                proto::QueryStoragePaymentInfo { address: address.clone() }.encode_to_vec(),
+            ),
+            Self::IcaHostParams {} => ("/ibc.applications.interchain_accounts.host.v1.Query/Params", vec![]),
+        };
+        proto::QueryRequest {
+            path: path.to_string(),
+            data,
+        }
+    }
+
+    fn decode_response(&self, data: &[u8]) -> Result<HostQueryResponse, prost::DecodeError> {
+        Ok(match self {
+            Self::File { .. } => {
+                let file = proto::QueryFileResponse::decode(data)?.file.unwrap_or_default();
+                HostQueryResponse::File {
+                    contents: file.contents,
+                    owner: file.owner,
+                    viewing_access: file.viewing_access,
+                    edit_access: file.edit_access,
+                    tracking_number: file.tracking_number,
+                }
+            }
+            Self::PubKey { .. } => HostQueryResponse::PubKey {
+                key: proto::QueryPubKeyResponse::decode(data)?.pub_key.unwrap_or_default().key,
+            },
+            Self::StoragePaymentInfo { .. } => {
+                let info = proto::QueryStoragePaymentInfoResponse::decode(data)?
+                    .storage_payment_info
+                    .unwrap_or_default();
+                HostQueryResponse::StoragePaymentInfo {
+                    start: info.start.and_then(|start| u64::try_from(start.seconds).ok()),
+                    end: info.end.and_then(|end| u64::try_from(end.seconds).ok()),
+                    space_available: info.space_available,
+                    space_used: info.space_used,
+                }
+            }
+            Self::IcaHostParams {} => {
+                let params = proto::QueryParamsResponse::decode(data)?.params.unwrap_or_default();
+                HostQueryResponse::IcaHostParams {
+                    host_enabled: params.host_enabled,
+                    allow_messages: params.allow_messages,
+                }
+            }
+        })
+    }
+}
+
+/// Returns the `MsgModuleQuerySafe` the ICA sends to run the queries.
+pub fn query_msg(ica_address: &str, queries: &[HostQuery]) -> cosmos_sdk_proto::Any {
+    let msg = proto::MsgModuleQuerySafe {
+        signer: ica_address.to_string(),
+        requests: queries.iter().map(HostQuery::to_request).collect(),
+    };
+    cosmos_sdk_proto::Any {
+        type_url: MSG_MODULE_QUERY_SAFE_TYPE_URL.to_string(),
+        value: msg.encode_to_vec(),
+    }
+}
+
+/// Decodes the answers from the acknowledgement of a successful `MsgModuleQuerySafe` packet,
+/// and saves them as the latest answers to their queries.
+pub fn save_results(
+    storage: &mut dyn Storage,
+    queries: Vec<HostQuery>,
+    ack_result: &Binary,
+    received_at: Timestamp,
+) -> Result<Vec<HostQueryResult>, ContractError> {
+    let invalid_ack = |err: prost::DecodeError| ContractError::InvalidHostQueryResponse(err.to_string());
+
+    let tx_msg_data = proto::TxMsgData::decode(ack_result.as_slice()).map_err(invalid_ack)?;
+    let msg_response = tx_msg_data
+        .msg_responses
+        .into_iter()
+        .next()
+        .ok_or_else(|| ContractError::InvalidHostQueryResponse("no msg response".to_string()))?;
+    let response = proto::MsgModuleQuerySafeResponse::decode(msg_response.value.as_slice()).map_err(invalid_ack)?;
+    if response.responses.len() != queries.len() {
+        return Err(ContractError::InvalidHostQueryResponse(format!(
+            "expected {} responses, got {}",
+            queries.len(),
+            response.responses.len()
+        )));
+    }
+
+    let results = queries
+        .into_iter()
+        .zip(response.responses)
+        .map(|(query, data)| {
+            Ok(HostQueryResult {
+                response: query.decode_response(&data).map_err(invalid_ack)?,
+                query,
+                height: response.height,
+                received_at,
+            })
+        })
+        .collect::<Result<Vec<_>, ContractError>>()?;
+    for result in &results {
+        HOST_QUERY_RESULTS.save(storage, &result.query.key(), result)?;
+        if let HostQueryResponse::IcaHostParams { host_enabled, allow_messages } = &result.response {
+            let params = HostParams {
+                host_enabled: *host_enabled,
+                allow_messages: allow_messages.clone(),
+                source: HostParamsSource::HostQuery { height: result.height },
+                updated_at: received_at,
+            };
+            HOST_PARAMS.save(storage, &params)?;
+        }
+    }
+    Ok(results)
+}
+
+/// Returns the message pushing the answers to the callback contract. It's sent with `reply_on_error`,
+/// so a failing callback contract never fails the acknowledgement.
+pub fn callback_msg(contract: String, results: Vec<HostQueryResult>, error: Option<String>) -> StdResult<SubMsg> {
+    let msg = WasmMsg::Execute {
+        contract_addr: contract,
+        msg: to_json_binary(&HostQueryCallbackMsg::HostQueryResult { results, error })?,
+        funds: vec![],
+    };
+    Ok(SubMsg::reply_on_error(msg, CALLBACK_NOTIFICATION_REPLY_ID))
+}
+
+/// The protobuf messages of ibc-go's ICA host and canine-chain's queries.
+pub(crate) mod proto {
+    /// QueryRequest defines the parameters for a particular query request by an interchain account.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct QueryRequest {
+        /// path defines the path of the query request as defined by ADR-021.
+        #[prost(string, tag = "1")]
+        pub path: ::prost::alloc::string::String,
+        /// data defines the payload of the query request as defined by ADR-021.
+        #[prost(bytes = "vec", tag = "2")]
+        pub data: ::prost::alloc::vec::Vec<u8>,
+    }
+
+    /// MsgModuleQuerySafe defines the payload for Msg/ModuleQuerySafe
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MsgModuleQuerySafe {
+        /// signer address
+        #[prost(string, tag = "1")]
+        pub signer: ::prost::alloc::string::String,
+        /// requests defines the module safe queries to execute.
+        #[prost(message, repeated, tag = "2")]
+        pub requests: ::prost::alloc::vec::Vec<QueryRequest>,
+    }
+
+    /// MsgModuleQuerySafeResponse defines the response for Msg/ModuleQuerySafe
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct MsgModuleQuerySafeResponse {
+        /// height at which the responses were queried
+        #[prost(uint64, tag = "1")]
+        pub height: u64,
+        /// protobuf encoded responses for each query
+        #[prost(bytes = "vec", repeated, tag = "2")]
+        pub responses: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
+    }
+
+    /// TxMsgData contains the responses of a tx's msgs, as found in the ICA acknowledgement.
+    /// Only the non-deprecated field is declared.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct TxMsgData {
+        /// msg_responses contains the Msg handler responses packed into Anys.
+        #[prost(message, repeated, tag = "2")]
+        pub msg_responses: ::prost::alloc::vec::Vec<cosmos_sdk_proto::Any>,
+    }
+
+    /// Query a filetree file
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct QueryFile {
+        /// MerklePath( file path )
+        #[prost(string, tag = "1")]
+        pub address: ::prost::alloc::string::String,
+        /// owner_address( MerklePath(file path), owner )
+        #[prost(string, tag = "2")]
+        pub owner_address: ::prost::alloc::string::String,
+    }
+
+    /// A filetree file
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Files {
+        /// MerklePath( file path )
+        #[prost(string, tag = "1")]
+        pub address: ::prost::alloc::string::String,
+        /// the file's contents
+        #[prost(string, tag = "2")]
+        pub contents: ::prost::alloc::string::String,
+        /// owner_address( MerklePath(file path), owner )
+        #[prost(string, tag = "3")]
+        pub owner: ::prost::alloc::string::String,
+        /// JSON map of the viewers
+        #[prost(string, tag = "4")]
+        pub viewing_access: ::prost::alloc::string::String,
+        /// JSON map of the editors
+        #[prost(string, tag = "5")]
+        pub edit_access: ::prost::alloc::string::String,
+        /// the file's tracking number
+        #[prost(string, tag = "6")]
+        pub tracking_number: ::prost::alloc::string::String,
+    }
+
+    /// The response to [`QueryFile`]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct QueryFileResponse {
+        /// the file
+        #[prost(message, optional, tag = "1")]
+        pub file: ::core::option::Option<Files>,
+    }
+
+    /// Query the public key an account posted
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct QueryPubKey {
+        /// the account's address
+        #[prost(string, tag = "1")]
+        pub address: ::prost::alloc::string::String,
+    }
+
+    /// A posted public key
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct PubKey {
+        /// the account's address
+        #[prost(string, tag = "1")]
+        pub address: ::prost::alloc::string::String,
+        /// hex.encode(ecies.PublicKey)
+        #[prost(string, tag = "2")]
+        pub key: ::prost::alloc::string::String,
+    }
+
+    /// The response to [`QueryPubKey`]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct QueryPubKeyResponse {
+        /// the public key
+        #[prost(message, optional, tag = "1")]
+        pub pub_key: ::core::option::Option<PubKey>,
+    }
+
+    /// Query the storage plan of an account
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct QueryStoragePaymentInfo {
+        /// the account's address
+        #[prost(string, tag = "1")]
+        pub address: ::prost::alloc::string::String,
+    }
+
+    /// A storage plan
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct StoragePaymentInfo {
+        /// when the plan started
+        #[prost(message, optional, tag = "1")]
+        pub start: ::core::option::Option<cosmos_sdk_proto::tendermint::google::protobuf::Timestamp>,
+        /// when the plan ends
+        #[prost(message, optional, tag = "2")]
+        pub end: ::core::option::Option<cosmos_sdk_proto::tendermint::google::protobuf::Timestamp>,
+        /// how many bytes the plan provides
+        #[prost(int64, tag = "3")]
+        pub space_available: i64,
+        /// how many bytes are in use
+        #[prost(int64, tag = "4")]
+        pub space_used: i64,
+        /// the account's address
+        #[prost(string, tag = "5")]
+        pub address: ::prost::alloc::string::String,
+    }
+
+    /// The response to [`QueryStoragePaymentInfo`]
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct QueryStoragePaymentInfoResponse {
+        /// the storage plan
+        #[prost(message, optional, tag = "1")]
+        pub storage_payment_info: ::core::option::Option<StoragePaymentInfo>,
+    }
+
+    /// Params defines the set of on-chain interchain accounts parameters.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Params {
+        /// host_enabled enables or disables the host submodule.
+        #[prost(bool, tag = "1")]
+        pub host_enabled: bool,
+        /// allow_messages defines a list of sdk message typeURLs allowed to be executed on a host chain.
+        #[prost(string, repeated, tag = "2")]
+        pub allow_messages: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    }
+
+    /// QueryParamsResponse is the response type for the ICA host Query/Params RPC method.
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct QueryParamsResponse {
+        /// params defines the parameters of the module.
+        #[prost(message, optional, tag = "1")]
+        pub params: ::core::option::Option<Params>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::MockStorage;
+
+    use super::*;
+
+    /// The acknowledgement result of a `MsgModuleQuerySafe` answered at height 12.
+    fn ack_result(responses: Vec<Vec<u8>>) -> Binary {
+        let response = proto::MsgModuleQuerySafeResponse { height: 12, responses };
+        let tx_msg_data = proto::TxMsgData {
+            msg_responses: vec![cosmos_sdk_proto::Any {
+                type_url: "/ibc.applications.interchain_accounts.host.v1.MsgModuleQuerySafeResponse".to_string(),
+                value: response.encode_to_vec(),
+            }],
+        };
+        Binary::from(tx_msg_data.encode_to_vec())
+    }
+
+    fn pub_key_response(key: &str) -> Vec<u8> {
+        let pub_key = proto::PubKey { address: "jkl1hot".to_string(), key: key.to_string() };
+        proto::QueryPubKeyResponse { pub_key: Some(pub_key) }.encode_to_vec()
+    }
+
+    #[test]
+    fn answers_are_saved_under_their_query() {
+        let mut storage = MockStorage::new();
+        let query = HostQuery::PubKey { address: "jkl1hot".to_string() };
+        let received_at = Timestamp::from_seconds(1_000);
+
+        let results = save_results(&mut storage, vec![query.clone()], &ack_result(vec![pub_key_response("02ab")]), received_at).unwrap();
+        assert_eq!(results[0].response, HostQueryResponse::PubKey { key: "02ab".to_string() });
+        assert_eq!((results[0].height, results[0].received_at), (12, received_at));
+        assert_eq!(HOST_QUERY_RESULTS.load(&storage, &query.key()).unwrap(), results[0]);
+        assert!(HOST_PARAMS.may_load(&storage).unwrap().is_none());
+    }
+
+    #[test]
+    fn response_count_must_match_the_queries() {
+        let mut storage = MockStorage::new();
+        let queries = vec![HostQuery::PubKey { address: "jkl1hot".to_string() }, HostQuery::PubKey { address: "jkl1cold".to_string() }];
+
+        let err = save_results(&mut storage, queries, &ack_result(vec![pub_key_response("02ab")]), Timestamp::default()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHostQueryResponse(reason) if reason == "expected 2 responses, got 1"));
+        assert!(HOST_QUERY_RESULTS.may_load(&storage, "pub_key/jkl1hot").unwrap().is_none());
+    }
+
+    #[test]
+    fn acks_without_msg_responses_are_rejected() {
+        let mut storage = MockStorage::new();
+        let queries = vec![HostQuery::IcaHostParams {}];
+
+        let ack_result = Binary::from(proto::TxMsgData { msg_responses: vec![] }.encode_to_vec());
+        let err = save_results(&mut storage, queries.clone(), &ack_result, Timestamp::default()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHostQueryResponse(reason) if reason == "no msg response"));
+
+        let err = save_results(&mut storage, queries, &Binary::from(b"not protobuf".as_slice()), Timestamp::default()).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidHostQueryResponse(_)));
+    }
+
+    #[test]
+    fn ica_host_params_answers_refresh_the_cached_params() {
+        let mut storage = MockStorage::new();
+        let received_at = Timestamp::from_seconds(1_000);
+        let params = proto::Params {
+            host_enabled: true,
+            allow_messages: vec!["/canine_chain.filetree.MsgPostFile".to_string()],
+        };
+        let data = proto::QueryParamsResponse { params: Some(params) }.encode_to_vec();
+
+        save_results(&mut storage, vec![HostQuery::IcaHostParams {}], &ack_result(vec![data]), received_at).unwrap();
+        let params = HOST_PARAMS.load(&storage).unwrap();
+        assert_eq!(
+            params,
+            HostParams {
+                host_enabled: true,
+                allow_messages: vec!["/canine_chain.filetree.MsgPostFile".to_string()],
+                source: HostParamsSource::HostQuery { height: 12 },
+                updated_at: received_at,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_answers_decode_to_defaults() {
+        let response = HostQuery::StoragePaymentInfo { address: "jkl1hot".to_string() }.decode_response(&[]).unwrap();
+        assert_eq!(
+            response,
+            HostQueryResponse::StoragePaymentInfo {
+                start: None,
+                end: None,
+                space_available: 0,
+                space_used: 0,
+            }
+        );
+        assert!(HostQuery::File { address: "a".to_string(), owner_address: "b".to_string() }.decode_response(b"\x0a\xff").is_err());
+    }
+}
@@ -0,0 +1,232 @@
+//! # ICA funding
+//!
+//! Moves funds from this chain to the ICA over ICS-20, e.g. the JKL it needs to buy storage.
+//! The transfers carry an ibc-hooks callback memo, so the contract learns how each of them ended
+//! through `sudo` and refunds the sender if the transfer failed or timed out.
+//!
+//! The owner withdraws the ICA's funds with transfers sent from the host chain in an ICA packet, which is
+//! tracked on the ICA channel. Its acknowledgement only means the transfers left the host chain.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    Addr, BankMsg, ChannelResponse, Coin, CosmosMsg, Deps, DepsMut, Env, IbcMsg, IbcQuery, IbcTimeout, Order,
+    StdResult, Storage, SubMsg,
+};
+
+use super::{
+    state::{PacketKind, PACKET_LEDGER, STATE, TRANSFER_CHANNEL},
+    ContractError,
+};
+use sha2::{Digest, Sha256};
+
+use crate::ibc::types::{
+    keys::TRANSFER_PORT_ID,
+    memo::PacketMemo,
+    packet::{IcaPacketData, DEFAULT_TIMEOUT_SECONDS},
+    stargate,
+};
+
+/// A transfer to the ICA waiting for its acknowledgement.
+#[cw_serde]
+pub struct PendingTransfer {
+    /// The sequence of the transfer packet on the transfer channel.
+    pub sequence: u64,
+    /// Who sent the funds, and gets them back if the transfer fails.
+    pub sender: String,
+    /// The transferred coin.
+    pub amount: Coin,
+}
+
+/// An ICA packet withdrawing the ICA's funds, waiting for its acknowledgement.
+#[cw_serde]
+pub struct PendingWithdrawal {
+    /// The sequence of the packet on the ICA channel.
+    pub sequence: u64,
+    /// The address receiving the funds on this chain.
+    pub receiver: String,
+    /// The withdrawn coins.
+    pub amount: Vec<Coin>,
+}
+
+/// The response to [`QueryMsg::GetIcaFunding`](super::msg::QueryMsg::GetIcaFunding).
+#[cw_serde]
+pub struct IcaFunding {
+    /// The ICS-20 channel the ICA is funded over, if the owner configured one.
+    pub transfer_channel_id: Option<String>,
+    /// The transfers waiting for their acknowledgement, oldest first.
+    pub pending: Vec<PendingTransfer>,
+    /// The withdrawals waiting for their acknowledgement, oldest first.
+    pub withdrawals: Vec<PendingWithdrawal>,
+}
+
+/// Returns the memo asking ibc-hooks to call the contract back with the transfer's acknowledgement,
+/// on top of the caller's own middleware instructions.
+///
+/// `forward` would send the funds on past the ICA, and a `wasm` hook can't run since the ICA isn't the
+/// hook contract, so only the callbacks are accepted.
+pub fn callback_memo(contract: &Addr, memo: Option<PacketMemo>) -> Result<String, ContractError> {
+    let memo = memo.unwrap_or_default();
+    if memo.ibc_callback.is_some() {
+        return Err(ContractError::InvalidMemo("ibc_callback is set by the outpost".to_string()));
+    }
+    if memo.forward.is_some() {
+        return Err(ContractError::InvalidMemo("the funds must stay on the ICA, forward is not allowed".to_string()));
+    }
+    if memo.wasm.is_some() {
+        return Err(ContractError::InvalidMemo("the ICA is not a contract, wasm is not allowed".to_string()));
+    }
+    memo.with_ibc_callback(contract).to_json_string()
+}
+
+/// Settles the transfer with the given sequence and returns the refund to its sender if it failed.
+/// Returns `None` if the transfer isn't tracked, e.g. if it was settled already.
+pub fn on_transfer_result(
+    storage: &mut dyn Storage,
+    channel_id: &str,
+    sequence: u64,
+    succeeded: bool,
+) -> StdResult<Option<(PendingTransfer, Option<BankMsg>)>> {
+    let key = (channel_id, sequence);
+    let Some(PacketKind::FundIca { sender, amount }) = PACKET_LEDGER.may_load(storage, key)? else {
+        return Ok(None);
+    };
+    PACKET_LEDGER.remove(storage, key);
+
+    let refund = (!succeeded).then(|| BankMsg::Send {
+        to_address: sender.clone(),
+        amount: vec![amount.clone()],
+    });
+    Ok(Some((PendingTransfer { sequence, sender, amount }, refund)))
+}
+
+/// Returns the funding configuration and the transfers and withdrawals still waiting for their acknowledgement.
+pub fn ica_funding(storage: &dyn Storage) -> StdResult<IcaFunding> {
+    let transfer_channel_id = TRANSFER_CHANNEL.may_load(storage)?;
+    let pending = match &transfer_channel_id {
+        Some(channel_id) => PACKET_LEDGER
+            .prefix(channel_id)
+            .range(storage, None, None, Order::Ascending)
+            .filter_map(|entry| match entry {
+                Ok((sequence, PacketKind::FundIca { sender, amount })) => Some(Ok(PendingTransfer { sequence, sender, amount })),
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<StdResult<_>>()?,
+        None => vec![],
+    };
+    let withdrawals = match STATE.load(storage)?.ica_info {
+        Some(ica_info) => PACKET_LEDGER
+            .prefix(&ica_info.channel_id)
+            .range(storage, None, None, Order::Ascending)
+            .filter_map(|entry| match entry {
+                Ok((sequence, PacketKind::Withdrawal { receiver, amount, .. })) => {
+                    Some(Ok(PendingWithdrawal { sequence, receiver, amount }))
+                }
+                Ok(_) => None,
+                Err(err) => Some(Err(err)),
+            })
+            .collect::<StdResult<_>>()?,
+        None => vec![],
+    };
+
+    Ok(IcaFunding {
+        transfer_channel_id,
+        pending,
+        withdrawals,
+    })
+}
+
+/// Returns the host's end of the transfer channel, which the ICA sends funds back from.
+pub fn host_transfer_channel(deps: Deps) -> Result<String, ContractError> {
+    let channel_id = TRANSFER_CHANNEL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoTransferChannel {})?;
+    let response: ChannelResponse = deps.querier.query(
+        &IbcQuery::Channel {
+            channel_id,
+            port_id: Some(TRANSFER_PORT_ID.to_string()),
+        }
+        .into(),
+    )?;
+
+    Ok(response
+        .channel
+        .ok_or(ContractError::NoTransferChannel {})?
+        .counterparty_endpoint
+        .channel_id)
+}
+
+/// Returns the denom a coin sent to the ICA over the transfer channel arrives as on the host.
+///
+/// A voucher going back over the channel it came in on is unwound to its previous denom, anything else
+/// becomes a voucher of the host's end of the channel.
+pub fn host_denom(deps: Deps, denom: &str) -> Result<String, ContractError> {
+    let channel_id = TRANSFER_CHANNEL
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoTransferChannel {})?;
+    let host_channel_id = host_transfer_channel(deps)?;
+    let (path, base_denom) = if denom.starts_with("ibc/") {
+        let trace = stargate::query::denom_trace(&deps.querier, denom)?;
+        (trace.path, trace.base_denom)
+    } else {
+        (String::new(), denom.to_string())
+    };
+
+    let hop = format!("{TRANSFER_PORT_ID}/{channel_id}");
+    let host_path = match path.strip_prefix(&hop) {
+        Some("") => String::new(),
+        Some(rest) if rest.starts_with('/') => rest[1..].to_string(),
+        _ if path.is_empty() => format!("{TRANSFER_PORT_ID}/{host_channel_id}"),
+        _ => format!("{TRANSFER_PORT_ID}/{host_channel_id}/{path}"),
+    };
+    if host_path.is_empty() {
+        return Ok(base_denom);
+    }
+    let hash = Sha256::digest(format!("{host_path}/{base_denom}").as_bytes());
+    Ok(format!("ibc/{}", hex::encode_upper(hash)))
+}
+
+/// Returns the tracked ICA packet in which the ICA sends the given funds back to the receiver on this chain.
+/// A withdrawal refunding a storage purchase marks it as refunded once it's acknowledged.
+pub fn withdrawal_packet(
+    deps: DepsMut,
+    env: &Env,
+    host_channel_id: &str,
+    receiver: String,
+    amount: Vec<Coin>,
+    purchase_id: Option<u64>,
+    timeout_seconds: Option<u64>,
+) -> Result<SubMsg, ContractError> {
+    let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+    let messages = host_transfers(env, host_channel_id, amount.clone(), &receiver, timeout_seconds);
+    let ica_packet = IcaPacketData::from_cosmos_msgs(messages, &ica_info.encoding, None, &ica_info.ica_address)?;
+    let send_packet_msg = ica_packet.to_ibc_msg(env, ica_info.channel_id, timeout_seconds)?;
+
+    Ok(PacketKind::Withdrawal { receiver, amount, purchase_id }.track(deps.storage, send_packet_msg)?)
+}
+
+/// Returns the ICS-20 transfers the ICA sends from the host chain, one per coin.
+/// The transfers only start once the host receives the packet, so they get the packet's timeout on top of theirs.
+pub fn host_transfers(
+    env: &Env,
+    host_channel_id: &str,
+    amount: Vec<Coin>,
+    receiver: &str,
+    timeout_seconds: Option<u64>,
+) -> Vec<CosmosMsg> {
+    let transfer_timeout = env
+        .block
+        .time
+        .plus_seconds(timeout_seconds.unwrap_or(DEFAULT_TIMEOUT_SECONDS) + DEFAULT_TIMEOUT_SECONDS);
+    amount
+        .into_iter()
+        .map(|amount| {
+            CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: host_channel_id.to_string(),
+                to_address: receiver.to_string(),
+                amount,
+                timeout: IbcTimeout::with_timestamp(transfer_timeout),
+            })
+        })
+        .collect()
+}
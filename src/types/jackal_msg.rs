@@ -0,0 +1,492 @@
+//! # Jackal messages
+//!
+//! [`JackalMsg`] describes the Jackal messages the ICA can execute as plain JSON, so callers don't have to
+//! encode them into [`CosmosMsg::Stargate`](cosmwasm_std::CosmosMsg::Stargate) bytes themselves. They are
+//! sent with [`ExecuteMsg::SendJackalMsgs`](super::msg::ExecuteMsg::SendJackalMsgs), or as
+//! [`CosmosMsg::Custom`](cosmwasm_std::CosmosMsg::Custom) through [`convert_to_proto_any`](super::cosmos_msg::convert_to_proto_any).
+//!
+//! The ICA signs every message, so signer fields like `creator` or `account` are derived from its address
+//! rather than set by the caller. [`JackalMsg::validate`] catches what the host would reject for sure.
+
+use cosmos_sdk_proto::{cosmos::base::v1beta1::Coin as ProtoCoin, Any};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Coin;
+use prost::Message;
+
+use crate::helpers::{
+    filetree_helpers::{hash_and_hex, merkle_helper, merkle_path, owner_address},
+    rns_helpers,
+};
+
+use super::{
+    cosmos_msg::ToProtoAny,
+    file_transfer::change_owner_msg,
+    filetree::{
+        MsgAddViewers, MsgDeleteFile, MsgPostFile, MsgPostKey, MSG_ADD_VIEWERS_TYPE_URL, MSG_DELETE_FILE_TYPE_URL,
+        MSG_POST_FILE_TYPE_URL, MSG_POST_KEY_TYPE_URL,
+    },
+    notifications::{
+        MsgBlockSenders, MsgCreateNotifications, MsgSetCounter, MSG_BLOCK_SENDERS_TYPE_URL,
+        MSG_CREATE_NOTIFICATIONS_TYPE_URL, MSG_SET_COUNTER_TYPE_URL,
+    },
+    rns::{
+        MsgAcceptBid, MsgAddRecord, MsgBid, MsgBuy, MsgCancelBid, MsgDelRecord, MsgDelist, MsgInit, MsgList,
+        MsgRegister, MsgTransfer, MsgUpdate, MSG_ACCEPT_BID_TYPE_URL, MSG_ADD_RECORD_TYPE_URL, MSG_BID_TYPE_URL,
+        MSG_BUY_TYPE_URL, MSG_CANCEL_BID_TYPE_URL, MSG_DELIST_TYPE_URL, MSG_DEL_RECORD_TYPE_URL, MSG_INIT_TYPE_URL,
+        MSG_LIST_TYPE_URL, MSG_REGISTER_TYPE_URL, MSG_TRANSFER_TYPE_URL, MSG_UPDATE_TYPE_URL,
+    },
+    storage::{MsgBuyStorage, MSG_BUY_STORAGE_TYPE_URL},
+    ContractError,
+};
+
+/// A Jackal message executed by the ICA.
+#[cw_serde]
+pub enum JackalMsg {
+    /// A message of the filetree module.
+    Filetree(FiletreeMsg),
+    /// A message of the storage module.
+    Storage(StorageMsg),
+    /// A message of the rns (name service) module.
+    Rns(RnsMsg),
+    /// A message of the notifications module.
+    Notifications(NotificationsMsg),
+}
+
+impl cosmwasm_std::CustomMsg for JackalMsg {}
+
+/// The messages of canine-chain's filetree module. Paths are plain file paths, e.g. `s/home/notes.txt`.
+#[cw_serde]
+pub enum FiletreeMsg {
+    /// Posts the ICA's public key.
+    PostKey {
+        /// hex.encode(ecies.PublicKey)
+        key: String,
+    },
+    /// Posts a file, or a folder, under its parent.
+    PostFile {
+        /// The path of the file.
+        path: String,
+        /// The file's contents, e.g. its fid.
+        contents: String,
+        /// The JSON map of the viewers' encrypted keys.
+        viewers: String,
+        /// The JSON map of the editors' encrypted keys.
+        editors: String,
+        /// The UUID the viewer and editor map keys are derived from.
+        tracking_number: String,
+    },
+    /// Lets more accounts decrypt a file.
+    AddViewers {
+        /// The path of the file.
+        path: String,
+        /// The viewer ids, see [`MsgAddViewers`].
+        viewer_ids: Vec<String>,
+        /// The viewers' encrypted keys, in the order of the ids.
+        viewer_keys: Vec<String>,
+        /// The Bech32 addresses of the viewers to notify that the file was shared with them.
+        /// [`ExecuteMsg::SendJackalMsgs`](super::msg::ExecuteMsg::SendJackalMsgs) sends one
+        /// [`shared_file_notification`] to each of them, in a second packet: the host rejects notifying
+        /// an account without a notification counter, which must not fail adding the viewers.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        notify: Vec<String>,
+    },
+    /// Deletes a file.
+    DeleteFile {
+        /// The path of the file.
+        path: String,
+    },
+    /// Hands a file over to another account.
+    ChangeOwner {
+        /// The path of the file.
+        path: String,
+        /// The Bech32 address of the new owner.
+        new_owner: String,
+    },
+}
+
+/// The messages of canine-chain's storage module.
+#[cw_serde]
+pub enum StorageMsg {
+    /// Buys a storage plan, paid by the ICA.
+    BuyStorage {
+        /// The address the plan is bought for. If not specified, the ICA is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        for_address: Option<String>,
+        /// How long the plan lasts.
+        duration_days: i64,
+        /// How many bytes the plan provides.
+        bytes: i64,
+        /// The denom the plan is paid in.
+        payment_denom: String,
+        /// Optional referral address.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        referral: Option<String>,
+    },
+}
+
+/// The messages of canine-chain's rns module. Names are given with their top level domain, e.g. `alice.jkl`.
+#[cw_serde]
+pub enum RnsMsg {
+    /// Registers a name for the ICA.
+    Register {
+        /// The name.
+        name: String,
+        /// How many years to register the name for.
+        years: i64,
+        /// JSON data attached to the name.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        data: Option<String>,
+    },
+    /// Extends the registration of a name the ICA owns, by registering it again.
+    /// Registering replaces the name's data, so the data to keep must be passed along.
+    Renew {
+        /// The name.
+        name: String,
+        /// How many years to extend the registration by.
+        years: i64,
+        /// The name's data after the renewal, e.g. its current data. "{}" clears it.
+        data: String,
+    },
+    /// Hands a name over to another account.
+    Transfer {
+        /// The name.
+        name: String,
+        /// The Bech32 address of the new owner.
+        receiver: String,
+    },
+    /// Replaces the JSON data attached to a name.
+    SetData {
+        /// The name.
+        name: String,
+        /// The new JSON data.
+        data: String,
+    },
+    /// Adds a sub-domain record to a name.
+    AddRecord {
+        /// The name.
+        name: String,
+        /// The sub-domain, e.g. `app` for `app.alice.jkl`.
+        record: String,
+        /// The Bech32 address the record resolves to.
+        value: String,
+        /// JSON data attached to the record.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        data: Option<String>,
+    },
+    /// Removes a sub-domain record from a name.
+    RemoveRecord {
+        /// The name.
+        name: String,
+        /// The sub-domain, e.g. `app` for `app.alice.jkl`.
+        record: String,
+    },
+    /// Puts a name up for sale.
+    List {
+        /// The name.
+        name: String,
+        /// The asking price.
+        price: Coin,
+    },
+    /// Takes a name off the market.
+    Delist {
+        /// The name.
+        name: String,
+    },
+    /// Buys a listed name at its asking price.
+    Buy {
+        /// The name.
+        name: String,
+    },
+    /// Bids on a name. The bid is held in escrow until it's accepted or cancelled.
+    Bid {
+        /// The name.
+        name: String,
+        /// The bid.
+        bid: Coin,
+    },
+    /// Sells a name the ICA owns to a bidder.
+    AcceptBid {
+        /// The name.
+        name: String,
+        /// The Bech32 address of the bidder.
+        from: String,
+    },
+    /// Withdraws the ICA's bid on a name.
+    CancelBid {
+        /// The name.
+        name: String,
+    },
+    /// Initializes the ICA's rns account.
+    Init {},
+}
+
+/// The messages of canine-chain's notifications module.
+#[cw_serde]
+pub enum NotificationsMsg {
+    /// Notifies an account.
+    Notify {
+        /// The Bech32 address of the recipient.
+        to: String,
+        /// The notification, as a JSON object.
+        notification: String,
+    },
+    /// Sets up the ICA's notification counter, so others can notify it.
+    SetCounter {},
+    /// Stops accounts from notifying the ICA.
+    BlockSenders {
+        /// The Bech32 addresses of the blocked senders.
+        senders: Vec<String>,
+    },
+}
+
+/// Returns the notification telling a viewer that the owner shared a file with them.
+/// `address` is the file's merkle path, which the viewer can look the file up with.
+pub fn shared_file_notification(owner: &str, address: &str) -> String {
+    format!(r#"{{"type":"file_shared","owner":"{owner}","address":"{address}"}}"#)
+}
+
+impl JackalMsg {
+    /// Returns the message signed by the ICA.
+    pub fn to_any(self, ica_address: &str) -> Any {
+        match self {
+            Self::Filetree(msg) => msg.to_any(ica_address),
+            Self::Storage(msg) => msg.to_any(ica_address),
+            Self::Rns(msg) => msg.to_any(ica_address),
+            Self::Notifications(msg) => msg.to_any(ica_address),
+        }
+    }
+
+    /// Returns the notifications the message asks to send along with it, see [`FiletreeMsg::AddViewers`].
+    pub fn notifications(&self, ica_address: &str) -> Vec<JackalMsg> {
+        match self {
+            Self::Filetree(FiletreeMsg::AddViewers { path, notify, .. }) => {
+                let notification = shared_file_notification(ica_address, &merkle_path(path));
+                notify
+                    .iter()
+                    .map(|to| {
+                        Self::Notifications(NotificationsMsg::Notify {
+                            to: to.clone(),
+                            notification: notification.clone(),
+                        })
+                    })
+                    .collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Checks what the host would reject for sure, like malformed rns names.
+    pub fn validate(&self) -> Result<(), ContractError> {
+        match self {
+            Self::Rns(msg) => msg.validate(),
+            Self::Notifications(msg) => msg.validate(),
+            Self::Filetree(_) | Self::Storage(_) => Ok(()),
+        }
+    }
+}
+
+impl ToProtoAny for JackalMsg {
+    fn to_proto_any(self, from_address: String) -> Result<Any, ContractError> {
+        Ok(self.to_any(&from_address))
+    }
+}
+
+impl FiletreeMsg {
+    /// Returns the message signed by the ICA.
+    pub fn to_any(self, ica_address: &str) -> Any {
+        let creator = ica_address.to_string();
+        let (type_url, value) = match self {
+            Self::PostKey { key } => (MSG_POST_KEY_TYPE_URL, MsgPostKey { creator, key }.encode_to_vec()),
+            Self::PostFile {
+                path,
+                contents,
+                viewers,
+                editors,
+                tracking_number,
+            } => {
+                let (hash_parent, hash_child) = merkle_helper(&path);
+                let msg = MsgPostFile {
+                    creator,
+                    account: hash_and_hex(ica_address),
+                    hash_parent,
+                    hash_child,
+                    contents,
+                    viewers,
+                    editors,
+                    tracking_number,
+                };
+                (MSG_POST_FILE_TYPE_URL, msg.encode_to_vec())
+            }
+            Self::AddViewers {
+                path,
+                viewer_ids,
+                viewer_keys,
+                ..
+            } => {
+                let address = merkle_path(&path);
+                let msg = MsgAddViewers {
+                    creator,
+                    viewer_ids: viewer_ids.join(","),
+                    viewer_keys: viewer_keys.join(","),
+                    file_owner: owner_address(&address, ica_address),
+                    address,
+                };
+                (MSG_ADD_VIEWERS_TYPE_URL, msg.encode_to_vec())
+            }
+            Self::DeleteFile { path } => {
+                let msg = MsgDeleteFile {
+                    creator,
+                    hash_path: merkle_path(&path),
+                    account: hash_and_hex(ica_address),
+                };
+                (MSG_DELETE_FILE_TYPE_URL, msg.encode_to_vec())
+            }
+            Self::ChangeOwner { path, new_owner } => return change_owner_msg(&path, ica_address, &new_owner),
+        };
+
+        Any {
+            type_url: type_url.to_string(),
+            value,
+        }
+    }
+}
+
+impl StorageMsg {
+    /// Returns the message signed by the ICA.
+    pub fn to_any(self, ica_address: &str) -> Any {
+        match self {
+            Self::BuyStorage {
+                for_address,
+                duration_days,
+                bytes,
+                payment_denom,
+                referral,
+            } => {
+                let msg = MsgBuyStorage {
+                    creator: ica_address.to_string(),
+                    for_address: for_address.unwrap_or_else(|| ica_address.to_string()),
+                    duration_days,
+                    bytes,
+                    payment_denom,
+                    referral: referral.unwrap_or_default(),
+                };
+                Any {
+                    type_url: MSG_BUY_STORAGE_TYPE_URL.to_string(),
+                    value: msg.encode_to_vec(),
+                }
+            }
+        }
+    }
+}
+
+impl RnsMsg {
+    /// Checks the names and records with [`rns_helpers`].
+    pub fn validate(&self) -> Result<(), ContractError> {
+        match self {
+            Self::AddRecord { name, record, .. } | Self::RemoveRecord { name, record } => {
+                rns_helpers::validate_name(name)?;
+                rns_helpers::validate_record(record)
+            }
+            Self::Register { name, .. }
+            | Self::Renew { name, .. }
+            | Self::Transfer { name, .. }
+            | Self::SetData { name, .. }
+            | Self::List { name, .. }
+            | Self::Delist { name }
+            | Self::Buy { name }
+            | Self::Bid { name, .. }
+            | Self::AcceptBid { name, .. }
+            | Self::CancelBid { name } => rns_helpers::validate_name(name),
+            Self::Init {} => Ok(()),
+        }
+    }
+
+    /// Returns the message signed by the ICA.
+    pub fn to_any(self, ica_address: &str) -> Any {
+        let creator = ica_address.to_string();
+        let proto_coin = |coin: Coin| ProtoCoin {
+            denom: coin.denom,
+            amount: coin.amount.to_string(),
+        };
+        let (type_url, value) = match self {
+            Self::Register { name, years, data } => {
+                let data = data.unwrap_or_else(|| "{}".to_string());
+                (MSG_REGISTER_TYPE_URL, MsgRegister { creator, name, years, data }.encode_to_vec())
+            }
+            Self::Renew { name, years, data } => {
+                (MSG_REGISTER_TYPE_URL, MsgRegister { creator, name, years, data }.encode_to_vec())
+            }
+            Self::Transfer { name, receiver } => {
+                (MSG_TRANSFER_TYPE_URL, MsgTransfer { creator, name, receiver }.encode_to_vec())
+            }
+            Self::SetData { name, data } => (MSG_UPDATE_TYPE_URL, MsgUpdate { creator, name, data }.encode_to_vec()),
+            Self::AddRecord { name, record, value, data } => {
+                let data = data.unwrap_or_else(|| "{}".to_string());
+                (MSG_ADD_RECORD_TYPE_URL, MsgAddRecord { creator, name, value, data, record }.encode_to_vec())
+            }
+            Self::RemoveRecord { name, record } => {
+                let name = rns_helpers::record_name(&record, &name);
+                (MSG_DEL_RECORD_TYPE_URL, MsgDelRecord { creator, name }.encode_to_vec())
+            }
+            Self::List { name, price } => {
+                let price = Some(proto_coin(price));
+                (MSG_LIST_TYPE_URL, MsgList { creator, name, price }.encode_to_vec())
+            }
+            Self::Delist { name } => (MSG_DELIST_TYPE_URL, MsgDelist { creator, name }.encode_to_vec()),
+            Self::Buy { name } => (MSG_BUY_TYPE_URL, MsgBuy { creator, name }.encode_to_vec()),
+            Self::Bid { name, bid } => {
+                let bid = Some(proto_coin(bid));
+                (MSG_BID_TYPE_URL, MsgBid { creator, name, bid }.encode_to_vec())
+            }
+            Self::AcceptBid { name, from } => {
+                (MSG_ACCEPT_BID_TYPE_URL, MsgAcceptBid { creator, name, from }.encode_to_vec())
+            }
+            Self::CancelBid { name } => (MSG_CANCEL_BID_TYPE_URL, MsgCancelBid { creator, name }.encode_to_vec()),
+            Self::Init {} => (MSG_INIT_TYPE_URL, MsgInit { creator }.encode_to_vec()),
+        };
+
+        Any {
+            type_url: type_url.to_string(),
+            value,
+        }
+    }
+}
+
+impl NotificationsMsg {
+    /// Checks that notifications are JSON objects, which is how clients read them.
+    pub fn validate(&self) -> Result<(), ContractError> {
+        match self {
+            Self::Notify { notification, .. } => crate::ibc::types::memo::ensure_json_object(notification)
+                .map_err(|_| ContractError::InvalidNotification(notification.clone())),
+            Self::SetCounter {} | Self::BlockSenders { .. } => Ok(()),
+        }
+    }
+
+    /// Returns the message signed by the ICA.
+    pub fn to_any(self, ica_address: &str) -> Any {
+        let creator = ica_address.to_string();
+        let (type_url, value) = match self {
+            Self::Notify { to, notification } => {
+                let msg = MsgCreateNotifications {
+                    creator,
+                    notification,
+                    address: to,
+                };
+                (MSG_CREATE_NOTIFICATIONS_TYPE_URL, msg.encode_to_vec())
+            }
+            Self::SetCounter {} => (MSG_SET_COUNTER_TYPE_URL, MsgSetCounter { creator }.encode_to_vec()),
+            Self::BlockSenders { senders } => {
+                let msg = MsgBlockSenders {
+                    creator,
+                    sender_ids: senders.join(","),
+                };
+                (MSG_BLOCK_SENDERS_TYPE_URL, msg.encode_to_vec())
+            }
+        };
+
+        Any {
+            type_url: type_url.to_string(),
+            value,
+        }
+    }
+}
@@ -8,3 +8,11 @@ pub const CONTRACT_NAME: &str = "crates.io:storage-outpost";
 /// CONTRACT_VERSION is the version of the cargo package.
 /// This is also the version of the contract recorded in cw2
 pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The reply id of the lifecycle notifications sent to the callback contract.
+/// These are sent with `reply_on_error` so that a failing callback contract never fails the IBC handshake.
+pub const CALLBACK_NOTIFICATION_REPLY_ID: u64 = 1;
+
+/// The reply id of ICA packets and ICS-20 transfers whose acknowledgement the contract acts on.
+/// The reply carries the packet's sequence, which is recorded in the packet ledger.
+pub const SEND_PACKET_REPLY_ID: u64 = 2;
@@ -6,6 +6,19 @@ pub mod keys;
 pub mod msg;
 pub mod state;
 pub mod filetree;
+pub mod rns;
+pub mod notifications;
+pub mod gov;
+pub mod grants;
 pub mod callback;
+pub mod decommission;
+pub mod file_transfer;
+pub mod host_params;
+pub mod host_query;
+pub mod ica_funding;
+pub mod jackal_msg;
+pub mod signer;
+pub mod storage;
+pub mod storage_purchase;
 
 pub use error::ContractError;
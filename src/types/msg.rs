@@ -3,7 +3,7 @@
 //! This module defines the messages the ICA controller contract receives.
 
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Binary, CosmosMsg};
+use cosmwasm_std::{Binary, Coin, CosmosMsg, Timestamp};
 
 use super::callback::Callback;
 
@@ -37,6 +37,7 @@ pub enum ExecuteMsg {
     /// This is a wrapper around [`options::ChannelOpenInitOptions`] and thus requires the
     /// same fields. If not specified, then the options specified in the contract instantiation
     /// are used.
+    /// The admin may also call this without options to retry a handshake that never completed.
     CreateChannel {
         /// The options to initialize the IBC channel.
         /// If not specified, the options specified in the last channel creation are used.
@@ -53,13 +54,300 @@ pub enum ExecuteMsg {
     SendCosmosMsgs {
         /// The stargate messages to convert and send to the ICA host.
         messages: Vec<CosmosMsg>,
-        /// Optional memo to include in the ibc packet.
+        /// Optional memo to include in the ibc packet. Memos starting with `{` must be valid JSON objects,
+        /// see [`PacketMemo`](crate::ibc::types::memo::PacketMemo) to build them.
         #[serde(skip_serializing_if = "Option::is_none")]
         packet_memo: Option<String>,
         /// Optional timeout in seconds to include with the ibc packet. 
         /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
         #[serde(skip_serializing_if = "Option::is_none")]
         timeout_seconds: Option<u64>,
+        /// If true, the `creator` and derived signer fields of known Jackal messages are filled in with the
+        /// ICA address, or checked against it if they are set. See [`crate::types::signer`].
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fill_signer_fields: Option<bool>,
+    },
+
+    /// `SendJackalMsgs` sends the provided [`JackalMsg`](crate::types::jackal_msg::JackalMsg)s to the ICA host
+    /// in one tx. Can only be called by the owner, on a protobuf channel. The ICA signs every message, so they don't name a signer.
+    /// The notifications asked for by `add_viewers` are sent in a second tx.
+    SendJackalMsgs {
+        /// The Jackal messages to send to the ICA host.
+        messages: Vec<crate::types::jackal_msg::JackalMsg>,
+        /// Optional memo to include in the ibc packet, see [`ExecuteMsg::SendCosmosMsgs`].
+        #[serde(skip_serializing_if = "Option::is_none")]
+        packet_memo: Option<String>,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `Decommission` retires the outpost for good. Can only be called by the owner.
+    /// The cleanup messages and fund transfers are sent to the ICA host in one packet. Once it succeeds,
+    /// the channel is closed and the outpost is unregistered from the factory. If it fails, nothing changed
+    /// on the host and the owner may call this again.
+    Decommission {
+        /// Messages the ICA executes before the outpost is retired, e.g. filetree messages deleting
+        /// or handing over the owner's files.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        cleanup_messages: Vec<CosmosMsg>,
+        /// Funds the ICA sends back over ICS-20.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        return_funds: Option<options::ReturnFunds>,
+        /// Optional timeout in seconds for the cleanup packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `TransferFiles` hands the given filetree files over to the ICA of another outpost with `MsgChangeOwner`.
+    /// Can only be called by the owner, on a protobuf channel. The files are sent in batches, one ICA packet per batch, and
+    /// their progress is returned by [`QueryMsg::GetFileTransfers`]. Files whose batch failed can be sent again.
+    TransferFiles {
+        /// The paths of the files to transfer, e.g. "s/home/photo.jpg".
+        paths: Vec<String>,
+        /// Who receives the files.
+        destination: options::FileDestination,
+        /// How many files are sent per packet. If not specified,
+        /// [the default](crate::types::file_transfer::DEFAULT_BATCH_SIZE) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        batch_size: Option<u32>,
+        /// Optional timeout in seconds for each packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `SetTransferChannel` sets the ICS-20 channel on this chain that leads to the ICA host. Can only be called by the owner.
+    SetTransferChannel {
+        /// The channel id on this chain.
+        channel_id: String,
+    },
+
+    /// `FundIca` sends the attached funds to the ICA over the [transfer channel](ExecuteMsg::SetTransferChannel).
+    /// Each coin is sent in its own transfer. If a transfer fails or times out, its coin is refunded to the sender.
+    FundIca {
+        /// Optional ADR-8 callbacks. The funds must arrive on the ICA, so `forward` and `wasm` are rejected,
+        /// and the outpost sets `ibc_callback` itself.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        memo: Option<crate::ibc::types::memo::PacketMemo>,
+        /// Optional timeout in seconds for the transfers.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `WithdrawFromIca` makes the ICA send funds back to this chain over the host's end of the
+    /// [transfer channel](ExecuteMsg::SetTransferChannel). Can only be called by the owner.
+    /// Each coin is sent in its own transfer, all in one ICA packet.
+    WithdrawFromIca {
+        /// The coins to withdraw, in their denoms on the host chain.
+        amount: Vec<Coin>,
+        /// The address receiving the funds on this chain. If not specified, the owner is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        receiver: Option<String>,
+        /// Optional timeout in seconds for the ICA packet. The transfers get the
+        /// [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) on top of it.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `BuyStorage` sends the attached coin to the ICA over the [transfer channel](ExecuteMsg::SetTransferChannel),
+    /// and has the ICA buy a storage plan with it once it arrives. Can only be called by the owner, on a protobuf channel.
+    /// The purchase's progress is returned by [`QueryMsg::GetStoragePurchase`], and the funds are
+    /// refunded if either step fails. Returns the purchase id as data.
+    BuyStorage {
+        /// How many bytes the plan provides.
+        bytes: u64,
+        /// How long the plan lasts.
+        duration_days: u64,
+        /// The Jackal address the plan is bought for. If not specified, the ICA is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        for_address: Option<String>,
+        /// The denom the attached coin arrives in on Jackal. It's worked out from the coin's denom trace if not
+        /// specified, and the purchase is rejected if it's specified but the coin arrives in another denom.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        payment_denom: Option<String>,
+        /// Optional referral address.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        referral: Option<String>,
+        /// Optional timeout in seconds for the transfer and the purchase packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `QueryHost` has the ICA query Jackal's filetree and storage state with `MsgModuleQuerySafe`.
+    /// Can only be called by the owner, on a protobuf channel. The latest answer to each query is returned by
+    /// [`QueryMsg::GetHostQueryResult`], and pushed to the callback contract if one is given.
+    QueryHost {
+        /// The queries, at most [`MAX_QUERIES`](crate::types::host_query::MAX_QUERIES).
+        queries: Vec<crate::types::host_query::HostQuery>,
+        /// A contract executed with a [`HostQueryCallbackMsg`](crate::types::host_query::HostQueryCallbackMsg)
+        /// once the packet is acknowledged or times out. Its failure doesn't affect the outpost.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        callback: Option<String>,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `Vote` makes the ICA vote on a canine-chain proposal with its staked JKL. Can only be called by the owner.
+    Vote {
+        /// The id of the proposal.
+        proposal_id: u64,
+        /// The vote.
+        option: cosmwasm_std::VoteOption,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `VoteWeighted` makes the ICA split its vote on a canine-chain proposal. Can only be called by the owner.
+    VoteWeighted {
+        /// The id of the proposal.
+        proposal_id: u64,
+        /// The votes and their weights, which must add up to 1.
+        options: Vec<cosmwasm_std::WeightedVoteOption>,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `SubmitProposal` makes the ICA submit a gov v1 proposal on canine-chain. Can only be called by the owner.
+    /// The initial deposit is paid by the ICA.
+    SubmitProposal {
+        /// The title of the proposal.
+        title: String,
+        /// The summary of the proposal.
+        summary: String,
+        /// The messages the gov module executes if the proposal passes.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        messages: Vec<crate::types::gov::ProposalMsg>,
+        /// The deposit paid with the proposal.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        initial_deposit: Vec<Coin>,
+        /// Optional metadata attached to the proposal.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        metadata: Option<String>,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `Deposit` makes the ICA add to the deposit of a canine-chain proposal. Can only be called by the owner.
+    Deposit {
+        /// The id of the proposal.
+        proposal_id: u64,
+        /// The deposit, paid by the ICA.
+        amount: Vec<Coin>,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `GrantAuthorization` makes the ICA issue authz grants, letting another Jackal account execute messages
+    /// as the ICA. Can only be called by the owner, on a protobuf channel. The grants are returned by [`QueryMsg::GetGrants`] once
+    /// the host acknowledges them.
+    GrantAuthorization {
+        /// The Jackal account the grants are issued to.
+        grantee: String,
+        /// What the grantee may do.
+        authorization: crate::types::grants::Authorization,
+        /// When the grants expire. If not specified, they last until revoked.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expiration: Option<Timestamp>,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `RevokeAuthorization` makes the ICA revoke authz grants. Can only be called by the owner.
+    RevokeAuthorization {
+        /// The Jackal account the grants were issued to.
+        grantee: String,
+        /// The authorization to revoke.
+        authorization: crate::types::grants::Authorization,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `GrantAllowance` makes the ICA issue a feegrant allowance, letting another Jackal account pay its fees
+    /// from the ICA's balance. Replaces the grantee's allowance, if it has one. Can only be called by the owner.
+    GrantAllowance {
+        /// The Jackal account the allowance is issued to.
+        grantee: String,
+        /// The most the grantee may spend on fees. If empty, there is no limit.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        spend_limit: Vec<Coin>,
+        /// When the allowance expires. If not specified, it lasts until revoked.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expiration: Option<Timestamp>,
+        /// The type urls of the messages whose fees are paid. If empty, every message's are.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        allowed_messages: Vec<String>,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `RevokeAllowance` makes the ICA revoke a feegrant allowance. Can only be called by the owner.
+    RevokeAllowance {
+        /// The Jackal account the allowance was issued to.
+        grantee: String,
+        /// Optional timeout in seconds to include with the ibc packet.
+        /// If not specified, the [default timeout](crate::ibc::types::packet::DEFAULT_TIMEOUT_SECONDS) is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        timeout_seconds: Option<u64>,
+    },
+
+    /// `SetHostAllowMessages` sets the cached list of the messages the host allows the ICA to execute,
+    /// see [`crate::types::host_params`]. Can only be called by the owner or the admin.
+    SetHostAllowMessages {
+        /// The allowed type urls, `"*"` allows every message. If not specified, the cache is
+        /// cleared and packets are no longer checked.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        allow_messages: Option<Vec<String>>,
+    },
+}
+
+/// The messages ibc-hooks sends the contract once an ICS-20 transfer it sent with a callback memo completes.
+#[cw_serde]
+pub enum SudoMsg {
+    /// The transfer was acknowledged or timed out.
+    IbcLifecycleComplete(IbcLifecycleComplete),
+}
+
+/// How an ICS-20 transfer completed.
+#[cw_serde]
+pub enum IbcLifecycleComplete {
+    /// The transfer was acknowledged.
+    IbcAck {
+        /// The channel the transfer was sent on
+        channel: String,
+        /// The sequence of the transfer packet
+        sequence: u64,
+        /// The acknowledgement, as a JSON string
+        ack: String,
+        /// Whether the transfer succeeded
+        success: bool,
+    },
+    /// The transfer timed out.
+    IbcTimeout {
+        /// The channel the transfer was sent on
+        channel: String,
+        /// The sequence of the transfer packet
+        sequence: u64,
     },
 }
 
@@ -78,7 +366,45 @@ pub enum OutpostFactoryExecuteMsg {
     MapUserOutpost {
         /// The user's address who will own the outpost
         outpost_owner: String, // this function is called for a specific purpose of updating a map so nothing is optional
-    }
+        /// The name the owner gave the outpost. If not specified, it's the owner's default outpost
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+    },
+    /// Sent by the outpost when the channel open handshake completes
+    OutpostChannelOpened {
+        /// The Interchain Account address on the host chain
+        ica_address: String,
+    },
+    /// Sent by the outpost when its channel is closed, e.g. after a packet timeout
+    OutpostChannelClosed {},
+    /// Sent by the outpost once it's decommissioned, so the factory can unregister it
+    OutpostDecommissioned {},
+}
+
+/// The queries of the outpost factory the outpost uses, see [`OutpostFactoryExecuteMsg`] for why they are copied here.
+#[cw_serde]
+pub enum OutpostFactoryQueryMsg {
+    /// Resolves a user address to their outpost and its ICA address
+    GetDirectoryEntryByUser {
+        /// The user owning the outpost
+        user_address: String,
+        /// The name of the outpost. If not specified, the user's default outpost is used
+        #[serde(skip_serializing_if = "Option::is_none")]
+        outpost_name: Option<String>,
+    },
+}
+
+/// The outpost factory's response to [`OutpostFactoryQueryMsg::GetDirectoryEntryByUser`]
+#[cw_serde]
+pub struct DirectoryEntry {
+    /// The user owning the outpost
+    pub user_address: String,
+    /// The name of the outpost
+    pub outpost_name: String,
+    /// The address of the outpost
+    pub outpost_address: String,
+    /// The outpost's ICA address on the host chain
+    pub ica_address: String,
 }
 
 /// The messages to query the ICA controller contract.
@@ -95,6 +421,64 @@ pub enum QueryMsg {
     /// GetCallbackCounter returns the callback counter.
     #[returns(crate::types::state::CallbackCounter)]
     GetCallbackCounter {},
+    /// GetDecommission returns the progress of the outpost's decommissioning, if the owner requested it.
+    #[returns(Option<crate::types::decommission::Decommission>)]
+    GetDecommission {},
+    /// GetFileTransfers returns the files handed over with `TransferFiles`, ordered by path.
+    #[returns(Vec<crate::types::file_transfer::FileTransfer>)]
+    GetFileTransfers {
+        /// The path to start after, for pagination.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        start_after: Option<String>,
+        /// How many transfers to return. Defaults to 30, at most 100.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+    /// GetIcaFunding returns the transfer channel and the transfers to the ICA waiting for their acknowledgement.
+    #[returns(crate::types::ica_funding::IcaFunding)]
+    GetIcaFunding {},
+    /// GetStoragePurchase returns a storage purchase made with `BuyStorage`.
+    #[returns(crate::types::storage_purchase::StoragePurchase)]
+    GetStoragePurchase {
+        /// The id of the purchase.
+        id: u64,
+    },
+    /// GetStoragePurchases returns the storage purchases made with `BuyStorage`, newest first.
+    #[returns(Vec<crate::types::storage_purchase::StoragePurchase>)]
+    GetStoragePurchases {
+        /// The id to start before, for pagination.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        start_before: Option<u64>,
+        /// How many purchases to return. Defaults to 30, at most 100.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
+    /// GetHostParams returns the cached ICA host params of the host, if there are any.
+    #[returns(Option<crate::types::host_params::HostParams>)]
+    GetHostParams {},
+    /// GetHostQueryResult returns the latest answer to a query sent with `QueryHost`, if one was received.
+    #[returns(Option<crate::types::host_query::HostQueryResult>)]
+    GetHostQueryResult {
+        /// The query.
+        query: crate::types::host_query::HostQuery,
+    },
+    /// GetGrants returns the authz grants and feegrant allowances issued by the ICA that haven't expired.
+    #[returns(crate::types::grants::GrantsResponse)]
+    GetGrants {
+        /// Only return the grants issued to this account.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        grantee: Option<String>,
+    },
+    /// GetHostQueryResults returns the latest answers to queries sent with `QueryHost`, ordered by query key.
+    #[returns(Vec<crate::types::host_query::HostQueryResult>)]
+    GetHostQueryResults {
+        /// The query key to start after, for pagination.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        start_after: Option<String>,
+        /// How many answers to return. Defaults to 30, at most 100.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        limit: Option<u32>,
+    },
 }
 
 /// The message to migrate this contract.
@@ -125,6 +509,41 @@ pub mod options {
         pub channel_ordering: Option<IbcOrder>,
     }
 
+    /// The ICA's funds to send back when decommissioning the outpost.
+    #[cw_serde]
+    pub struct ReturnFunds {
+        /// The ICS-20 channel on the host chain that leads back to the receiver's chain.
+        pub transfer_channel_id: String,
+        /// The coins to send. Each coin is sent in its own ICS-20 transfer.
+        pub amount: Vec<Coin>,
+        /// The address receiving the funds on this chain. If not specified, the outpost owner is used.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub receiver: Option<String>,
+    }
+
+    /// Who receives the files of a `TransferFiles`.
+    #[cw_serde]
+    pub enum FileDestination {
+        /// An ICA address on the host chain.
+        Ica {
+            /// The Bech32 address of the ICA.
+            address: String,
+        },
+        /// Another outpost, whose ICA receives the files.
+        Outpost {
+            /// The address of the outpost.
+            address: String,
+        },
+        /// A user's outpost, looked up in the factory that created this outpost.
+        User {
+            /// The user owning the outpost.
+            user_address: String,
+            /// The name of the outpost. If not specified, the user's default outpost is used.
+            #[serde(skip_serializing_if = "Option::is_none")]
+            outpost_name: Option<String>,
+        },
+    }
+
     impl ChannelOpenInitOptions {
         /// Returns the counterparty port id.
         pub fn counterparty_port_id(&self) -> String {
@@ -0,0 +1,69 @@
+//! # notifications
+//!
+//! Contains the transaction msgs needed to interact with canine-chain's notifications module,
+//! which lets accounts notify each other, e.g. that a file was shared with them.
+//! documentation for the notifications module can be found here:
+//! https://github.com/JackalLabs/canine-chain/tree/master/x/notifications
+
+/// The type url of [`MsgCreateNotifications`] on canine-chain.
+pub const MSG_CREATE_NOTIFICATIONS_TYPE_URL: &str = "/canine_chain.notifications.MsgCreateNotifications";
+
+/// The type url of [`MsgSetCounter`] on canine-chain.
+pub const MSG_SET_COUNTER_TYPE_URL: &str = "/canine_chain.notifications.MsgSetCounter";
+
+/// The type url of [`MsgBlockSenders`] on canine-chain.
+pub const MSG_BLOCK_SENDERS_TYPE_URL: &str = "/canine_chain.notifications.MsgBlockSenders";
+
+/// Send a notification to an account
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgCreateNotifications {
+    /// The sender's Bech32 address, which broadcasts this message
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The notification, as JSON
+    #[prost(string, tag = "2")]
+    pub notification: ::prost::alloc::string::String,
+
+    /// The recipient's Bech32 address
+    #[prost(string, tag = "3")]
+    pub address: ::prost::alloc::string::String,
+}
+
+/// A successful broadcast guarantees that the notification was stored, so the response is empty
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgCreateNotificationsResponse {}
+
+/// Set up the creator's notification counter, which has to exist before it can be notified
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgSetCounter {
+    /// The Bech32 address of the counter's owner
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+}
+
+/// A successful broadcast guarantees that the counter was set, so the response is empty
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgSetCounterResponse {}
+
+/// Stop accounts from notifying the creator
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgBlockSenders {
+    /// The Bech32 address of the blocking account
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// Comma separated Bech32 addresses of the blocked senders
+    #[prost(string, tag = "2")]
+    pub sender_ids: ::prost::alloc::string::String,
+}
+
+/// A successful broadcast guarantees that the senders are blocked, so the response is empty
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgBlockSendersResponse {}
@@ -0,0 +1,237 @@
+//! # rns
+//!
+//! Contains the transaction msgs needed to interact with canine-chain's rns (name service) module.
+//! documentation for the rns module can be found here:
+//! https://github.com/JackalLabs/canine-chain/tree/master/x/rns
+//!
+//! Names are given with their top level domain, e.g. `alice.jkl`, see [`crate::helpers::rns_helpers`].
+
+use cosmos_sdk_proto::cosmos::base::v1beta1::Coin;
+
+/// The type url of [`MsgRegister`] on canine-chain.
+pub const MSG_REGISTER_TYPE_URL: &str = "/canine_chain.rns.MsgRegister";
+
+/// The type url of [`MsgUpdate`] on canine-chain.
+pub const MSG_UPDATE_TYPE_URL: &str = "/canine_chain.rns.MsgUpdate";
+
+/// The type url of [`MsgTransfer`] on canine-chain.
+pub const MSG_TRANSFER_TYPE_URL: &str = "/canine_chain.rns.MsgTransfer";
+
+/// The type url of [`MsgAddRecord`] on canine-chain.
+pub const MSG_ADD_RECORD_TYPE_URL: &str = "/canine_chain.rns.MsgAddRecord";
+
+/// The type url of [`MsgDelRecord`] on canine-chain.
+pub const MSG_DEL_RECORD_TYPE_URL: &str = "/canine_chain.rns.MsgDelRecord";
+
+/// The type url of [`MsgList`] on canine-chain.
+pub const MSG_LIST_TYPE_URL: &str = "/canine_chain.rns.MsgList";
+
+/// The type url of [`MsgDelist`] on canine-chain.
+pub const MSG_DELIST_TYPE_URL: &str = "/canine_chain.rns.MsgDelist";
+
+/// The type url of [`MsgBuy`] on canine-chain.
+pub const MSG_BUY_TYPE_URL: &str = "/canine_chain.rns.MsgBuy";
+
+/// The type url of [`MsgBid`] on canine-chain.
+pub const MSG_BID_TYPE_URL: &str = "/canine_chain.rns.MsgBid";
+
+/// The type url of [`MsgAcceptBid`] on canine-chain.
+pub const MSG_ACCEPT_BID_TYPE_URL: &str = "/canine_chain.rns.MsgAcceptBid";
+
+/// The type url of [`MsgCancelBid`] on canine-chain.
+pub const MSG_CANCEL_BID_TYPE_URL: &str = "/canine_chain.rns.MsgCancelBid";
+
+/// The type url of [`MsgInit`] on canine-chain.
+pub const MSG_INIT_TYPE_URL: &str = "/canine_chain.rns.MsgInit";
+
+/// Register a name, or extend the registration of a name the creator owns
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgRegister {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name to register, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+
+    /// How many years to register the name for
+    #[prost(int64, tag = "3")]
+    pub years: i64,
+
+    /// JSON data attached to the name
+    #[prost(string, tag = "4")]
+    pub data: ::prost::alloc::string::String,
+}
+
+/// Replace the JSON data attached to a name
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgUpdate {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+
+    /// The new JSON data
+    #[prost(string, tag = "3")]
+    pub data: ::prost::alloc::string::String,
+}
+
+/// Hand a name over to another account
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgTransfer {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+
+    /// The Bech32 address of the new owner
+    #[prost(string, tag = "3")]
+    pub receiver: ::prost::alloc::string::String,
+}
+
+/// Add a sub-domain record to a name
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgAddRecord {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+
+    /// The Bech32 address the record resolves to
+    #[prost(string, tag = "3")]
+    pub value: ::prost::alloc::string::String,
+
+    /// JSON data attached to the record
+    #[prost(string, tag = "4")]
+    pub data: ::prost::alloc::string::String,
+
+    /// The sub-domain, e.g. `app` for `app.alice.jkl`
+    #[prost(string, tag = "5")]
+    pub record: ::prost::alloc::string::String,
+}
+
+/// Remove a sub-domain record
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgDelRecord {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The full record name, e.g. `app.alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+}
+
+/// Put a name up for sale
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgList {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+
+    /// The asking price
+    #[prost(message, optional, tag = "3")]
+    pub price: ::core::option::Option<Coin>,
+}
+
+/// Take a name off the market
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgDelist {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+}
+
+/// Buy a listed name at its asking price
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgBuy {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+}
+
+/// Bid on a name, the bid is held in escrow until it's accepted or cancelled
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgBid {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+
+    /// The bid
+    #[prost(message, optional, tag = "3")]
+    pub bid: ::core::option::Option<Coin>,
+}
+
+/// Sell a name to a bidder
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgAcceptBid {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+
+    /// The Bech32 address of the bidder
+    #[prost(string, tag = "3")]
+    pub from: ::prost::alloc::string::String,
+}
+
+/// Withdraw a bid
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgCancelBid {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// The name, e.g. `alice.jkl`
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+}
+
+/// Initialize the creator's account with a free name
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgInit {
+    /// The Bech32 address of the broadcaster
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+}
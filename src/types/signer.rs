@@ -0,0 +1,95 @@
+//! # Signer fields
+//!
+//! Jackal messages name their signer in their own fields: `creator` is the ICA's address, and fields like
+//! `account` or `file_owner` are derived from it. With `fill_signer_fields`, contracts can send these
+//! messages through the outpost without knowing its ICA address. Empty fields are filled in, and fields
+//! that are set must match the ICA, so a message signed for another account is rejected before it's sent.
+//!
+//! Only [`CosmosMsg::Stargate`] messages of the types below are touched, anything else is sent as is:
+//! - `MsgPostKey`: `creator`
+//! - `MsgPostFile`: `creator`, `account`
+//! - `MsgAddViewers`: `creator`, `file_owner`
+//! - `MsgDeleteFile`: `creator`, `account`
+//! - `MsgChangeOwner`: `creator`, `file_owner`
+//! - `MsgBuyStorage`: `creator`
+
+use cosmwasm_std::{Binary, CosmosMsg};
+use prost::Message;
+
+use crate::helpers::filetree_helpers::{hash_and_hex, owner_address};
+
+use super::{
+    file_transfer::MSG_CHANGE_OWNER_TYPE_URL,
+    filetree::{
+        MsgAddViewers, MsgChangeOwner, MsgDeleteFile, MsgPostFile, MsgPostKey, MSG_ADD_VIEWERS_TYPE_URL,
+        MSG_DELETE_FILE_TYPE_URL, MSG_POST_FILE_TYPE_URL, MSG_POST_KEY_TYPE_URL,
+    },
+    storage::{MsgBuyStorage, MSG_BUY_STORAGE_TYPE_URL},
+    ContractError,
+};
+
+/// Fills in or verifies the signer fields of a known Jackal message against the ICA address.
+pub fn fill_signer_fields(msg: CosmosMsg, ica_address: &str) -> Result<CosmosMsg, ContractError> {
+    let CosmosMsg::Stargate { type_url, value } = msg else {
+        return Ok(msg);
+    };
+    let fill = |field: &str, value: &mut String, expected: String| fill_field(&type_url, field, value, expected);
+
+    let value = match type_url.as_str() {
+        MSG_POST_KEY_TYPE_URL => {
+            let mut msg = MsgPostKey::decode(value.as_slice())?;
+            fill("creator", &mut msg.creator, ica_address.to_string())?;
+            msg.encode_to_vec()
+        }
+        MSG_POST_FILE_TYPE_URL => {
+            let mut msg = MsgPostFile::decode(value.as_slice())?;
+            fill("creator", &mut msg.creator, ica_address.to_string())?;
+            fill("account", &mut msg.account, hash_and_hex(ica_address))?;
+            msg.encode_to_vec()
+        }
+        MSG_ADD_VIEWERS_TYPE_URL => {
+            let mut msg = MsgAddViewers::decode(value.as_slice())?;
+            fill("creator", &mut msg.creator, ica_address.to_string())?;
+            fill("file_owner", &mut msg.file_owner, owner_address(&msg.address, ica_address))?;
+            msg.encode_to_vec()
+        }
+        MSG_DELETE_FILE_TYPE_URL => {
+            let mut msg = MsgDeleteFile::decode(value.as_slice())?;
+            fill("creator", &mut msg.creator, ica_address.to_string())?;
+            fill("account", &mut msg.account, hash_and_hex(ica_address))?;
+            msg.encode_to_vec()
+        }
+        MSG_CHANGE_OWNER_TYPE_URL => {
+            let mut msg = MsgChangeOwner::decode(value.as_slice())?;
+            fill("creator", &mut msg.creator, ica_address.to_string())?;
+            fill("file_owner", &mut msg.file_owner, owner_address(&msg.address, ica_address))?;
+            msg.encode_to_vec()
+        }
+        MSG_BUY_STORAGE_TYPE_URL => {
+            let mut msg = MsgBuyStorage::decode(value.as_slice())?;
+            fill("creator", &mut msg.creator, ica_address.to_string())?;
+            msg.encode_to_vec()
+        }
+        _ => return Ok(CosmosMsg::Stargate { type_url, value }),
+    };
+
+    Ok(CosmosMsg::Stargate {
+        type_url,
+        value: Binary(value),
+    })
+}
+
+/// Sets an empty field to its expected value, or errors if it's set to anything else.
+fn fill_field(type_url: &str, field: &str, value: &mut String, expected: String) -> Result<(), ContractError> {
+    if value.is_empty() {
+        *value = expected;
+    } else if *value != expected {
+        return Err(ContractError::SignerMismatch {
+            type_url: type_url.to_string(),
+            field: field.to_string(),
+            expected,
+            found: value.clone(),
+        });
+    }
+    Ok(())
+}
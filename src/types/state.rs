@@ -1,13 +1,18 @@
 //! This module defines the state storage of the Contract.
 
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, IbcChannel};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Coin, CosmosMsg, IbcChannel, StdResult, Storage, SubMsg};
+use cw_storage_plus::{Deque, Item, Map};
 
-use super::{msg::options::ChannelOpenInitOptions, ContractError};
+use super::{
+    callback::Callback, decommission::Decommission, file_transfer::FileTransfer,
+    grants::{AuthzGrant, FeeAllowance, GrantChange}, host_params::HostParams, host_query::{HostQuery, HostQueryResult}, keys::SEND_PACKET_REPLY_ID,
+    msg::options::ChannelOpenInitOptions, storage_purchase::StoragePurchase, ContractError,
+};
 
 pub use channel::ChannelState;
 pub use contract::{CallbackCounter, ContractState};
+pub use packets::PacketKind;
 
 /// The item used to store the state of the IBC application.
 pub const STATE: Item<ContractState> = Item::new("state");
@@ -30,6 +35,46 @@ pub const ALLOW_CHANNEL_CLOSE_INIT: Item<bool> = Item::new("allow_channel_close_
 /// The item used to store the successful and erroneous callbacks in store.
 pub const CALLBACK_COUNTER: Item<CallbackCounter> = Item::new("callback_counter");
 
+/// The item used to store the callback given at instantiation.
+/// The callback contract is notified when the channel opens or closes.
+pub const CALLBACK: Item<Callback> = Item::new("callback");
+
+/// The item used to store the owner's request to decommission the outpost.
+/// The outpost is active as long as this is not set.
+pub const DECOMMISSION: Item<Decommission> = Item::new("decommission");
+
+/// The ICA packets whose acknowledgement the contract acts on, keyed by channel id and sequence.
+/// Entries are removed once the packet is acknowledged or times out.
+pub const PACKET_LEDGER: Map<(&str, u64), PacketKind> = Map::new("packet_ledger");
+
+/// The kinds of the tracked ICA packets being sent, in the order they were sent, until their sequence
+/// is known in the reply.
+pub const PENDING_PACKETS: Deque<PacketKind> = Deque::new("pending_packets");
+
+/// The ICS-20 channel the ICA is funded over with `FundIca`.
+pub const TRANSFER_CHANNEL: Item<String> = Item::new("transfer_channel");
+
+/// The storage purchases made with `BuyStorage`, keyed by purchase id.
+pub const STORAGE_PURCHASES: Map<u64, StoragePurchase> = Map::new("storage_purchases");
+
+/// The number of storage purchases made, which is also the id of the next one.
+pub const STORAGE_PURCHASE_COUNT: Item<u64> = Item::new("storage_purchase_count");
+
+/// The transfers of filetree files to another outpost, keyed by file path.
+pub const FILE_TRANSFERS: Map<&str, FileTransfer> = Map::new("file_transfers");
+
+/// The latest answers to host queries, keyed by [`HostQuery::key`].
+pub const HOST_QUERY_RESULTS: Map<&str, HostQueryResult> = Map::new("host_query_results");
+
+/// The cached ICA host params of the host, see [`crate::types::host_params`].
+pub const HOST_PARAMS: Item<HostParams> = Item::new("host_params");
+
+/// The authz grants issued by the ICA, keyed by grantee and message type url.
+pub const AUTHZ_GRANTS: Map<(&str, &str), AuthzGrant> = Map::new("authz_grants");
+
+/// The feegrant allowances issued by the ICA, keyed by grantee.
+pub const FEE_ALLOWANCES: Map<&str, FeeAllowance> = Map::new("fee_allowances");
+
 mod contract {
     use crate::ibc::types::metadata::TxEncoding;
 
@@ -217,3 +262,73 @@ mod channel {
         }
     }
 }
+
+mod packets {
+    use super::*;
+
+    /// Why the contract tracks an ICA packet.
+    #[cw_serde]
+    pub enum PacketKind {
+        /// The cleanup packet of [`Decommission`].
+        Decommission,
+        /// A batch of [`FileTransfer`]s.
+        FileTransfer {
+            /// The paths of the files transferred by this packet
+            paths: Vec<String>,
+        },
+        /// The transfers withdrawing the ICA's funds back to this chain.
+        Withdrawal {
+            /// The address receiving the funds on this chain
+            receiver: String,
+            /// The withdrawn coins
+            amount: Vec<Coin>,
+            /// The storage purchase the withdrawal refunds, if any
+            #[serde(default, skip_serializing_if = "Option::is_none")]
+            purchase_id: Option<u64>,
+        },
+        /// An ICS-20 transfer funding the ICA, tracked on the transfer channel.
+        FundIca {
+            /// Who sent the funds, and gets them back if the transfer fails
+            sender: String,
+            /// The transferred coin
+            amount: Coin,
+        },
+        /// The ICS-20 transfer paying for a [`StoragePurchase`], tracked on the transfer channel.
+        PurchaseFunding {
+            /// The id of the purchase
+            id: u64,
+        },
+        /// The ICA packet buying the storage of a [`StoragePurchase`].
+        StoragePurchase {
+            /// The id of the purchase
+            id: u64,
+        },
+        /// The `MsgModuleQuerySafe` answering [`HostQuery`]s.
+        HostQuery {
+            /// The queries, in the order they were sent
+            queries: Vec<HostQuery>,
+            /// The contract the answers are pushed to
+            callback: Option<String>,
+        },
+        /// The authz or feegrant messages changing the ICA's grants.
+        Grant {
+            /// The change recorded once the packet is acknowledged
+            change: GrantChange,
+        },
+    }
+
+    impl PacketKind {
+        /// Returns true if the packet is an ICS-20 transfer sent from this chain, which is tracked on the
+        /// transfer channel rather than the ICA channel.
+        pub fn is_transfer(&self) -> bool {
+            matches!(self, Self::FundIca { .. } | Self::PurchaseFunding { .. })
+        }
+
+        /// Queues the packet's kind and returns the message sending it, so its sequence is recorded in the
+        /// packet ledger once it's sent. Packets sent in the same call are recorded in order.
+        pub fn track(self, storage: &mut dyn Storage, msg: impl Into<CosmosMsg>) -> StdResult<SubMsg> {
+            PENDING_PACKETS.push_back(storage, &self)?;
+            Ok(SubMsg::reply_on_success(msg, SEND_PACKET_REPLY_ID))
+        }
+    }
+}
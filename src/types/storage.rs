@@ -0,0 +1,42 @@
+//! # storage
+//!
+//! Contains the transaction msgs needed to interact with canine-chain's storage module.
+//! documentation for the storage module can be found here:
+//! https://github.com/JackalLabs/canine-chain/tree/master/x/storage
+
+/// The type url of [`MsgBuyStorage`] on canine-chain.
+pub const MSG_BUY_STORAGE_TYPE_URL: &str = "/canine_chain.storage.MsgBuyStorage";
+
+/// Buy a storage plan for an account, paid by the creator
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgBuyStorage {
+    /// jkl address paying for the plan
+    #[prost(string, tag = "1")]
+    pub creator: ::prost::alloc::string::String,
+
+    /// jkl address the plan is bought for
+    #[prost(string, tag = "2")]
+    pub for_address: ::prost::alloc::string::String,
+
+    /// How long the plan lasts, at least 30 days
+    #[prost(int64, tag = "3")]
+    pub duration_days: i64,
+
+    /// How many bytes the plan provides
+    #[prost(int64, tag = "4")]
+    pub bytes: i64,
+
+    /// The denom the plan is paid in, e.g. "ujkl"
+    #[prost(string, tag = "5")]
+    pub payment_denom: ::prost::alloc::string::String,
+
+    /// Optional referral address
+    #[prost(string, tag = "6")]
+    pub referral: ::prost::alloc::string::String,
+}
+
+/// A successful broadcast guarantees that the plan was bought, so the response is empty
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgBuyStorageResponse {}
@@ -0,0 +1,199 @@
+//! # Storage purchase
+//!
+//! Takes a user from native funds on this chain to a storage plan on Jackal in one call.
+//! The funds are sent to the ICA over ICS-20 first. Once ibc-hooks reports that the transfer succeeded,
+//! the ICA buys the plan with `MsgBuyStorage`. If the transfer fails, the funds are refunded on this chain.
+//! If the purchase fails, the ICA sends the funds back to the buyer. A purchase packet that times out
+//! closes the channel, so its funds stay on the ICA until the owner withdraws them.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{BankMsg, Coin, DepsMut, Env, StdResult, Storage, SubMsg};
+use prost::Message;
+
+use crate::ibc::types::packet::IcaPacketData;
+
+use super::{
+    ica_funding,
+    state::{PacketKind, CHANNEL_STATE, STATE, STORAGE_PURCHASES, STORAGE_PURCHASE_COUNT},
+    storage::{MsgBuyStorage, MSG_BUY_STORAGE_TYPE_URL},
+    ContractError,
+};
+
+/// How far along the purchase is.
+#[cw_serde]
+pub enum PurchaseStatus {
+    /// The funds are on their way to the ICA.
+    Funding,
+    /// The funds arrived, the packet buying the plan was sent.
+    Buying,
+    /// The plan was bought.
+    Completed,
+    /// A leg of the purchase failed.
+    Failed {
+        /// Why the purchase failed
+        error: String,
+        /// Whether the funds were sent back to the buyer. If not, they are on the ICA, or on their way
+        /// back until the refund is acknowledged.
+        refunded: bool,
+    },
+}
+
+/// A storage plan bought with funds from this chain.
+#[cw_serde]
+pub struct StoragePurchase {
+    /// The id of the purchase.
+    pub id: u64,
+    /// Who paid, and gets the funds back if the purchase fails.
+    pub buyer: String,
+    /// The funds sent to the ICA.
+    pub amount: Coin,
+    /// The Jackal address the plan is bought for.
+    pub for_address: String,
+    /// How many bytes the plan provides.
+    pub bytes: u64,
+    /// How long the plan lasts.
+    pub duration_days: u64,
+    /// The denom the funds arrive in on Jackal, which pays for the plan.
+    pub payment_denom: String,
+    /// The optional referral address.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub referral: Option<String>,
+    /// The timeout in seconds of the purchase's packets.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+    /// How far along the purchase is.
+    pub status: PurchaseStatus,
+}
+
+impl PurchaseStatus {
+    /// Returns the status' name, as used in events.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Funding => "funding",
+            Self::Buying => "buying",
+            Self::Completed => "completed",
+            Self::Failed { .. } => "failed",
+        }
+    }
+}
+
+impl StoragePurchase {
+    /// Returns the `MsgBuyStorage` the ICA broadcasts to buy the plan.
+    pub fn buy_storage_msg(&self, ica_address: &str) -> Result<cosmos_sdk_proto::Any, ContractError> {
+        let msg = MsgBuyStorage {
+            creator: ica_address.to_string(),
+            for_address: self.for_address.clone(),
+            duration_days: i64::try_from(self.duration_days).map_err(|_| ContractError::InvalidStoragePlan {})?,
+            bytes: i64::try_from(self.bytes).map_err(|_| ContractError::InvalidStoragePlan {})?,
+            payment_denom: self.payment_denom.clone(),
+            referral: self.referral.clone().unwrap_or_default(),
+        };
+
+        Ok(cosmos_sdk_proto::Any {
+            type_url: MSG_BUY_STORAGE_TYPE_URL.to_string(),
+            value: msg.encode_to_vec(),
+        })
+    }
+
+    /// Returns the funds as they arrived on Jackal.
+    pub fn host_amount(&self) -> Coin {
+        Coin::new(self.amount.amount.u128(), self.payment_denom.clone())
+    }
+}
+
+/// Saves a new purchase under the next id and returns it.
+pub fn create(storage: &mut dyn Storage, mut purchase: StoragePurchase) -> StdResult<StoragePurchase> {
+    let id = STORAGE_PURCHASE_COUNT.may_load(storage)?.unwrap_or_default();
+    STORAGE_PURCHASE_COUNT.save(storage, &(id + 1))?;
+
+    purchase.id = id;
+    STORAGE_PURCHASES.save(storage, id, &purchase)?;
+    Ok(purchase)
+}
+
+/// Moves the purchase to the given status and returns it.
+pub fn set_status(storage: &mut dyn Storage, id: u64, status: PurchaseStatus) -> StdResult<StoragePurchase> {
+    STORAGE_PURCHASES.update(storage, id, |purchase| -> StdResult<_> {
+        let mut purchase = purchase.ok_or_else(|| cosmwasm_std::StdError::not_found("storage purchase"))?;
+        purchase.status = status;
+        Ok(purchase)
+    })
+}
+
+/// Moves the purchase on once its funding transfer completes: buys the plan if the funds arrived,
+/// or refunds the buyer if they didn't. Returns the message doing so, if any.
+pub fn on_funding_result(
+    deps: DepsMut,
+    env: &Env,
+    id: u64,
+    success: bool,
+) -> Result<(StoragePurchase, Option<SubMsg>), ContractError> {
+    let purchase = STORAGE_PURCHASES.load(deps.storage, id)?;
+    if !success {
+        let refund = BankMsg::Send {
+            to_address: purchase.buyer.clone(),
+            amount: vec![purchase.amount.clone()],
+        };
+        let status = PurchaseStatus::Failed {
+            error: "the transfer to the ICA failed".to_string(),
+            refunded: true,
+        };
+        return Ok((set_status(deps.storage, id, status)?, Some(SubMsg::new(refund))));
+    }
+
+    // The funds are on the ICA now, the owner can withdraw them if the plan can't be bought
+    let channel_open = CHANNEL_STATE
+        .may_load(deps.storage)?
+        .is_some_and(|channel_state| channel_state.is_open());
+    if !channel_open {
+        let status = PurchaseStatus::Failed {
+            error: ContractError::ChannelNotOpen {}.to_string(),
+            refunded: false,
+        };
+        return Ok((set_status(deps.storage, id, status)?, None));
+    }
+
+    let ica_info = STATE.load(deps.storage)?.get_ica_info()?;
+    let buy_storage_msg = purchase.buy_storage_msg(&ica_info.ica_address)?;
+    let ica_packet = IcaPacketData::from_proto_anys(vec![buy_storage_msg], None);
+    let send_packet_msg = ica_packet.to_ibc_msg(env, ica_info.channel_id, purchase.timeout_seconds)?;
+    let send_packet_msg = PacketKind::StoragePurchase { id }.track(deps.storage, send_packet_msg)?;
+
+    Ok((set_status(deps.storage, id, PurchaseStatus::Buying)?, Some(send_packet_msg)))
+}
+
+/// Marks the purchase as failed after the ICA couldn't buy the plan, and returns the packet sending the
+/// funds back to the buyer. If the packet can't be built, the funds are left on the ICA.
+pub fn on_purchase_error(
+    mut deps: DepsMut,
+    env: &Env,
+    id: u64,
+    error: String,
+) -> Result<Option<SubMsg>, ContractError> {
+    let purchase = STORAGE_PURCHASES.load(deps.storage, id)?;
+    let refund_msg = ica_funding::host_transfer_channel(deps.as_ref()).and_then(|host_channel_id| {
+        ica_funding::withdrawal_packet(
+            deps.branch(),
+            env,
+            &host_channel_id,
+            purchase.buyer.clone(),
+            vec![purchase.host_amount()],
+            Some(id),
+            purchase.timeout_seconds,
+        )
+    });
+
+    // The purchase is only refunded once the host acknowledges the refund, see `on_refund_sent`
+    let status = PurchaseStatus::Failed { error, refunded: false };
+    set_status(deps.storage, id, status)?;
+    Ok(refund_msg.ok())
+}
+
+/// Marks the purchase as refunded once the host acknowledged the withdrawal sending the funds back.
+pub fn on_refund_sent(storage: &mut dyn Storage, id: u64) -> StdResult<StoragePurchase> {
+    let purchase = STORAGE_PURCHASES.load(storage, id)?;
+    let PurchaseStatus::Failed { error, .. } = purchase.status else {
+        return Ok(purchase);
+    };
+    set_status(storage, id, PurchaseStatus::Failed { error, refunded: true })
+}